@@ -0,0 +1 @@
+module V2022 = Dota_v2022
@@ -0,0 +1,17 @@
+module Ability = Dota_v2022_ability
+module Affects_attribute = Dota_v2022_affects_attribute
+module Affinity = Dota_v2022_affinity
+module Affinity_proportionality = Dota_v2022_affinity_proportionality
+module Attribute = Dota_v2022_attribute
+module Attribute_effect = Dota_v2022_attribute_effect
+module Attribute_effect_kind = Dota_v2022_attribute_effect_kind
+module Cast_range = Dota_v2022_cast_range
+module Casting_type = Dota_v2022_casting_type
+module Damage_type = Dota_v2022_damage_type
+module Has_affinity_proportionality = Dota_v2022_has_affinity_proportionality
+module Has_affinity_with = Dota_v2022_has_affinity_with
+module Has_effect_kind = Dota_v2022_has_effect_kind
+module Hello = Dota_v2022_hello
+module Hero = Dota_v2022_hero
+module Movement_speed = Dota_v2022_movement_speed
+module Targetting_type = Dota_v2022_targetting_type
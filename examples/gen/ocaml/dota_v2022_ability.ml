@@ -0,0 +1,4 @@
+type t = { dota_v2022_is_constrained_by: Dota_v2022_attribute.t } 
+
+let make ~dota_v2022_is_constrained_by = { dota_v2022_is_constrained_by = dota_v2022_is_constrained_by }
+let dota_v2022_is_constrained_by t = t.dota_v2022_is_constrained_by
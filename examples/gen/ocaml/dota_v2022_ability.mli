@@ -1,2 +1,3 @@
-type t = { dota_v2022_isConstrainedBy: Dota_v2022_attribute.t } 
-
+type t
+val make : dota_v2022_is_constrained_by:Dota_v2022_attribute.t -> t
+val dota_v2022_is_constrained_by : t -> Dota_v2022_attribute.t
@@ -0,0 +1 @@
+module World = Dota_v2022_hello_world
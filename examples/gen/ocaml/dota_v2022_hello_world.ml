@@ -0,0 +1 @@
+module Name = Dota_v2022_hello_world_name
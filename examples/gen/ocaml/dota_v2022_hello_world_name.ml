@@ -0,0 +1,4 @@
+type t = { lore_v1_rel_as_string: string } 
+
+let make ~lore_v1_rel_as_string = { lore_v1_rel_as_string = lore_v1_rel_as_string }
+let lore_v1_rel_as_string t = t.lore_v1_rel_as_string
@@ -1,2 +1,3 @@
-type t = { lore_v1_rel_asString: Lore_v1_string.t } 
-
+type t
+val make : lore_v1_rel_as_string:string -> t
+val lore_v1_rel_as_string : t -> string
@@ -0,0 +1,4 @@
+type t = { dota_v2022_main_name: Dota_v2022_hello_world_name.t } 
+
+let make ~dota_v2022_main_name = { dota_v2022_main_name = dota_v2022_main_name }
+let dota_v2022_main_name t = t.dota_v2022_main_name
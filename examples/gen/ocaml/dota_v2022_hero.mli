@@ -1,2 +1,3 @@
-type t = { dota_v2022_mainName: Dota_v2022_name.t } 
-
+type t
+val make : dota_v2022_main_name:Dota_v2022_hello_world_name.t -> t
+val dota_v2022_main_name : t -> Dota_v2022_hello_world_name.t
@@ -2,10 +2,5 @@
 
 cargo build
 
-for target in ocaml erlang elixir graphql; do
-  ../target/debug/lore \
-    codegen \
-    --target $target \
-    --output-dir ./gen/$target \
-    *.lore
-done
+# every target is listed in lore.toml
+../target/debug/lore codegen
@@ -1,4 +1,5 @@
 use crate::uri::*;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Name {
@@ -6,9 +7,9 @@ pub struct Name {
     pub uri: URI,
 }
 
-impl ToString for Name {
-    fn to_string(&self) -> String {
-        self.uri.to_string()
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.uri)
     }
 }
 
@@ -43,16 +44,19 @@ impl Name {
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Literal {
     Number(u64),
+    /// A number with a fractional part, kept as it was written.
+    Float(String),
     String(String),
     Name(Name),
 }
 
-impl ToString for Literal {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Literal::Number(n) => n.to_string(),
-            Literal::String(s) => s.to_string(),
-            Literal::Name(n) => n.to_string(),
+            Literal::Number(n) => write!(f, "{}", n),
+            Literal::Float(n) => write!(f, "{}", n),
+            Literal::String(s) => write!(f, "{}", s),
+            Literal::Name(n) => write!(f, "{}", n),
         }
     }
 }
@@ -83,6 +87,33 @@ pub struct Relation {
     pub fields: Vec<Field>,
 }
 
+/// Where something was written, as a byte offset and length into its source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// Where the parts of a thing were written, so problems with its data can be pointed at.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThingSpans {
+    pub file: PathBuf,
+    pub name: Span,
+    pub kind: Span,
+    /// One span per field, in the same order as the fields.
+    pub fields: Vec<Span>,
+}
+
+/// An individual described by the ontology, like `thing AntiMage : Hero { movementSpeed 310 }`.
+/// Each field is a property of the thing, named by the predicate of a relation.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Thing {
+    pub name: Name,
+    pub kind: Name,
+    pub fields: Vec<Field>,
+    pub spans: ThingSpans,
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Structure {
     pub kinds: Vec<Kind>,
@@ -90,4 +121,6 @@ pub struct Structure {
     pub attributes: Vec<Attribute>,
 
     pub relations: Vec<Relation>,
+
+    pub things: Vec<Thing>,
 }
@@ -0,0 +1,211 @@
+use crate::ast::*;
+use crate::uri::*;
+
+/// How many objects a relation points to, read from the well-known fields of its block:
+///
+/// * `lore:v1/cardinality N` or `lore:v1/cardinality/exactly N`
+/// * `lore:v1/cardinality lore:v1/many`
+/// * `lore:v1/cardinality/min N` and `lore:v1/cardinality/max N` (or `lore:v1/many`)
+///
+/// A relation without any of them has exactly one object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cardinality {
+    pub min: u64,
+    pub max: Option<u64>,
+}
+
+/// The shape a cardinality takes in generated code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Multiplicity {
+    One,
+    Optional,
+    List,
+    NonEmptyList,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CardinalityError {
+    ExpectedNumber(Field),
+    MinGreaterThanMax { min: u64, max: u64 },
+    MaxIsZero,
+    Conflicting(Field),
+}
+
+impl std::fmt::Display for CardinalityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardinalityError::ExpectedNumber(field) => write!(
+                f,
+                "`{}` expects a number{}, but found `{}`",
+                field.name,
+                if field.name.uri == Cardinality::max_uri() || field.name.uri == Cardinality::uri()
+                {
+                    " or `lore:v1/many`"
+                } else {
+                    ""
+                },
+                field.value
+            ),
+            CardinalityError::MinGreaterThanMax { min, max } => {
+                write!(
+                    f,
+                    "the minimum ({}) is greater than the maximum ({})",
+                    min, max
+                )
+            }
+            CardinalityError::MaxIsZero => write!(f, "the maximum cannot be 0"),
+            CardinalityError::Conflicting(field) => write!(
+                f,
+                "`{}` conflicts with a cardinality set earlier in the same block",
+                field.name
+            ),
+        }
+    }
+}
+
+/// Written like UML multiplicities: `1`, `0..1`, `1..6`, `1..*`, or `*`.
+impl std::fmt::Display for Cardinality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (0, None) => write!(f, "*"),
+            (min, None) => write!(f, "{}..*", min),
+            (min, Some(max)) if min == max => write!(f, "{}", min),
+            (min, Some(max)) => write!(f, "{}..{}", min, max),
+        }
+    }
+}
+
+impl Default for Cardinality {
+    fn default() -> Cardinality {
+        Cardinality::exactly(1)
+    }
+}
+
+impl Cardinality {
+    pub fn exactly(n: u64) -> Cardinality {
+        Cardinality {
+            min: n,
+            max: Some(n),
+        }
+    }
+
+    pub fn many() -> Cardinality {
+        Cardinality { min: 0, max: None }
+    }
+
+    pub fn uri() -> URI {
+        URI::from_string("lore:v1/cardinality".to_string())
+    }
+
+    pub fn exactly_uri() -> URI {
+        Cardinality::uri().join("exactly")
+    }
+
+    pub fn min_uri() -> URI {
+        Cardinality::uri().join("min")
+    }
+
+    pub fn max_uri() -> URI {
+        Cardinality::uri().join("max")
+    }
+
+    pub fn many_uri() -> URI {
+        URI::from_string("lore:v1/many".to_string())
+    }
+
+    pub fn is_cardinality_field(field: &Field) -> bool {
+        [
+            Cardinality::uri(),
+            Cardinality::exactly_uri(),
+            Cardinality::min_uri(),
+            Cardinality::max_uri(),
+        ]
+        .contains(&field.name.uri)
+    }
+
+    pub fn from_fields(fields: &[Field]) -> Result<Cardinality, CardinalityError> {
+        let mut exact: Option<Cardinality> = None;
+        let mut min: Option<u64> = None;
+        let mut max: Option<Option<u64>> = None;
+
+        for field in fields
+            .iter()
+            .filter(|f| Cardinality::is_cardinality_field(f))
+        {
+            let uri = &field.name.uri;
+            let conflicts = if *uri == Cardinality::min_uri() {
+                exact.is_some() || min.is_some()
+            } else if *uri == Cardinality::max_uri() {
+                exact.is_some() || max.is_some()
+            } else {
+                exact.is_some() || min.is_some() || max.is_some()
+            };
+            if conflicts {
+                return Err(CardinalityError::Conflicting(field.clone()));
+            }
+
+            if *uri == Cardinality::min_uri() {
+                min = Some(Cardinality::number(field)?);
+            } else if *uri == Cardinality::max_uri() {
+                max = Some(Cardinality::number_or_many(field)?);
+            } else if *uri == Cardinality::exactly_uri() {
+                exact = Some(Cardinality::exactly(Cardinality::number(field)?));
+            } else {
+                exact = Some(match Cardinality::number_or_many(field)? {
+                    Some(n) => Cardinality::exactly(n),
+                    None => Cardinality::many(),
+                });
+            }
+        }
+
+        let cardinality = match exact {
+            Some(cardinality) => cardinality,
+            None if min.is_none() && max.is_none() => Cardinality::default(),
+            None => Cardinality {
+                min: min.unwrap_or(0),
+                max: max.unwrap_or(None),
+            },
+        };
+
+        match cardinality.max {
+            Some(0) => Err(CardinalityError::MaxIsZero),
+            Some(max) if cardinality.min > max => Err(CardinalityError::MinGreaterThanMax {
+                min: cardinality.min,
+                max,
+            }),
+            _ => Ok(cardinality),
+        }
+    }
+
+    fn number(field: &Field) -> Result<u64, CardinalityError> {
+        match field.value {
+            Literal::Number(n) => Ok(n),
+            _ => Err(CardinalityError::ExpectedNumber(field.clone())),
+        }
+    }
+
+    fn number_or_many(field: &Field) -> Result<Option<u64>, CardinalityError> {
+        match &field.value {
+            Literal::Number(n) => Ok(Some(*n)),
+            Literal::Name(name) if name.uri == Cardinality::many_uri() => Ok(None),
+            _ => Err(CardinalityError::ExpectedNumber(field.clone())),
+        }
+    }
+
+    pub fn multiplicity(&self) -> Multiplicity {
+        match (self.min, self.max) {
+            (1, Some(1)) => Multiplicity::One,
+            (0, Some(1)) => Multiplicity::Optional,
+            (0, _) => Multiplicity::List,
+            (_, _) => Multiplicity::NonEmptyList,
+        }
+    }
+}
+
+impl Relation {
+    /// The cardinality of this relation. Invalid cardinalities are rejected by the validator, so
+    /// here they fall back to exactly one.
+    pub fn cardinality(&self) -> Cardinality {
+        Cardinality::from_fields(&self.fields).unwrap_or_default()
+    }
+}
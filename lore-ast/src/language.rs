@@ -0,0 +1,119 @@
+use crate::ast::*;
+use crate::uri::*;
+
+/// Fields that hold human-readable text in some language, written as `lore:v1/doc/en` or
+/// `lore:v1/label/es-AR`. A field without a language tag (`lore:v1/doc`) is also accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Localized {
+    Doc,
+    Label,
+}
+
+impl Localized {
+    pub fn all() -> Vec<Localized> {
+        vec![Localized::Doc, Localized::Label]
+    }
+
+    pub fn to_uri(&self) -> URI {
+        match self {
+            Localized::Doc => URI::from_string("lore:v1/doc".to_string()),
+            Localized::Label => URI::from_string("lore:v1/label".to_string()),
+        }
+    }
+
+    /// The language tag of a field of this kind, or `None` if the field is of another kind.
+    /// Untagged fields have an empty tag.
+    pub fn language_of(&self, field: &Field) -> Option<String> {
+        let uri = field.name.uri.to_string();
+        let base = self.to_uri().to_string();
+        if uri == base {
+            return Some("".to_string());
+        }
+        uri.strip_prefix(&format!("{}/", base))
+            .map(|tag| tag.to_string())
+    }
+
+    /// Every `(language, text)` pair of this kind in the fields, in the order they were written.
+    pub fn texts(&self, fields: &[Field]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .filter_map(|field| {
+                self.language_of(field)
+                    .map(|lang| (lang, field.value.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Whether a language tag is well-formed according to BCP 47 (RFC 5646). This only checks the
+/// syntax, not whether the subtags are registered.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    let parts: Vec<&str> = tag.split('-').collect();
+    if parts
+        .iter()
+        .any(|p| p.is_empty() || p.len() > 8 || !p.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return false;
+    }
+
+    let alpha = |p: &str| p.chars().all(|c| c.is_ascii_alphabetic());
+    let digit = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+    let private_use = |rest: &[&str]| !rest.is_empty();
+
+    if parts[0].eq_ignore_ascii_case("x") {
+        return private_use(&parts[1..]);
+    }
+
+    // language, with up to three extended language subtags after a short one
+    let language = parts[0];
+    if !alpha(language) || language.len() < 2 || language.len() == 4 {
+        return false;
+    }
+    let mut i = 1;
+    if language.len() <= 3 {
+        let mut extlangs = 0;
+        while i < parts.len() && extlangs < 3 && parts[i].len() == 3 && alpha(parts[i]) {
+            i += 1;
+            extlangs += 1;
+        }
+    }
+
+    // script
+    if i < parts.len() && parts[i].len() == 4 && alpha(parts[i]) {
+        i += 1;
+    }
+
+    // region
+    if i < parts.len()
+        && ((parts[i].len() == 2 && alpha(parts[i])) || (parts[i].len() == 3 && digit(parts[i])))
+    {
+        i += 1;
+    }
+
+    // variants
+    while i < parts.len()
+        && (parts[i].len() >= 5
+            || (parts[i].len() == 4 && parts[i].starts_with(|c: char| c.is_ascii_digit())))
+    {
+        i += 1;
+    }
+
+    // extensions
+    while i < parts.len() && parts[i].len() == 1 && !parts[i].eq_ignore_ascii_case("x") {
+        let start = i;
+        i += 1;
+        while i < parts.len() && parts[i].len() >= 2 {
+            i += 1;
+        }
+        if i == start + 1 {
+            return false;
+        }
+    }
+
+    // private use
+    if i < parts.len() && parts[i].eq_ignore_ascii_case("x") {
+        return private_use(&parts[i + 1..]);
+    }
+
+    i == parts.len()
+}
@@ -1,5 +1,13 @@
 pub mod ast;
+pub mod cardinality;
+pub mod language;
+pub mod primitive;
+pub mod superkind;
 pub mod uri;
+pub mod variant;
 
 pub use ast::*;
+pub use cardinality::*;
+pub use language::*;
+pub use primitive::*;
 pub use uri::*;
@@ -0,0 +1,75 @@
+use crate::uri::*;
+
+/// The built-in vocabulary of primitive types, available to every ontology as `lore:v1/<Name>`
+/// (usually written `@lore/String` after `prefix lore:v1 as @lore`).
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Primitive {
+    String,
+    Int,
+    Float,
+    Bool,
+    Date,
+    DateTime,
+    URI,
+}
+
+impl Primitive {
+    pub fn namespace() -> URI {
+        URI::from_string("lore:v1".to_string())
+    }
+
+    pub fn all() -> Vec<Primitive> {
+        vec![
+            Primitive::String,
+            Primitive::Int,
+            Primitive::Float,
+            Primitive::Bool,
+            Primitive::Date,
+            Primitive::DateTime,
+            Primitive::URI,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::String => "String",
+            Primitive::Int => "Int",
+            Primitive::Float => "Float",
+            Primitive::Bool => "Bool",
+            Primitive::Date => "Date",
+            Primitive::DateTime => "DateTime",
+            Primitive::URI => "URI",
+        }
+    }
+
+    pub fn to_uri(&self) -> URI {
+        Primitive::namespace().join(self.name())
+    }
+
+    pub fn from_uri(uri: &URI) -> Option<Primitive> {
+        Primitive::all().into_iter().find(|p| &p.to_uri() == uri)
+    }
+
+    /// Whether a URI lives where primitives do: directly under `lore:v1/` and capitalized, like
+    /// `lore:v1/String`. Used to catch typos such as `@lore/Strnig`.
+    pub fn is_primitive_like(uri: &URI) -> bool {
+        let namespace = format!("{}/", Primitive::namespace());
+        match uri.to_string().strip_prefix(&namespace) {
+            Some(name) => !name.contains('/') && name.starts_with(|c: char| c.is_ascii_uppercase()),
+            None => false,
+        }
+    }
+
+    /// The XML Schema datatype this primitive corresponds to in the RDF graph.
+    pub fn xsd_datatype(&self) -> &'static str {
+        match self {
+            Primitive::String => "http://www.w3.org/2001/XMLSchema#string",
+            Primitive::Int => "http://www.w3.org/2001/XMLSchema#integer",
+            Primitive::Float => "http://www.w3.org/2001/XMLSchema#double",
+            Primitive::Bool => "http://www.w3.org/2001/XMLSchema#boolean",
+            Primitive::Date => "http://www.w3.org/2001/XMLSchema#date",
+            Primitive::DateTime => "http://www.w3.org/2001/XMLSchema#dateTime",
+            Primitive::URI => "http://www.w3.org/2001/XMLSchema#anyURI",
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::ast::*;
+use crate::uri::*;
+
+/// Kinds whose things are also things of other kinds, listed in the block of the kind:
+///
+/// ```lore
+/// kind Hero {
+///   lore:v1/superkind Unit
+/// }
+/// ```
+///
+/// Every relation of `Unit` is then a relation of `Hero` too.
+impl Kind {
+    pub fn superkind_uri() -> URI {
+        URI::from_string("lore:v1/superkind".to_string())
+    }
+
+    pub fn is_superkind_field(field: &Field) -> bool {
+        field.name.uri == Kind::superkind_uri()
+    }
+
+    /// The direct superkinds of this kind in the order they were written. Superkinds that are
+    /// not names are rejected by the validator, so here they are skipped.
+    pub fn superkinds(&self) -> Vec<&Name> {
+        self.fields
+            .iter()
+            .filter(|field| Kind::is_superkind_field(field))
+            .filter_map(|field| match &field.value {
+                Literal::Name(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+}
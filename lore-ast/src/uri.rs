@@ -1,9 +1,9 @@
 #[derive(Clone, Debug, Hash, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct URI(String);
 
-impl ToString for URI {
-    fn to_string(&self) -> String {
-        self.0.clone()
+impl std::fmt::Display for URI {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
@@ -31,4 +31,24 @@ impl URI {
     pub fn expand_prefix(&self, prefix: &str, expanded: &URI) -> URI {
         URI(self.0.replace(prefix, &expanded.to_string()))
     }
+
+    /// The last segment of a URI: `dota:v2022/hello/Hero` is `Hero`.
+    pub fn local_name(&self) -> String {
+        self.0
+            .rsplit(['/', ':'])
+            .find(|segment| !segment.is_empty())
+            .unwrap_or(&self.0)
+            .to_string()
+    }
+
+    /// Everything in a URI before its last segment: `dota:v2022/hello/Hero` is in
+    /// `dota:v2022/hello`.
+    pub fn namespace(&self) -> URI {
+        let namespace = self
+            .0
+            .strip_suffix(&self.local_name())
+            .unwrap_or("")
+            .trim_end_matches(['/', ':']);
+        URI(namespace.to_string())
+    }
 }
@@ -0,0 +1,38 @@
+use crate::ast::*;
+use crate::uri::*;
+
+/// Kinds that are exactly one of a fixed set of names, listed in the block of the kind:
+///
+/// ```lore
+/// kind PrimaryAttribute {
+///   lore:v1/variant Strength
+///   lore:v1/variant Agility
+///   lore:v1/variant Intelligence
+/// }
+/// ```
+impl Kind {
+    pub fn variant_uri() -> URI {
+        URI::from_string("lore:v1/variant".to_string())
+    }
+
+    pub fn is_variant_field(field: &Field) -> bool {
+        field.name.uri == Kind::variant_uri()
+    }
+
+    /// The variants of this kind in the order they were written. Variants that are not names are
+    /// rejected by the validator, so here they are skipped.
+    pub fn variants(&self) -> Vec<&Name> {
+        self.fields
+            .iter()
+            .filter(|field| Kind::is_variant_field(field))
+            .filter_map(|field| match &field.value {
+                Literal::Name(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn is_enumeration(&self) -> bool {
+        !self.variants().is_empty()
+    }
+}
@@ -1,7 +1,9 @@
-use miette::Result;
+use miette::{IntoDiagnostic, Result};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+mod watch;
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "lore", about = "a little language to capture reality")]
 struct Args {
@@ -15,17 +17,29 @@ enum TargetLang {
     GraphQL,
     Erlang,
     Elixir,
+    ReScript,
+    JsonSchema,
+    Protobuf,
+    Sql,
+    Markdown,
+    Shacl,
 }
 
-impl ToString for TargetLang {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for TargetLang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
             TargetLang::OCaml => "ocaml",
             TargetLang::GraphQL => "graphql",
             TargetLang::Erlang => "erlang",
             TargetLang::Elixir => "elixir",
-        }
-        .to_string()
+            TargetLang::ReScript => "rescript",
+            TargetLang::JsonSchema => "jsonschema",
+            TargetLang::Protobuf => "protobuf",
+            TargetLang::Sql => "sql",
+            TargetLang::Markdown => "markdown",
+            TargetLang::Shacl => "shacl",
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -38,7 +52,13 @@ impl std::str::FromStr for TargetLang {
             "erlang" => Ok(TargetLang::Erlang),
             "elixir" => Ok(TargetLang::Elixir),
             "graphql" => Ok(TargetLang::GraphQL),
-            _ => Err(format!("Could not find target: {}. Try one of: ocaml | elixir | erlang | graphql | rescript", s)),
+            "rescript" => Ok(TargetLang::ReScript),
+            "jsonschema" => Ok(TargetLang::JsonSchema),
+            "protobuf" => Ok(TargetLang::Protobuf),
+            "sql" => Ok(TargetLang::Sql),
+            "markdown" => Ok(TargetLang::Markdown),
+            "shacl" => Ok(TargetLang::Shacl),
+            _ => Err(format!("Could not find target: {}. Try one of: ocaml | elixir | erlang | graphql | rescript | jsonschema | protobuf | sql | markdown | shacl", s)),
         }
     }
 }
@@ -54,6 +74,12 @@ enum Command {
             help = "prints the AST of the file on successful parsing"
         )]
         dump_ast: bool,
+
+        #[structopt(
+            long = "watch",
+            help = "re-run whenever the inputs change, printing only new diagnostics"
+        )]
+        watch: bool,
     },
 
     Query {
@@ -64,18 +90,210 @@ enum Command {
         query: String,
     },
 
+    Graph {
+        #[structopt(
+            name = "INPUTS",
+            help = "source .lore files or directories to read, or the sources in lore.toml",
+            parse(from_os_str)
+        )]
+        inputs: Vec<PathBuf>,
+
+        #[structopt(
+            long = "format",
+            name = "FORMAT",
+            help = "the diagram format: dot | mermaid",
+            default_value = "dot"
+        )]
+        format: lore_codegen::DiagramFormat,
+
+        #[structopt(
+            short = "o",
+            long = "output",
+            name = "OUTPUT",
+            help = "write the diagram to this file instead of stdout",
+            parse(from_os_str)
+        )]
+        output: Option<PathBuf>,
+
+        #[structopt(
+            long = "namespace",
+            name = "NAMESPACE",
+            help = "only draw names in this namespace, like dota:v2022"
+        )]
+        namespace: Option<String>,
+
+        #[structopt(
+            long = "around",
+            name = "NAME",
+            help = "only draw the names related to this one, given as a URI or its last segment"
+        )]
+        around: Option<String>,
+
+        #[structopt(
+            long = "depth",
+            name = "DEPTH",
+            help = "how many relations away from --around to keep drawing",
+            default_value = "1"
+        )]
+        depth: usize,
+    },
+
+    #[structopt(name = "shacl-check")]
+    ShaclCheck {
+        #[structopt(
+            name = "DATA",
+            help = "the RDF data to check, as Turtle",
+            parse(from_os_str)
+        )]
+        data: PathBuf,
+
+        #[structopt(
+            name = "INPUTS",
+            help = "source .lore files to derive the shapes from",
+            parse(from_os_str)
+        )]
+        inputs: Vec<PathBuf>,
+    },
+
+    Load {
+        #[structopt(
+            name = "DATA",
+            help = "the things to load, as a .csv or .json file",
+            parse(from_os_str)
+        )]
+        data: PathBuf,
+
+        #[structopt(
+            name = "INPUTS",
+            help = "source .lore files declaring the kinds of the things",
+            parse(from_os_str)
+        )]
+        inputs: Vec<PathBuf>,
+
+        #[structopt(
+            long = "kind",
+            name = "KIND",
+            help = "the kind of every thing, as a URI or its last segment"
+        )]
+        kind: Option<String>,
+
+        #[structopt(
+            long = "mapping",
+            name = "MAPPING",
+            help = "a JSON file mapping columns to relations",
+            parse(from_os_str)
+        )]
+        mapping: Option<PathBuf>,
+
+        #[structopt(long = "id", name = "ID", help = "the column that names each thing")]
+        id: Option<String>,
+
+        #[structopt(
+            long = "namespace",
+            name = "NAMESPACE",
+            help = "name the things in this namespace instead of the one of their kind"
+        )]
+        namespace: Option<String>,
+
+        #[structopt(
+            short = "o",
+            long = "output",
+            name = "OUTPUT",
+            help = "write the things as a .lore file",
+            parse(from_os_str)
+        )]
+        output: Option<PathBuf>,
+    },
+
+    Lsp,
+
+    Lock,
+
+    Diff {
+        #[structopt(
+            name = "OLD",
+            help = "the old version: a .lore file, or a directory with or without a lore.toml",
+            parse(from_os_str)
+        )]
+        old: PathBuf,
+
+        #[structopt(
+            name = "NEW",
+            help = "the new version to compare it to",
+            parse(from_os_str)
+        )]
+        new: PathBuf,
+
+        #[structopt(
+            long = "fail-on-breaking",
+            help = "fail if any change breaks code generated from the old version"
+        )]
+        fail_on_breaking: bool,
+    },
+
+    Fmt {
+        #[structopt(
+            name = "INPUTS",
+            help = "source .lore files or directories to format in place, or the sources in lore.toml",
+            parse(from_os_str)
+        )]
+        inputs: Vec<PathBuf>,
+
+        #[structopt(
+            long = "check",
+            help = "only list the files that are not formatted, and fail if there are any"
+        )]
+        check: bool,
+    },
+
+    Doc {
+        #[structopt(
+            name = "INPUTS",
+            help = "source .lore files or directories to read, or the sources in lore.toml",
+            parse(from_os_str)
+        )]
+        inputs: Vec<PathBuf>,
+
+        #[structopt(
+            short = "o",
+            long = "output-dir",
+            name = "OUTPUT_DIR",
+            help = "the output directory for the generated site",
+            default_value = "./doc",
+            parse(from_os_str)
+        )]
+        output_dir: PathBuf,
+
+        #[structopt(
+            long = "title",
+            name = "TITLE",
+            help = "the title of the site",
+            default_value = "Ontology"
+        )]
+        title: String,
+
+        #[structopt(
+            long = "lang",
+            name = "LANGS",
+            help = "preferred languages for summaries, as comma-separated BCP 47 tags",
+            default_value = "en",
+            use_delimiter = true
+        )]
+        languages: Vec<String>,
+    },
+
     Codegen {
         #[structopt(
             short = "",
             long = "target",
             name = "TARGET",
-            help = "the target language to generate sources in"
+            help = "the target language to generate sources in, or every target in lore.toml"
         )]
-        target: TargetLang,
+        target: Option<TargetLang>,
 
         #[structopt(
             name = "INPUTS",
-            help = "source .lore files to read",
+            help = "source .lore files or directories to read, or the sources in lore.toml",
             parse(from_os_str)
         )]
         inputs: Vec<PathBuf>,
@@ -89,32 +307,294 @@ enum Command {
             parse(from_os_str)
         )]
         output_dir: PathBuf,
+
+        #[structopt(
+            long = "json",
+            help = "also generate JSON (de)serializers for every type (ocaml only)"
+        )]
+        json: bool,
+
+        #[structopt(
+            long = "strip-namespace",
+            help = "name generated types and fields after the last segment of their URI only"
+        )]
+        strip_namespace: bool,
+
+        #[structopt(
+            long = "lang",
+            name = "LANGS",
+            help = "preferred languages for docs and labels, as comma-separated BCP 47 tags",
+            default_value = "en",
+            use_delimiter = true
+        )]
+        languages: Vec<String>,
+
+        #[structopt(
+            long = "all-langs",
+            help = "include docs and labels in every language they are written in"
+        )]
+        all_languages: bool,
+
+        #[structopt(
+            long = "per-kind",
+            help = "write one schema file per type instead of a single bundle (jsonschema only)"
+        )]
+        per_kind: bool,
+
+        #[structopt(
+            long = "proto-lock",
+            name = "PROTO_LOCK",
+            help = "the lockfile that keeps field numbers stable across runs (protobuf only)",
+            default_value = "./proto.lock",
+            parse(from_os_str)
+        )]
+        proto_lock: PathBuf,
+
+        #[structopt(
+            long = "sql-dialect",
+            name = "DIALECT",
+            help = "the SQL dialect to generate: postgres | sqlite (sql only)",
+            default_value = "postgres"
+        )]
+        sql_dialect: lore_codegen::SqlDialect,
+
+        #[structopt(
+            long = "watch",
+            help = "re-run whenever the inputs change, printing only new diagnostics"
+        )]
+        watch: bool,
     },
 }
 
+/// One target to generate sources for, from the flags or from a `[codegen.<target>]` table.
+struct Generator {
+    target: TargetLang,
+    output_dir: PathBuf,
+    json: bool,
+    strip_namespace: bool,
+    docs: lore_codegen::Docs,
+    per_kind: bool,
+    proto_lock: PathBuf,
+    sql_dialect: lore_codegen::SqlDialect,
+}
+
+impl Generator {
+    fn from_manifest(
+        manifest: &lore_store::Manifest,
+        target: &lore_store::Target,
+    ) -> Result<Generator> {
+        let invalid = |reason: String| lore_store::StoreError::InvalidManifest {
+            path: manifest.root.join(lore_store::MANIFEST_FILE),
+            reason,
+        };
+        Ok(Generator {
+            target: target.target.parse().map_err(invalid)?,
+            output_dir: target.output_dir.clone(),
+            json: target.json,
+            strip_namespace: target.strip_namespace,
+            docs: lore_codegen::Docs::new()
+                .with_languages(target.languages.clone())
+                .with_all_languages(target.all_languages),
+            per_kind: target.per_kind,
+            proto_lock: target.proto_lock.clone(),
+            sql_dialect: target.sql_dialect.parse().map_err(invalid)?,
+        })
+    }
+
+    fn generate(&self, store: &lore_store::Store) -> Result<()> {
+        // written only once the sources are, so a failed run does not lock in its numbers
+        let mut proto_lock = None;
+        let source_set = match &self.target {
+            TargetLang::OCaml => {
+                let emitter = lore_codegen::OCamlEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::ocaml().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone())
+                    .with_json(self.json);
+                emitter.translate(store)?
+            }
+            TargetLang::ReScript => {
+                let emitter = lore_codegen::ReScriptEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::rescript().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone());
+                emitter.translate(store)?
+            }
+            TargetLang::GraphQL => {
+                let emitter = lore_codegen::GraphQLEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::graphql().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone());
+                emitter.translate(store)?
+            }
+            TargetLang::Erlang => {
+                let emitter = lore_codegen::ErlangEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::erlang().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone());
+                emitter.translate(store)?
+            }
+            TargetLang::Elixir => {
+                let emitter = lore_codegen::ElixirEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::elixir().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone());
+                emitter.translate(store)?
+            }
+            TargetLang::JsonSchema => {
+                let emitter = lore_codegen::JsonSchemaEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::json_schema()
+                            .with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone())
+                    .with_bundle(!self.per_kind);
+                emitter.translate(store)?
+            }
+            TargetLang::Protobuf => {
+                let field_numbers = if self.proto_lock.exists() {
+                    let json = std::fs::read_to_string(&self.proto_lock).into_diagnostic()?;
+                    lore_codegen::FieldNumbers::from_json(&json)?
+                } else {
+                    lore_codegen::FieldNumbers::new()
+                };
+                // packages already carry the namespace, so names are always stripped
+                let emitter = lore_codegen::ProtobufEmitter::new()
+                    .with_docs(self.docs.clone())
+                    .with_field_numbers(field_numbers);
+                let source_set = emitter.translate(store)?;
+                let lock = emitter.field_numbers(store)?;
+                proto_lock = Some(lore_codegen::Source::new(
+                    self.proto_lock.clone(),
+                    lock.to_json(),
+                ));
+                source_set
+            }
+            TargetLang::Sql => {
+                let emitter = lore_codegen::SqlEmitter::new()
+                    .with_naming(
+                        lore_codegen::Naming::sql().with_strip_namespace(self.strip_namespace),
+                    )
+                    .with_docs(self.docs.clone())
+                    .with_dialect(self.sql_dialect);
+                emitter.translate(store)?
+            }
+            TargetLang::Markdown => {
+                let emitter = lore_codegen::MarkdownEmitter::new().with_docs(self.docs.clone());
+                emitter.translate(store)?
+            }
+            TargetLang::Shacl => lore_codegen::ShaclEmitter::new().translate(store)?,
+        };
+
+        for source in source_set.sources() {
+            source.write(&self.output_dir)?;
+        }
+        if let Some(lock) = proto_lock {
+            lock.write(std::path::Path::new(""))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The manifest of the project we are in, if there is one.
+fn manifest() -> Result<Option<lore_store::Manifest>> {
+    let dir = std::env::current_dir().into_diagnostic()?;
+    Ok(lore_store::Manifest::find(&dir)?)
+}
+
+fn manifest_not_found() -> lore_store::StoreError {
+    lore_store::StoreError::ManifestNotFound {
+        dir: std::env::current_dir().unwrap_or_default(),
+    }
+}
+
+/// The files and directories to read, which are the sources in the manifest when no inputs are
+/// given.
+fn sources(inputs: Vec<PathBuf>, manifest: &Option<lore_store::Manifest>) -> Result<Vec<PathBuf>> {
+    if !inputs.is_empty() {
+        return Ok(inputs);
+    }
+    match manifest {
+        Some(manifest) => Ok(manifest.sources.clone()),
+        None => Err(manifest_not_found().into()),
+    }
+}
+
+/// The files and directories to read, and the store to read them into, which can import the
+/// packages of the project.
+fn project(
+    inputs: Vec<PathBuf>,
+    manifest: &Option<lore_store::Manifest>,
+) -> Result<(Vec<PathBuf>, lore_store::Store)> {
+    let paths = sources(inputs, manifest)?;
+    let mut store = lore_store::Store::new();
+    if let Some(manifest) = manifest {
+        store = store.with_packages(manifest.packages()?);
+        if let Some(namespace) = manifest.namespace.clone() {
+            store = store.with_namespace(namespace);
+        }
+    }
+    Ok((paths, store))
+}
+
+/// Reads every `.lore` file in the inputs, or in the project when there are none.
+fn load(inputs: Vec<PathBuf>) -> Result<lore_store::Store> {
+    let (paths, mut store) = project(inputs, &manifest()?)?;
+    for file in lore_store::lore_files(&paths) {
+        store.add_file(file)?;
+    }
+    Ok(store)
+}
+
+/// Reads one version of an ontology, as the project in `path` when it has a lore.toml.
+fn version(path: PathBuf) -> Result<lore_store::Store> {
+    let manifest_file = path.join(lore_store::MANIFEST_FILE);
+    let (paths, mut store) = if manifest_file.is_file() {
+        project(vec![], &Some(lore_store::Manifest::read(&manifest_file)?))?
+    } else {
+        project(vec![path], &None)?
+    };
+    for file in lore_store::lore_files(&paths) {
+        store.add_file(file)?;
+    }
+    Ok(store)
+}
+
 impl Command {
     pub fn run(self) -> Result<()> {
         match self {
-            Command::Validate { inputs, dump_ast } => {
-                for input in inputs {
-                    let mut parser = lore_parser::Parser::for_file(input.clone())?;
-                    let validator = lore_parser::Validator::new();
-                    let result = validator.validate(parser.parse()?)?;
-                    if dump_ast {
-                        dbg!(result);
-                    }
+            Command::Validate {
+                inputs,
+                dump_ast,
+                watch,
+            } => {
+                let (paths, mut store) = project(inputs, &manifest()?)?;
+                if watch {
+                    return watch::watch(&paths, store, |store| Ok(store.check_things()?));
+                }
+                if dump_ast {
+                    store = store.with_inspector(|_, ast| {
+                        dbg!(&ast);
+                    });
                 }
+
+                for file in lore_store::lore_files(&paths) {
+                    store.add_file(file)?;
+                }
+                // things can only be checked once every kind they use is known
+                store.check_things()?;
+
                 Ok(())
             }
 
             Command::Query { inputs, query } => {
-                let mut store = lore_store::Store::new();
-                for input in inputs {
-                    let mut parser = lore_parser::Parser::for_file(input.clone())?;
-                    let validator = lore_parser::Validator::new();
-                    let ast = validator.validate(parser.parse()?)?;
-                    store.add_tree(ast)?;
-                }
+                let store = load(inputs)?;
 
                 println!("QUERY: {}", &query);
 
@@ -125,33 +605,225 @@ impl Command {
                 Ok(())
             }
 
-            Command::Codegen {
+            Command::Graph {
                 inputs,
-                target,
-                output_dir,
+                format,
+                output,
+                namespace,
+                around,
+                depth,
             } => {
-                let mut store = lore_store::Store::new();
-                for input in inputs {
-                    let mut parser = lore_parser::Parser::for_file(input.clone())?;
-                    let validator = lore_parser::Validator::new();
-                    let ast = validator.validate(parser.parse()?)?;
-                    store.add_tree(ast)?;
+                let store = load(inputs)?;
+
+                let mut emitter = lore_codegen::DiagramEmitter::new()
+                    .with_format(format)
+                    .with_depth(depth);
+                if let Some(namespace) = namespace {
+                    emitter = emitter.with_namespace(namespace);
+                }
+                if let Some(around) = around {
+                    emitter = emitter.with_focus(around);
+                }
+
+                let diagram = emitter.render(&store)?;
+                match output {
+                    Some(output) => std::fs::write(output, diagram).into_diagnostic()?,
+                    None => print!("{}", diagram),
+                }
+
+                Ok(())
+            }
+
+            Command::ShaclCheck { data, inputs } => {
+                let store = load(inputs)?;
+
+                let graph = lore_store::Store::load_turtle(&data)?;
+                let violations = store.shapes().validate(&graph);
+                for violation in &violations {
+                    println!("{}", violation);
                 }
 
-                let source_set = match target {
-                    TargetLang::OCaml => {
-                        let emitter = lore_codegen::OCamlEmitter::new();
-                        emitter.translate(&store)?
+                if violations.is_empty() {
+                    Ok(())
+                } else {
+                    Err(lore_store::StoreError::NonConforming {
+                        path: data,
+                        violations: violations.len(),
+                    }
+                    .into())
+                }
+            }
+
+            Command::Load {
+                data,
+                inputs,
+                kind,
+                mapping,
+                id,
+                namespace,
+                output,
+            } => {
+                let mut store = load(inputs)?;
+
+                let mut mapping = match mapping {
+                    Some(mapping) => {
+                        let json = std::fs::read_to_string(mapping).into_diagnostic()?;
+                        lore_store::Mapping::from_json(&json)?
                     }
-                    _ => lore_codegen::SourceSet::empty(),
+                    None => lore_store::Mapping::new(),
                 };
+                // flags win over the mapping file
+                mapping.kind = kind.or(mapping.kind);
+                mapping.id = id.unwrap_or(mapping.id);
+                mapping.namespace = namespace.or(mapping.namespace);
+
+                let things = store.load_data(&data, &mapping)?;
+                store.check_things()?;
+
+                if let Some(output) = output {
+                    let lore = lore_store::things_to_lore(&store, &things, &output);
+                    std::fs::write(output, lore).into_diagnostic()?;
+                }
+                println!("Loaded {} things from {}", things.len(), data.display());
+
+                Ok(())
+            }
+
+            Command::Lsp => Ok(lore_lsp::run()?),
+
+            Command::Lock => {
+                let manifest = manifest()?.ok_or_else(manifest_not_found)?;
+                let lock = manifest.lock()?;
+                println!(
+                    "Locked {} package(s) in {}",
+                    lock.packages.len(),
+                    manifest.root.join(lore_store::LOCK_FILE).display()
+                );
+                Ok(())
+            }
 
-                for source in source_set.sources() {
+            Command::Diff {
+                old,
+                new,
+                fail_on_breaking,
+            } => {
+                let changes = lore_store::diff(&version(old)?, &version(new)?);
+                for change in &changes {
+                    println!("{}", change);
+                }
+                let breaking = changes.iter().filter(|change| change.breaking).count();
+                println!("{} change(s), {} breaking", changes.len(), breaking);
+                if fail_on_breaking && breaking > 0 {
+                    return Err(lore_store::StoreError::BreakingChanges { count: breaking }.into());
+                }
+                Ok(())
+            }
+
+            Command::Fmt { inputs, check } => {
+                let paths = sources(inputs, &manifest()?)?;
+                let mut unformatted = vec![];
+                for input in lore_store::lore_files(&paths) {
+                    let mut parser = lore_parser::Parser::for_file(input.clone())?;
+                    let parsetree = parser.parse()?;
+                    let formatted = lore_parser::Printer::new().print(&parsetree);
+                    if formatted == parser.source() {
+                        continue;
+                    }
+                    if check {
+                        println!("{}", input.display());
+                        unformatted.push(input);
+                    } else {
+                        std::fs::write(&input, formatted).into_diagnostic()?;
+                    }
+                }
+
+                if unformatted.is_empty() {
+                    Ok(())
+                } else {
+                    Err(lore_parser::UnformattedError { files: unformatted }.into())
+                }
+            }
+
+            Command::Doc {
+                inputs,
+                output_dir,
+                title,
+                languages,
+            } => {
+                let store = load(inputs)?;
+
+                let emitter = lore_codegen::SiteEmitter::new()
+                    .with_title(title)
+                    .with_docs(lore_codegen::Docs::new().with_languages(languages));
+                for source in emitter.translate(&store)?.sources() {
                     source.write(&output_dir)?;
                 }
 
                 Ok(())
             }
+
+            Command::Codegen {
+                inputs,
+                target,
+                output_dir,
+                json,
+                strip_namespace,
+                languages,
+                all_languages,
+                per_kind,
+                proto_lock,
+                sql_dialect,
+                watch,
+            } => {
+                let manifest = manifest()?;
+                let generators = match target {
+                    Some(target) => vec![Generator {
+                        target,
+                        output_dir,
+                        json,
+                        strip_namespace,
+                        docs: lore_codegen::Docs::new()
+                            .with_languages(languages)
+                            .with_all_languages(all_languages),
+                        per_kind,
+                        proto_lock,
+                        sql_dialect,
+                    }],
+                    None => {
+                        let manifest = manifest.as_ref().ok_or_else(manifest_not_found)?;
+                        if manifest.targets.is_empty() {
+                            return Err(lore_store::StoreError::InvalidManifest {
+                                path: manifest.root.join(lore_store::MANIFEST_FILE),
+                                reason: "pass a --target, or add a [codegen.<target>] table"
+                                    .to_string(),
+                            }
+                            .into());
+                        }
+                        manifest
+                            .targets
+                            .iter()
+                            .map(|target| Generator::from_manifest(manifest, target))
+                            .collect::<Result<Vec<Generator>>>()?
+                    }
+                };
+                let (paths, mut store) = project(inputs, &manifest)?;
+
+                let generate = |store: &lore_store::Store| -> Result<()> {
+                    for generator in &generators {
+                        generator.generate(store)?;
+                    }
+                    Ok(())
+                };
+
+                if watch {
+                    return watch::watch(&paths, store, generate);
+                }
+
+                for file in lore_store::lore_files(&paths) {
+                    store.add_file(file)?;
+                }
+                generate(&store)
+            }
         }
     }
 }
@@ -0,0 +1,254 @@
+use miette::{IntoDiagnostic, Result};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait for more changes before re-running, since editors often save in bursts.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// A file as it was the last time we parsed it, or the diagnostic we got for it.
+struct Parsed {
+    modified: Option<SystemTime>,
+    result: std::result::Result<(String, lore_parser::Structure), String>,
+}
+
+/// Keeps the parsed files and the diagnostics already printed between runs.
+pub struct Session {
+    /// The settings every run starts from, before any file is added.
+    store: lore_store::Store,
+    parsed: BTreeMap<PathBuf, Parsed>,
+    reported: BTreeSet<String>,
+    /// The files the inputs imported in the last run.
+    imported: BTreeSet<PathBuf>,
+}
+
+fn render(diagnostic: impl miette::Diagnostic + Send + Sync + 'static) -> String {
+    format!("{:?}", miette::Report::new(diagnostic))
+}
+
+impl Session {
+    pub fn new(store: lore_store::Store) -> Session {
+        Session {
+            store,
+            parsed: BTreeMap::new(),
+            reported: BTreeSet::new(),
+            imported: BTreeSet::new(),
+        }
+    }
+
+    /// An empty store with the namespace and packages of the one we started from. Clones of a
+    /// store share its graph, so reusing one would keep everything every run ever added.
+    fn fresh_store(&self) -> lore_store::Store {
+        let mut store = lore_store::Store::new().with_packages(self.store.packages.clone());
+        if let Some(namespace) = &self.store.namespace {
+            store = store.with_namespace(namespace.clone());
+        }
+        store
+    }
+
+    /// Parses the files that changed since the last run, rebuilds the store, runs `run` on it,
+    /// and returns the diagnostics that were not there last time.
+    pub fn check<F>(&mut self, inputs: &[PathBuf], run: &mut F) -> Vec<String>
+    where
+        F: FnMut(&lore_store::Store) -> Result<()>,
+    {
+        let files = lore_store::lore_files(inputs);
+        self.parsed.retain(|path, _| files.contains(path));
+        for file in &files {
+            let modified = std::fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if let Some(parsed) = self.parsed.get(file) {
+                if modified.is_some() && parsed.modified == modified {
+                    continue;
+                }
+            }
+            let result = self.store.parse_file(file).map_err(render);
+            self.parsed
+                .insert(file.clone(), Parsed { modified, result });
+        }
+
+        let mut diagnostics: BTreeSet<String> = self
+            .parsed
+            .values()
+            .filter_map(|parsed| parsed.result.as_ref().err().cloned())
+            .collect();
+
+        // a store with some files missing would only add confusing errors about the rest
+        if diagnostics.is_empty() {
+            let mut store = self.fresh_store();
+            for (path, parsed) in &self.parsed {
+                if let Ok((source, parsetree)) = &parsed.result {
+                    if let Err(err) =
+                        store.add_parsed_file(path.clone(), source.clone(), parsetree.clone())
+                    {
+                        diagnostics.insert(render(err));
+                    }
+                }
+            }
+            self.imported = store.imported_files().into_iter().cloned().collect();
+            if diagnostics.is_empty() {
+                if let Err(report) = run(&store) {
+                    diagnostics.insert(format!("{:?}", report));
+                }
+            }
+        }
+
+        let new = diagnostics.difference(&self.reported).cloned().collect();
+        self.reported = diagnostics;
+        new
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.reported.is_empty()
+    }
+
+    pub fn imported_files(&self) -> &BTreeSet<PathBuf> {
+        &self.imported
+    }
+}
+
+/// The directory to watch for changes to a file, since editors often save by replacing it.
+fn parent_dir(file: &Path) -> &Path {
+    file.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Runs `run` on the inputs, and again every time any of them changes, until interrupted.
+pub fn watch<F>(inputs: &[PathBuf], store: lore_store::Store, mut run: F) -> Result<()>
+where
+    F: FnMut(&lore_store::Store) -> Result<()>,
+{
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender).into_diagnostic()?;
+    for input in inputs {
+        if input.is_dir() {
+            watcher
+                .watch(input, RecursiveMode::Recursive)
+                .into_diagnostic()?;
+        } else {
+            watcher
+                .watch(parent_dir(input), RecursiveMode::NonRecursive)
+                .into_diagnostic()?;
+        }
+    }
+
+    let mut session = Session::new(store);
+    let mut watched_imports = BTreeSet::new();
+    loop {
+        for diagnostic in session.check(inputs, &mut run) {
+            eprintln!("{}", diagnostic);
+        }
+        // imports can point anywhere, so their directories are watched once we know about them
+        for file in session.imported_files() {
+            let dir = parent_dir(file).to_path_buf();
+            if watched_imports.insert(dir.clone()) {
+                watcher
+                    .watch(&dir, RecursiveMode::NonRecursive)
+                    .into_diagnostic()?;
+            }
+        }
+        if session.is_ok() {
+            eprintln!("All good. Watching for changes...");
+        } else {
+            eprintln!(
+                "{} problem(s). Watching for changes...",
+                session.reported.len()
+            );
+        }
+
+        // wait for a change to a .lore file, then let the rest of the burst settle
+        loop {
+            let event = receiver.recv().into_diagnostic()?;
+            if let Ok(event) = event {
+                if event
+                    .paths
+                    .iter()
+                    .any(|path| lore_store::is_lore_file(path))
+                {
+                    break;
+                }
+            }
+        }
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_diagnostic_once() {
+        let dir = std::env::temp_dir().join(format!("lore-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("heroes.lore");
+        let inputs = vec![dir.clone()];
+        let mut runs = 0;
+        let mut run = |_: &lore_store::Store| {
+            runs += 1;
+            Ok(())
+        };
+
+        std::fs::write(&file, "using dota:v2022\nkind Hero\n").unwrap();
+        let mut session = Session::new(lore_store::Store::new());
+        assert!(session.check(&inputs, &mut run).is_empty());
+        assert!(session.is_ok());
+
+        std::fs::write(&file, "using dota:v2022\nkind Hero {\n").unwrap();
+        session.parsed.get_mut(&file).unwrap().modified = None;
+        assert_eq!(session.check(&inputs, &mut run).len(), 1);
+        // nothing changed, so nothing new to say
+        assert!(session.check(&inputs, &mut run).is_empty());
+        assert!(!session.is_ok());
+
+        std::fs::write(&file, "using dota:v2022\nkind Hero\n").unwrap();
+        session.parsed.get_mut(&file).unwrap().modified = None;
+        assert!(session.check(&inputs, &mut run).is_empty());
+        assert!(session.is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    fn starts_every_run_from_an_empty_graph() {
+        let dir = std::env::temp_dir().join(format!("lore-watch-fresh-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        let file = dir.join("heroes.lore");
+        let inputs = vec![file.clone()];
+        let mut sizes = vec![];
+        let mut run = |store: &lore_store::Store| {
+            sizes.push(store.graph.len());
+            Ok(())
+        };
+
+        std::fs::write(dir.join("base/names.lore"), "using dota:v2022\nattr Name\n").unwrap();
+        std::fs::write(
+            &file,
+            "using dota:v2022\nimport \"base/names.lore\"\nkind Hero\nkind Item\n",
+        )
+        .unwrap();
+        let mut session = Session::new(lore_store::Store::new());
+        assert!(session.check(&inputs, &mut run).is_empty());
+        assert_eq!(
+            session.imported_files().iter().collect::<Vec<_>>(),
+            vec![&dir.join("base/names.lore")]
+        );
+
+        std::fs::write(
+            &file,
+            "using dota:v2022\nimport \"base/names.lore\"\nkind Hero\n",
+        )
+        .unwrap();
+        session.parsed.get_mut(&file).unwrap().modified = None;
+        assert!(session.check(&inputs, &mut run).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(sizes.len(), 2);
+        assert!(sizes[1] < sizes[0]);
+    }
+}
@@ -0,0 +1,573 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Dot,
+    Mermaid,
+}
+
+impl std::fmt::Display for DiagramFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DiagramFormat::Dot => "dot",
+            DiagramFormat::Mermaid => "mermaid",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for DiagramFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<DiagramFormat, String> {
+        match s {
+            "dot" => Ok(DiagramFormat::Dot),
+            "mermaid" => Ok(DiagramFormat::Mermaid),
+            _ => Err(format!(
+                "Could not find diagram format: {}. Try one of: dot | mermaid",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NodeKind {
+    Kind,
+    Enumeration,
+    Attribute,
+    /// Used in a relation but never declared.
+    Undeclared,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node {
+    name: lore_ast::Name,
+    kind: NodeKind,
+    /// Relations to primitives, drawn inside the node.
+    fields: Vec<(String, lore_ast::Primitive, lore_ast::Cardinality)>,
+    variants: Vec<String>,
+    doc: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Edge {
+    subject: lore_ast::URI,
+    predicate: String,
+    object: lore_ast::URI,
+    cardinality: lore_ast::Cardinality,
+}
+
+/// Draws the kinds and attributes of a store as a class diagram, with relations to primitives
+/// inside each node and every other relation as an edge labeled by its predicate.
+pub struct DiagramEmitter {
+    format: DiagramFormat,
+    docs: Docs,
+    namespace: Option<String>,
+    focus: Option<String>,
+    depth: usize,
+}
+
+impl Default for DiagramEmitter {
+    fn default() -> DiagramEmitter {
+        DiagramEmitter {
+            format: DiagramFormat::Dot,
+            docs: Docs::new(),
+            namespace: None,
+            focus: None,
+            depth: 1,
+        }
+    }
+}
+
+impl DiagramEmitter {
+    pub fn new() -> DiagramEmitter {
+        DiagramEmitter::default()
+    }
+
+    pub fn with_format(self, format: DiagramFormat) -> DiagramEmitter {
+        DiagramEmitter { format, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> DiagramEmitter {
+        DiagramEmitter { docs, ..self }
+    }
+
+    /// Only draw names in this namespace, like `dota:v2022` or just `dota`.
+    pub fn with_namespace(self, namespace: String) -> DiagramEmitter {
+        DiagramEmitter {
+            namespace: Some(namespace),
+            ..self
+        }
+    }
+
+    /// Only draw the names around this one, given as a URI or as its last segment.
+    pub fn with_focus(self, focus: String) -> DiagramEmitter {
+        DiagramEmitter {
+            focus: Some(focus),
+            ..self
+        }
+    }
+
+    /// How many relations away from the focus to keep drawing.
+    pub fn with_depth(self, depth: usize) -> DiagramEmitter {
+        DiagramEmitter { depth, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let filename = match self.format {
+            DiagramFormat::Dot => "ontology.dot",
+            DiagramFormat::Mermaid => "ontology.mmd",
+        };
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from(filename),
+            self.render(store)?,
+        )]))
+    }
+
+    pub fn render(&self, store: &lore_store::Store) -> Result<String, EmitterError> {
+        let (nodes, edges) = self.graph(store)?;
+        match self.format {
+            DiagramFormat::Dot => Ok(self.dot(&nodes, &edges)),
+            DiagramFormat::Mermaid => self.mermaid(&nodes, &edges),
+        }
+    }
+
+    fn graph(
+        &self,
+        store: &lore_store::Store,
+    ) -> Result<(BTreeMap<lore_ast::URI, Node>, Vec<Edge>), EmitterError> {
+        let mut nodes = BTreeMap::new();
+        let mut edges = vec![];
+
+        let node = |name: &lore_ast::Name, kind: NodeKind, fields: &[lore_ast::Field]| Node {
+            name: name.clone(),
+            kind,
+            fields: vec![],
+            variants: vec![],
+            doc: self.docs.doc(fields),
+        };
+
+        for attr in store.attributes() {
+            nodes.insert(
+                attr.name.to_uri(),
+                node(&attr.name, NodeKind::Attribute, &attr.fields),
+            );
+        }
+        for kind in store.kinds() {
+            let mut n = if kind.is_enumeration() {
+                node(&kind.name, NodeKind::Enumeration, &kind.fields)
+            } else {
+                node(&kind.name, NodeKind::Kind, &kind.fields)
+            };
+            n.variants = kind.variants().iter().map(|v| local_name(v)).collect();
+            nodes.insert(kind.name.to_uri(), n);
+        }
+
+        let mut subjects: Vec<&lore_ast::URI> = store.relations_by_subject.keys().collect();
+        subjects.sort();
+        for subject in subjects {
+            for rel in &store.relations_by_subject[subject] {
+                for name in [&rel.subject, &rel.object] {
+                    if lore_ast::Primitive::from_uri(&name.uri).is_none() {
+                        nodes
+                            .entry(name.to_uri())
+                            .or_insert_with(|| node(name, NodeKind::Undeclared, &[]));
+                    }
+                }
+
+                let predicate = local_name(&rel.predicate);
+                match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                    Some(primitive) => {
+                        if let Some(node) = nodes.get_mut(&rel.subject.to_uri()) {
+                            node.fields.push((predicate, primitive, rel.cardinality()));
+                        }
+                    }
+                    None => edges.push(Edge {
+                        subject: rel.subject.to_uri(),
+                        predicate,
+                        object: rel.object.to_uri(),
+                        cardinality: rel.cardinality(),
+                    }),
+                }
+            }
+        }
+
+        if let Some(namespace) = &self.namespace {
+            nodes.retain(|_, node| {
+                let uri = node.name.to_string();
+                uri.starts_with(&format!("{}/", namespace))
+                    || uri.starts_with(&format!("{}:", namespace))
+            });
+        }
+
+        if let Some(focus) = &self.focus {
+            let focus = self.resolve(focus, &nodes)?;
+            let mut kept = BTreeSet::new();
+            let mut queue = VecDeque::new();
+            kept.insert(focus.clone());
+            queue.push_back((focus, 0));
+            while let Some((uri, distance)) = queue.pop_front() {
+                if distance == self.depth {
+                    continue;
+                }
+                for edge in &edges {
+                    let next = if edge.subject == uri {
+                        &edge.object
+                    } else if edge.object == uri {
+                        &edge.subject
+                    } else {
+                        continue;
+                    };
+                    if nodes.contains_key(next) && kept.insert(next.clone()) {
+                        queue.push_back((next.clone(), distance + 1));
+                    }
+                }
+            }
+            nodes.retain(|uri, _| kept.contains(uri));
+        }
+
+        edges.retain(|edge| nodes.contains_key(&edge.subject) && nodes.contains_key(&edge.object));
+        Ok((nodes, edges))
+    }
+
+    fn resolve(
+        &self,
+        focus: &str,
+        nodes: &BTreeMap<lore_ast::URI, Node>,
+    ) -> Result<lore_ast::URI, EmitterError> {
+        if let Some(node) = nodes.values().find(|node| node.name.to_string() == focus) {
+            return Ok(node.name.to_uri());
+        }
+
+        let candidates: Vec<&Node> = nodes
+            .values()
+            .filter(|node| local_name(&node.name) == focus)
+            .collect();
+        match candidates.as_slice() {
+            [] => Err(EmitterError::UnknownName {
+                name: focus.to_string(),
+            }),
+            [node] => Ok(node.name.to_uri()),
+            candidates => Err(EmitterError::AmbiguousName {
+                name: focus.to_string(),
+                candidates: candidates
+                    .iter()
+                    .map(|node| format!("`{}`", node.name))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            }),
+        }
+    }
+
+    fn clusters(nodes: &BTreeMap<lore_ast::URI, Node>) -> BTreeMap<String, Vec<&Node>> {
+        let mut clusters: BTreeMap<String, Vec<&Node>> = BTreeMap::new();
+        for node in nodes.values() {
+            clusters
+                .entry(namespace(&node.name))
+                .or_default()
+                .push(node);
+        }
+        clusters
+    }
+
+    fn dot(&self, nodes: &BTreeMap<lore_ast::URI, Node>, edges: &[Edge]) -> String {
+        let escape = |text: &str| {
+            text.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        };
+        // record labels also give a meaning to these
+        let escape_record = |text: &str| {
+            let mut escaped = escape(text);
+            for c in ['{', '}', '|', '<', '>', '[', ']'] {
+                escaped = escaped.replace(c, &format!("\\{}", c));
+            }
+            escaped
+        };
+
+        let mut out = String::new();
+        out.push_str("digraph ontology {\n");
+        out.push_str("  rankdir=LR;\n");
+        out.push_str("  node [shape=record, fontname=\"Helvetica\"];\n");
+        out.push_str("  edge [fontname=\"Helvetica\"];\n");
+
+        for (namespace, cluster) in DiagramEmitter::clusters(nodes) {
+            out.push('\n');
+            out.push_str(&format!(
+                "  subgraph \"cluster_{}\" {{\n",
+                escape(&namespace)
+            ));
+            out.push_str(&format!("    label=\"{}\";\n", escape(&namespace)));
+            for node in cluster {
+                let mut sections = vec![];
+                let title = escape_record(&local_name(&node.name));
+                sections.push(match node.kind {
+                    NodeKind::Enumeration => format!("\\<\\<enumeration\\>\\>\\n{}", title),
+                    NodeKind::Attribute => format!("\\<\\<attribute\\>\\>\\n{}", title),
+                    _ => title,
+                });
+                if !node.fields.is_empty() {
+                    let fields: Vec<String> = node
+                        .fields
+                        .iter()
+                        .map(|(name, primitive, cardinality)| {
+                            let type_ = match cardinality.multiplicity() {
+                                lore_ast::Multiplicity::One => primitive.name().to_string(),
+                                _ => format!("{} [{}]", primitive.name(), cardinality),
+                            };
+                            format!("{}: {}\\l", escape_record(name), escape_record(&type_))
+                        })
+                        .collect();
+                    sections.push(fields.join(""));
+                }
+                if !node.variants.is_empty() {
+                    let variants: Vec<String> = node
+                        .variants
+                        .iter()
+                        .map(|v| format!("{}\\l", escape_record(v)))
+                        .collect();
+                    sections.push(variants.join(""));
+                }
+
+                let mut attrs = vec![format!("label=\"{{{}}}\"", sections.join("|"))];
+                match node.kind {
+                    NodeKind::Attribute => attrs.push("style=rounded".to_string()),
+                    NodeKind::Undeclared => attrs.push("style=dashed".to_string()),
+                    _ => (),
+                }
+                if let Some(doc) = &node.doc {
+                    attrs.push(format!("tooltip=\"{}\"", escape(doc)));
+                }
+                out.push_str(&format!(
+                    "    \"{}\" [{}];\n",
+                    escape(&node.name.to_string()),
+                    attrs.join(", ")
+                ));
+            }
+            out.push_str("  }\n");
+        }
+
+        if !edges.is_empty() {
+            out.push('\n');
+        }
+        for edge in edges {
+            let label = match edge.cardinality.multiplicity() {
+                lore_ast::Multiplicity::One => edge.predicate.clone(),
+                _ => format!("{} [{}]", edge.predicate, edge.cardinality),
+            };
+            out.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                escape(&edge.subject.to_string()),
+                escape(&edge.object.to_string()),
+                escape(&label)
+            ));
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid identifiers can only have letters, digits, and underscores, so every node gets an
+    /// identifier built from its URI and a label with its last segment.
+    fn mermaid(
+        &self,
+        nodes: &BTreeMap<lore_ast::URI, Node>,
+        edges: &[Edge],
+    ) -> Result<String, EmitterError> {
+        let identifier = |text: &str| -> String {
+            text.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect()
+        };
+        let escape = |text: &str| text.replace('"', "#quot;").replace('\n', " ");
+
+        let mut scope = Scope::new();
+        let mut ids = BTreeMap::new();
+        for node in nodes.values() {
+            let id = scope.claim(identifier(&node.name.to_string()), &node.name)?;
+            ids.insert(node.name.to_uri(), id);
+        }
+
+        let mut out = String::new();
+        out.push_str("classDiagram\n");
+
+        for (namespace, cluster) in DiagramEmitter::clusters(nodes) {
+            out.push_str(&format!("  namespace {} {{\n", identifier(&namespace)));
+            for node in cluster {
+                let mut members = vec![];
+                match node.kind {
+                    NodeKind::Enumeration => members.push("<<enumeration>>".to_string()),
+                    NodeKind::Attribute => members.push("<<attribute>>".to_string()),
+                    _ => (),
+                }
+                for (name, primitive, cardinality) in &node.fields {
+                    let type_ = match cardinality.multiplicity() {
+                        lore_ast::Multiplicity::One => primitive.name().to_string(),
+                        lore_ast::Multiplicity::Optional => {
+                            format!("Optional~{}~", primitive.name())
+                        }
+                        _ => format!("List~{}~", primitive.name()),
+                    };
+                    members.push(format!("+{} {}", type_, name));
+                }
+                members.extend(node.variants.iter().cloned());
+
+                let class = format!(
+                    "class {}[\"{}\"]",
+                    ids[&node.name.to_uri()],
+                    escape(&local_name(&node.name))
+                );
+                if members.is_empty() {
+                    out.push_str(&format!("    {}\n", class));
+                } else {
+                    out.push_str(&format!("    {} {{\n", class));
+                    for member in members {
+                        out.push_str(&format!("      {}\n", member));
+                    }
+                    out.push_str("    }\n");
+                }
+            }
+            out.push_str("  }\n");
+        }
+
+        for edge in edges {
+            out.push_str(&format!(
+                "  {} --> \"{}\" {} : {}\n",
+                ids[&edge.subject],
+                edge.cardinality,
+                ids[&edge.object],
+                escape(&edge.predicate)
+            ));
+        }
+
+        for node in nodes.values() {
+            if let Some(doc) = &node.doc {
+                out.push_str(&format!(
+                    "  note for {} \"{}\"\n",
+                    ids[&node.name.to_uri()],
+                    escape(doc)
+                ));
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $emitter:expr, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let emitter = $emitter;
+                let diagram = emitter.render(&store).unwrap();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{}
+"#,
+                    $src, diagram
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    const HEROES: &str = r#"
+
+prefix lore:v1 as @lore
+prefix valve:items as @items
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character."
+}
+
+kind Ability
+kind @items/Item
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+  @lore/variant Intelligence
+}
+
+attr Name
+
+rel Hero mainName Name
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Hero carries @items/Item {
+  @lore/cardinality/max 6
+}
+
+rel Ability tags @lore/String {
+  @lore/cardinality @lore/many
+}
+
+rel Ability upgradedBy @items/Item {
+  @lore/cardinality/max 1
+}
+
+rel @items/Item cost @lore/Int
+
+        "#;
+
+    test!(ontology_to_dot, DiagramEmitter::new(), HEROES);
+
+    test!(
+        ontology_to_mermaid,
+        DiagramEmitter::new().with_format(DiagramFormat::Mermaid),
+        HEROES
+    );
+
+    test!(
+        filter_by_namespace,
+        DiagramEmitter::new().with_namespace("valve".to_string()),
+        HEROES
+    );
+
+    test!(
+        filter_by_neighbourhood,
+        DiagramEmitter::new()
+            .with_format(DiagramFormat::Mermaid)
+            .with_focus("Item".to_string()),
+        HEROES
+    );
+
+    #[test]
+    fn unknown_focus() {
+        let mut store = lore_store::Store::new();
+        let store = store.add_from_string(HEROES).unwrap();
+        let emitter = DiagramEmitter::new().with_focus("Creep".to_string());
+        assert_eq!(
+            emitter.render(store),
+            Err(EmitterError::UnknownName {
+                name: "Creep".to_string()
+            })
+        );
+    }
+}
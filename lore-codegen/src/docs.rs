@@ -0,0 +1,143 @@
+use lore_ast::Localized;
+
+/// Which languages to take documentation from. Emitters ask this for the doc string of a kind,
+/// attribute, or relation instead of reading `lore:v1/doc/en` directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Docs {
+    languages: Vec<String>,
+    all_languages: bool,
+}
+
+impl Default for Docs {
+    fn default() -> Docs {
+        Docs {
+            languages: vec!["en".to_string()],
+            all_languages: false,
+        }
+    }
+}
+
+impl Docs {
+    pub fn new() -> Docs {
+        Docs::default()
+    }
+
+    /// Languages in order of preference, as BCP 47 tags.
+    pub fn with_languages(self, languages: Vec<String>) -> Docs {
+        Docs { languages, ..self }
+    }
+
+    /// Include the text in every language, each one tagged with its language.
+    pub fn with_all_languages(self, all_languages: bool) -> Docs {
+        Docs {
+            all_languages,
+            ..self
+        }
+    }
+
+    /// The documentation in the preferred language, falling back to a label when there is no
+    /// documentation at all.
+    pub fn doc(&self, fields: &[lore_ast::Field]) -> Option<String> {
+        self.select(Localized::Doc, fields)
+            .or_else(|| self.select(Localized::Label, fields))
+    }
+
+    pub fn label(&self, fields: &[lore_ast::Field]) -> Option<String> {
+        self.select(Localized::Label, fields)
+    }
+
+    fn select(&self, localized: Localized, fields: &[lore_ast::Field]) -> Option<String> {
+        let texts = localized.texts(fields);
+
+        if self.all_languages && texts.len() > 1 {
+            let texts: Vec<String> = texts
+                .iter()
+                .map(|(lang, text)| match lang.as_str() {
+                    "" => text.clone(),
+                    lang => format!("[{}] {}", lang, text),
+                })
+                .collect();
+            return Some(texts.join("\n"));
+        }
+
+        for language in &self.languages {
+            if let Some((_, text)) = Docs::best_match(language, &texts) {
+                return Some(text.clone());
+            }
+        }
+
+        // nothing in a preferred language: untagged text, then whatever was written first
+        texts
+            .iter()
+            .find(|(lang, _)| lang.is_empty())
+            .or_else(|| texts.first())
+            .map(|(_, text)| text.clone())
+    }
+
+    /// Matches `en-GB` exactly, then a more specific tag like `en-GB-oxendict`, then falls back
+    /// to less specific tags like `en`.
+    fn best_match<'a>(
+        language: &str,
+        texts: &'a [(String, String)],
+    ) -> Option<&'a (String, String)> {
+        let language = language.to_ascii_lowercase();
+        let tag = |t: &(String, String)| t.0.to_ascii_lowercase();
+
+        if let Some(text) = texts.iter().find(|t| tag(t) == language) {
+            return Some(text);
+        }
+
+        if let Some(text) = texts
+            .iter()
+            .find(|t| tag(t).starts_with(&format!("{}-", language)))
+        {
+            return Some(text);
+        }
+
+        let mut prefix = language.as_str();
+        while let Some(i) = prefix.rfind('-') {
+            prefix = &prefix[..i];
+            if let Some(text) = texts.iter().find(|t| tag(t) == prefix) {
+                return Some(text);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(tag: &str, text: &str) -> lore_ast::Field {
+        lore_ast::Field {
+            name: lore_ast::Name::of_uri(&lore_ast::URI::from_string(format!(
+                "lore:v1/doc/{}",
+                tag
+            ))),
+            value: lore_ast::Literal::String(text.to_string()),
+        }
+    }
+
+    #[test]
+    fn prefers_languages_in_order() {
+        let fields = vec![doc("en", "Hero"), doc("es-AR", "Héroe"), doc("pt", "Herói")];
+
+        let docs = Docs::new().with_languages(vec!["es".to_string(), "en".to_string()]);
+        assert_eq!(docs.doc(&fields), Some("Héroe".to_string()));
+
+        let docs = Docs::new().with_languages(vec!["pt-BR".to_string()]);
+        assert_eq!(docs.doc(&fields), Some("Herói".to_string()));
+
+        let docs = Docs::new().with_languages(vec!["fr".to_string()]);
+        assert_eq!(docs.doc(&fields), Some("Hero".to_string()));
+    }
+
+    #[test]
+    fn includes_all_languages() {
+        let fields = vec![doc("en", "Hero"), doc("es", "Héroe")];
+        let docs = Docs::new().with_all_languages(true);
+        assert_eq!(docs.doc(&fields), Some("[en] Hero\n[es] Héroe".to_string()));
+    }
+}
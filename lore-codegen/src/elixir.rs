@@ -1,13 +1,39 @@
+use crate::docs::*;
 use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ElixirType {
     name: String,
+    definition: String,
+    doc: Option<String>,
+}
+
+impl ElixirType {
+    pub fn primitive(primitive: lore_ast::Primitive) -> String {
+        match primitive {
+            lore_ast::Primitive::String => "String.t()",
+            lore_ast::Primitive::Int => "integer()",
+            lore_ast::Primitive::Float => "float()",
+            lore_ast::Primitive::Bool => "boolean()",
+            lore_ast::Primitive::Date => "Date.t()",
+            lore_ast::Primitive::DateTime => "DateTime.t()",
+            lore_ast::Primitive::URI => "URI.t()",
+        }
+        .to_string()
+    }
 }
 
 impl std::fmt::Display for ElixirType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "@type {}()", self.name)
+        if let Some(doc) = &self.doc {
+            // Rust's escaping matches Elixir's, except that `#{` would interpolate
+            writeln!(f, "@typedoc {}", format!("{:?}", doc).replace("#{", "\\#{"))?;
+            write!(f, "  ")?;
+        }
+        write!(f, "@type {}() :: {}", self.name, self.definition)
     }
 }
 
@@ -19,9 +45,9 @@ pub struct ElixirModule {
 
 impl std::fmt::Display for ElixirModule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "defmodule {} do\n", self.name)?;
+        writeln!(f, "defmodule {} do", self.name)?;
         for t in &self.types {
-            write!(f, "{}\n", t)?;
+            writeln!(f, "  {}", t)?;
         }
         write!(f, "end")
     }
@@ -35,14 +61,27 @@ pub struct ElixirLib {
 impl std::fmt::Display for ElixirLib {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         for m in &self.modules {
-            write!(f, "{}\n", m)?;
+            writeln!(f, "{}", m)?;
         }
         Ok(())
     }
 }
 
-#[derive(Default)]
-pub struct ElixirEmitter {}
+pub struct ElixirEmitter {
+    root_module: String,
+    naming: Naming,
+    docs: Docs,
+}
+
+impl Default for ElixirEmitter {
+    fn default() -> ElixirEmitter {
+        ElixirEmitter {
+            root_module: "Ontology".to_string(),
+            naming: Naming::elixir(),
+            docs: Docs::new(),
+        }
+    }
+}
 
 // impl LoreEmitter<Document> for ElixirEmitter {
 impl ElixirEmitter {
@@ -50,24 +89,89 @@ impl ElixirEmitter {
         ElixirEmitter::default()
     }
 
-    pub fn translate(&self, store: &lore_store::Store) -> Result<ElixirLib, EmitterError> {
-        let mut modules = vec![];
+    pub fn with_naming(self, naming: Naming) -> ElixirEmitter {
+        ElixirEmitter { naming, ..self }
+    }
 
-        for kind in store.kinds() {
-            let mut name = kind.name.to_string().replace(":", "_").replace("/", ".");
-            if let Some(first) = name.get_mut(0..1) {
-                first.make_ascii_uppercase();
+    pub fn with_docs(self, docs: Docs) -> ElixirEmitter {
+        ElixirEmitter { docs, ..self }
+    }
+
+    /// Elixir module names are already hierarchical, so every namespace segment becomes one
+    /// level: `dota:v2022/Hero` is `Ontology.Dota.V2022.Hero`.
+    fn module_name(&self, name: &lore_ast::Name) -> String {
+        let mut path = vec![self.root_module.clone()];
+        if !self.naming.strips_namespace() {
+            for segment in namespace_segments(name) {
+                path.push(self.naming.segment_name(&segment));
             }
+        }
+        path.push(
+            self.naming
+                .clone()
+                .with_strip_namespace(true)
+                .type_name(name),
+        );
+        path.join(".")
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let mut modules = vec![];
+        let mut module_scope = Scope::for_store(store);
+
+        let names = store
+            .attributes()
+            .into_iter()
+            .map(|attr| (&attr.name, &attr.fields))
+            .chain(
+                store
+                    .kinds()
+                    .into_iter()
+                    .map(|kind| (&kind.name, &kind.fields)),
+            );
+
+        for (name, fields) in names {
+            let definition = match store.relations_by_subject.get(&name.to_uri()) {
+                None => "term()".to_string(),
+                Some(rels) => {
+                    let fields: Vec<String> = rels
+                        .iter()
+                        .map(|rel| {
+                            let value = match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                                Some(primitive) => ElixirType::primitive(primitive),
+                                None => format!("{}.t()", self.module_name(&rel.object)),
+                            };
+                            let value = match rel.cardinality().multiplicity() {
+                                lore_ast::Multiplicity::One => value,
+                                lore_ast::Multiplicity::Optional => format!("{} | nil", value),
+                                lore_ast::Multiplicity::List => format!("[{}]", value),
+                                lore_ast::Multiplicity::NonEmptyList => {
+                                    format!("nonempty_list({})", value)
+                                }
+                            };
+                            format!("\"{}\": {}", rel.predicate, value)
+                        })
+                        .collect();
+                    format!("%{{{}}}", fields.join(", "))
+                }
+            };
 
             modules.push(ElixirModule {
-                name,
+                name: module_scope.claim(self.module_name(name), name)?,
                 types: vec![ElixirType {
                     name: "t".to_string(),
+                    definition,
+                    doc: self.docs.doc(fields),
                 }],
             });
         }
 
-        Ok(ElixirLib { modules })
+        let lib = ElixirLib { modules };
+
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from("ontology.ex"),
+            lib.to_string(),
+        )]))
     }
 }
 
@@ -78,12 +182,15 @@ mod tests {
 
     macro_rules! test {
         ($name:ident, $src:expr) => {
+            test!($name, ElixirEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
             #[test]
             fn $name() {
                 let mut store = lore_store::Store::new();
                 let store = store.add_from_string($src).unwrap();
-                let emitter = ElixirEmitter::new();
-                let caml_value = emitter.translate(&store).unwrap();
+                let emitter = $emitter;
+                let sources = emitter.translate(&store).unwrap();
                 let snapshot = format!(
                     r#"
 input:
@@ -91,9 +198,9 @@ input:
 
 output:
 
-{}
+{:#?}
 "#,
-                    $src, caml_value
+                    $src, sources
                 );
                 assert_snapshot!(snapshot)
             }
@@ -104,12 +211,7 @@ output:
         kind_to_type,
         r#"
 
-use spotify:ontology:2022/Artist as Artist
-use spotify:ontology:2022/Album as Album
-use spotify:ontology:2022/Track as Track
-use spotify:ontology:2022/Name as Name
-use spotify:ontology:2022/hasOne as hasOne
-use spotify:ontology:2022/isListedIn as isListedIn
+using spotify:ontology:2022
 
 kind Artist
 
@@ -125,4 +227,82 @@ rel Track isListedIn Album
 
         "#
     );
+
+    test!(
+        primitives_to_native_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+
+rel Hero name @lore/String
+rel Hero movementSpeed @lore/Int
+rel Hero turnRate @lore/Float
+rel Hero isMelee @lore/Bool
+rel Hero releasedOn @lore/Date
+
+        "#
+    );
+
+    test!(
+        cardinality_to_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+kind Item
+kind Talent
+
+rel Hero mainName @lore/String
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max @lore/many
+}
+
+rel Hero carries Item {
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Talent {
+  @lore/cardinality/min 0
+  @lore/cardinality/max 1
+}
+
+rel Ability name @lore/String
+
+rel Ability upgrades Ability {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
+
+    test!(
+        docs_in_all_languages,
+        ElixirEmitter::new().with_docs(Docs::new().with_all_languages(true)),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character, see #{name}."
+  @lore/doc/es "Un personaje jugable."
+}
+
+attr Name {
+  @lore/label/en "Name"
+}
+
+        "#
+    );
 }
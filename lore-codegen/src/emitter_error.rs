@@ -3,8 +3,67 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Diagnostic, Error, Debug, PartialEq, Eq)]
-#[error("Error emitting file {filename:?}")]
-#[diagnostic(code(lore::codegen::emitter), url(docsrs))]
-pub struct EmitterError {
-    filename: PathBuf,
+pub enum EmitterError {
+    #[error("Error emitting file {filename:?}")]
+    #[diagnostic(code(lore::codegen::emitter), url(docsrs))]
+    EmitFailed { filename: PathBuf },
+
+    #[error("The identifier `{identifier}` would be generated for both `{first}` and `{second}`")]
+    #[diagnostic(
+        code(lore::codegen::name_collision),
+        help("Keep the namespace in generated names, or rename one of them.")
+    )]
+    NameCollision {
+        identifier: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("The identifier `{identifier}` for `{name}` is already used by generated code")]
+    #[diagnostic(
+        code(lore::codegen::name_collision),
+        help("Keep the namespace in generated names, or rename it.")
+    )]
+    ReservedName { identifier: String, name: String },
+
+    #[error("The relation `{relation}` has an invalid field number `{number}`: {reason}")]
+    #[diagnostic(
+        code(lore::codegen::invalid_field_number),
+        help("Field numbers go from 1 to 536870911, except 19000 to 19999.")
+    )]
+    InvalidFieldNumber {
+        relation: String,
+        number: String,
+        reason: String,
+    },
+
+    #[error("The field number {number} of `{message}` is used by both `{first}` and `{second}`")]
+    #[diagnostic(
+        code(lore::codegen::field_number_collision),
+        help("Give one of them another number.")
+    )]
+    FieldNumberCollision {
+        message: String,
+        number: u64,
+        first: String,
+        second: String,
+    },
+
+    #[error("Could not read the field numbers lockfile: {reason}")]
+    #[diagnostic(
+        code(lore::codegen::invalid_lockfile),
+        help("The lockfile maps the URI of every message to the URIs and numbers of its fields.")
+    )]
+    InvalidLockfile { reason: String },
+
+    #[error("Could not find `{name}`")]
+    #[diagnostic(
+        code(lore::codegen::unknown_name),
+        help("Use the full URI of a kind or attribute, or its last segment.")
+    )]
+    UnknownName { name: String },
+
+    #[error("`{name}` could be any of {candidates}")]
+    #[diagnostic(code(lore::codegen::ambiguous_name), help("Use the full URI instead."))]
+    AmbiguousName { name: String, candidates: String },
 }
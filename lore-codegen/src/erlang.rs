@@ -1,13 +1,39 @@
+use crate::docs::*;
 use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ErlangType {
     name: String,
+    definition: String,
+    doc: Option<String>,
+}
+
+impl ErlangType {
+    pub fn primitive(primitive: lore_ast::Primitive) -> String {
+        match primitive {
+            lore_ast::Primitive::Int => "integer()",
+            lore_ast::Primitive::Float => "float()",
+            lore_ast::Primitive::Bool => "boolean()",
+            lore_ast::Primitive::String
+            | lore_ast::Primitive::Date
+            | lore_ast::Primitive::DateTime
+            | lore_ast::Primitive::URI => "string()",
+        }
+        .to_string()
+    }
 }
 
 impl std::fmt::Display for ErlangType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "-type {}().", self.name)
+        if let Some(doc) = &self.doc {
+            for line in doc.lines() {
+                writeln!(f, "%% {}", line)?;
+            }
+        }
+        write!(f, "-type {}() :: {}.", self.name, self.definition)
     }
 }
 
@@ -19,16 +45,33 @@ pub struct ErlangModule {
 
 impl std::fmt::Display for ErlangModule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "-module({}).\n", self.name)?;
+        writeln!(f, "-module({}).", self.name)?;
+        if !self.types.is_empty() {
+            let exports: Vec<String> = self.types.iter().map(|t| format!("{}/0", t.name)).collect();
+            writeln!(f)?;
+            writeln!(f, "-export_type([{}]).", exports.join(", "))?;
+        }
         for t in &self.types {
-            write!(f, "{}\n", t)?;
+            writeln!(f)?;
+            writeln!(f, "{}", t)?;
         }
         Ok(())
     }
 }
 
-#[derive(Default)]
-pub struct ErlangEmitter {}
+pub struct ErlangEmitter {
+    naming: Naming,
+    docs: Docs,
+}
+
+impl Default for ErlangEmitter {
+    fn default() -> ErlangEmitter {
+        ErlangEmitter {
+            naming: Naming::erlang(),
+            docs: Docs::new(),
+        }
+    }
+}
 
 // impl LoreEmitter<Document> for ErlangEmitter {
 impl ErlangEmitter {
@@ -36,19 +79,76 @@ impl ErlangEmitter {
         ErlangEmitter::default()
     }
 
-    pub fn translate(&self, store: &lore_store::Store) -> Result<ErlangModule, EmitterError> {
+    pub fn with_naming(self, naming: Naming) -> ErlangEmitter {
+        ErlangEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> ErlangEmitter {
+        ErlangEmitter { docs, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
         let mut types = vec![];
+        let mut type_scope = Scope::for_store(store);
+
+        let names = store
+            .attributes()
+            .into_iter()
+            .map(|attr| (&attr.name, &attr.fields))
+            .chain(
+                store
+                    .kinds()
+                    .into_iter()
+                    .map(|kind| (&kind.name, &kind.fields)),
+            );
 
-        for kind in store.kinds() {
-            let name = kind.name.to_string().replace(":", "_").replace("/", "__");
+        for (name, fields) in names {
+            let definition = match store.relations_by_subject.get(&name.to_uri()) {
+                None => "term()".to_string(),
+                Some(rels) => {
+                    let fields: Vec<String> = rels
+                        .iter()
+                        .map(|rel| {
+                            let value = match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                                Some(primitive) => ErlangType::primitive(primitive),
+                                None => format!("{}()", self.naming.type_name(&rel.object)),
+                            };
+                            match rel.cardinality().multiplicity() {
+                                lore_ast::Multiplicity::One => {
+                                    format!("'{}' := {}", rel.predicate, value)
+                                }
+                                lore_ast::Multiplicity::Optional => {
+                                    format!("'{}' => {}", rel.predicate, value)
+                                }
+                                lore_ast::Multiplicity::List => {
+                                    format!("'{}' := [{}]", rel.predicate, value)
+                                }
+                                lore_ast::Multiplicity::NonEmptyList => {
+                                    format!("'{}' := [{}, ...]", rel.predicate, value)
+                                }
+                            }
+                        })
+                        .collect();
+                    format!("#{{{}}}", fields.join(", "))
+                }
+            };
 
-            types.push(ErlangType { name });
+            types.push(ErlangType {
+                name: type_scope.claim(self.naming.type_name(name), name)?,
+                definition,
+                doc: self.docs.doc(fields),
+            });
         }
 
-        Ok(ErlangModule {
-            name: "Ontology".to_string(),
+        let module = ErlangModule {
+            name: "ontology".to_string(),
             types,
-        })
+        };
+
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from(format!("{}.erl", module.name)),
+            module.to_string(),
+        )]))
     }
 }
 
@@ -64,7 +164,7 @@ mod tests {
                 let mut store = lore_store::Store::new();
                 let store = store.add_from_string($src).unwrap();
                 let emitter = ErlangEmitter::new();
-                let caml_value = emitter.translate(&store).unwrap();
+                let sources = emitter.translate(&store).unwrap();
                 let snapshot = format!(
                     r#"
 input:
@@ -72,9 +172,9 @@ input:
 
 output:
 
-{}
+{:#?}
 "#,
-                    $src, caml_value
+                    $src, sources
                 );
                 assert_snapshot!(snapshot)
             }
@@ -85,12 +185,7 @@ output:
         kind_to_type,
         r#"
 
-use spotify:ontology:2022/Artist as Artist
-use spotify:ontology:2022/Album as Album
-use spotify:ontology:2022/Track as Track
-use spotify:ontology:2022/Name as Name
-use spotify:ontology:2022/hasOne as hasOne
-use spotify:ontology:2022/isListedIn as isListedIn
+using spotify:ontology:2022
 
 kind Artist
 
@@ -106,4 +201,61 @@ rel Track isListedIn Album
 
         "#
     );
+
+    test!(
+        primitives_to_native_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+
+rel Hero name @lore/String
+rel Hero movementSpeed @lore/Int
+rel Hero turnRate @lore/Float
+rel Hero isMelee @lore/Bool
+rel Hero releasedOn @lore/Date
+
+        "#
+    );
+
+    test!(
+        cardinality_to_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+kind Item
+kind Talent
+
+rel Hero mainName @lore/String
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max @lore/many
+}
+
+rel Hero carries Item {
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Talent {
+  @lore/cardinality/min 0
+  @lore/cardinality/max 1
+}
+
+rel Ability name @lore/String
+
+rel Ability upgrades Ability {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
 }
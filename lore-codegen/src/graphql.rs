@@ -1,9 +1,24 @@
+use crate::docs::*;
 use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
 use graphql_parser::schema::*;
 use graphql_parser::Pos;
+use std::path::PathBuf;
 
-#[derive(Default)]
-pub struct GraphQLEmitter {}
+pub struct GraphQLEmitter {
+    naming: Naming,
+    docs: Docs,
+}
+
+impl Default for GraphQLEmitter {
+    fn default() -> GraphQLEmitter {
+        GraphQLEmitter {
+            naming: Naming::graphql(),
+            docs: Docs::new(),
+        }
+    }
+}
 
 // impl LoreEmitter<Document> for GraphQLEmitter {
 impl GraphQLEmitter {
@@ -11,49 +26,196 @@ impl GraphQLEmitter {
         GraphQLEmitter::default()
     }
 
-    pub fn translate(
+    pub fn with_naming(self, naming: Naming) -> GraphQLEmitter {
+        GraphQLEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> GraphQLEmitter {
+        GraphQLEmitter { docs, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let document = self.document(store)?;
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from("schema.graphql"),
+            document.to_string(),
+        )]))
+    }
+
+    pub fn document(
         &self,
         store: &lore_store::Store,
     ) -> Result<Document<'static, String>, EmitterError> {
         let mut definitions = vec![];
+        let mut scalars = vec![];
 
-        for kind in store.kinds() {
-            for typedef in self.kind_to_types(kind)? {
+        let names = store
+            .attributes()
+            .into_iter()
+            .map(|attr| (&attr.name, &attr.fields))
+            .chain(
+                store
+                    .kinds()
+                    .into_iter()
+                    .map(|kind| (&kind.name, &kind.fields)),
+            );
+
+        // input types share the namespace of every other type
+        let mut type_scope = Scope::for_store(store);
+        for (name, _) in names.clone() {
+            type_scope.claim(self.name_to_type_name(name), name)?;
+            if store.relations_by_subject.contains_key(&name.to_uri()) {
+                type_scope.claim(self.name_to_input_name(name), name)?;
+            }
+        }
+
+        for (name, fields) in names {
+            for typedef in self.name_to_types(store, name, fields, &mut scalars)? {
                 definitions.push(typedef);
             }
         }
 
-        Ok(Document { definitions })
+        scalars.sort();
+        scalars.dedup();
+        let mut document = scalars
+            .into_iter()
+            .map(|scalar| {
+                Definition::TypeDefinition(TypeDefinition::Scalar(ScalarType::new(scalar)))
+            })
+            .collect::<Vec<Definition<'static, String>>>();
+        document.extend(definitions);
+
+        Ok(Document {
+            definitions: document,
+        })
     }
 
     fn name_to_type_name(&self, name: &lore_ast::Name) -> String {
-        name.to_string().replace(":", "_").replace("/", "__")
+        self.naming.type_name(name)
     }
 
     fn name_to_input_name(&self, name: &lore_ast::Name) -> String {
-        format!("{}__Input", self.name_to_type_name(name))
+        format!("{}Input", self.name_to_type_name(name))
+    }
+
+    fn primitive_to_type_name(&self, primitive: lore_ast::Primitive) -> String {
+        match primitive {
+            lore_ast::Primitive::String => "String",
+            lore_ast::Primitive::Int => "Int",
+            lore_ast::Primitive::Float => "Float",
+            lore_ast::Primitive::Bool => "Boolean",
+            lore_ast::Primitive::Date => "Date",
+            lore_ast::Primitive::DateTime => "DateTime",
+            lore_ast::Primitive::URI => "URI",
+        }
+        .to_string()
+    }
+
+    /// GraphQL has no non-empty lists, so those are plain non-null lists.
+    fn field_type(
+        &self,
+        type_name: String,
+        multiplicity: lore_ast::Multiplicity,
+    ) -> Type<'static, String> {
+        let named = Type::NamedType(type_name);
+        match multiplicity {
+            lore_ast::Multiplicity::One => Type::NonNullType(Box::new(named)),
+            lore_ast::Multiplicity::Optional => named,
+            lore_ast::Multiplicity::List | lore_ast::Multiplicity::NonEmptyList => {
+                Type::NonNullType(Box::new(Type::ListType(Box::new(Type::NonNullType(
+                    Box::new(named),
+                )))))
+            }
+        }
     }
 
-    fn kind_to_types(
+    /// Names without relations have no known structure, so they become custom scalars. Names
+    /// with relations become an object type and a matching input type.
+    fn name_to_types(
         &self,
-        kind: &lore_ast::Kind,
+        store: &lore_store::Store,
+        name: &lore_ast::Name,
+        name_fields: &[lore_ast::Field],
+        scalars: &mut Vec<String>,
     ) -> Result<Vec<Definition<'static, String>>, EmitterError> {
         let position = Pos { line: 0, column: 0 };
+
+        let rels = match store.relations_by_subject.get(&name.to_uri()) {
+            None => {
+                scalars.push(self.name_to_type_name(name));
+                return Ok(vec![]);
+            }
+            Some(rels) => rels,
+        };
+
+        let mut fields = vec![];
+        let mut input_fields = vec![];
+        let mut field_scope = Scope::for_store(store);
+        for rel in rels {
+            let field_name =
+                field_scope.claim(self.naming.value_name(&rel.predicate), &rel.predicate)?;
+            let (type_name, input_name) = match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                Some(primitive) => {
+                    let type_name = self.primitive_to_type_name(primitive);
+                    if let lore_ast::Primitive::Date
+                    | lore_ast::Primitive::DateTime
+                    | lore_ast::Primitive::URI = primitive
+                    {
+                        scalars.push(type_name.clone());
+                    }
+                    (type_name.clone(), type_name)
+                }
+                None if store
+                    .relations_by_subject
+                    .contains_key(&rel.object.to_uri()) =>
+                {
+                    (
+                        self.name_to_type_name(&rel.object),
+                        self.name_to_input_name(&rel.object),
+                    )
+                }
+                None => {
+                    let type_name = self.name_to_type_name(&rel.object);
+                    scalars.push(type_name.clone());
+                    (type_name.clone(), type_name)
+                }
+            };
+
+            let multiplicity = rel.cardinality().multiplicity();
+            let description = self.docs.doc(&rel.fields);
+            fields.push(Field {
+                position,
+                description: description.clone(),
+                name: field_name.clone(),
+                arguments: vec![],
+                field_type: self.field_type(type_name, multiplicity),
+                directives: vec![],
+            });
+            input_fields.push(InputValue {
+                position,
+                description,
+                name: field_name,
+                value_type: self.field_type(input_name, multiplicity),
+                default_value: None,
+                directives: vec![],
+            });
+        }
+
         Ok(vec![
             Definition::TypeDefinition(TypeDefinition::Object(ObjectType {
-                name: self.name_to_type_name(&kind.name),
+                name: self.name_to_type_name(name),
                 position,
                 implements_interfaces: vec![],
-                description: None,
+                description: self.docs.doc(name_fields),
                 directives: vec![],
-                fields: vec![],
+                fields,
             })),
             Definition::TypeDefinition(TypeDefinition::InputObject(InputObjectType {
-                name: self.name_to_input_name(&kind.name),
+                name: self.name_to_input_name(name),
                 position,
-                description: None,
+                description: self.docs.doc(name_fields),
                 directives: vec![],
-                fields: vec![],
+                fields: input_fields,
             })),
         ])
     }
@@ -66,12 +228,15 @@ mod tests {
 
     macro_rules! test {
         ($name:ident, $src:expr) => {
+            test!($name, GraphQLEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
             #[test]
             fn $name() {
                 let mut store = lore_store::Store::new();
                 let store = store.add_from_string($src).unwrap();
-                let emitter = GraphQLEmitter::new();
-                let document = emitter.translate(&store).unwrap();
+                let emitter = $emitter;
+                let sources = emitter.translate(&store).unwrap();
                 let snapshot = format!(
                     r#"
 input:
@@ -79,9 +244,9 @@ input:
 
 output:
 
-{}
+{:#?}
 "#,
-                    $src, document
+                    $src, sources
                 );
                 assert_snapshot!(snapshot)
             }
@@ -92,12 +257,7 @@ output:
         kind_to_type,
         r#"
 
-use spotify:ontology:2022/Artist as Artist
-use spotify:ontology:2022/Album as Album
-use spotify:ontology:2022/Track as Track
-use spotify:ontology:2022/Name as Name
-use spotify:ontology:2022/hasOne as hasOne
-use spotify:ontology:2022/isListedIn as isListedIn
+using spotify:ontology:2022
 
 kind Artist
 
@@ -113,4 +273,101 @@ rel Track isListedIn Album
 
         "#
     );
+
+    test!(
+        primitives_to_native_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+
+rel Hero name @lore/String
+rel Hero movementSpeed @lore/Int
+rel Hero turnRate @lore/Float
+rel Hero isMelee @lore/Bool
+rel Hero releasedOn @lore/Date
+
+        "#
+    );
+
+    test!(
+        cardinality_to_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+kind Item
+kind Talent
+
+rel Hero mainName @lore/String
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max @lore/many
+}
+
+rel Hero carries Item {
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Talent {
+  @lore/cardinality/min 0
+  @lore/cardinality/max 1
+}
+
+rel Ability name @lore/String
+
+rel Ability upgrades Ability {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
+
+    test!(
+        strip_namespace_from_names,
+        GraphQLEmitter::new().with_naming(Naming::graphql().with_strip_namespace(true)),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Date
+
+rel Hero mainName @lore/String
+rel Hero releasedOn Date
+rel Date day @lore/Int
+
+        "#
+    );
+
+    test!(
+        docs_in_preferred_language,
+        GraphQLEmitter::new().with_docs(Docs::new().with_languages(vec!["es".to_string()])),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character."
+  @lore/doc/es "Un personaje jugable."
+}
+
+rel Hero mainName @lore/String {
+  @lore/label/en "Main name"
+}
+
+        "#
+    );
 }
@@ -0,0 +1,349 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Describes the JSON shape of every kind and attribute. Objects use the full URI of each
+/// relation as their keys, just like the OCaml JSON serializers.
+pub struct JsonSchemaEmitter {
+    naming: Naming,
+    docs: Docs,
+    bundle: bool,
+}
+
+impl Default for JsonSchemaEmitter {
+    fn default() -> JsonSchemaEmitter {
+        JsonSchemaEmitter {
+            naming: Naming::json_schema(),
+            docs: Docs::new(),
+            bundle: true,
+        }
+    }
+}
+
+impl JsonSchemaEmitter {
+    pub fn new() -> JsonSchemaEmitter {
+        JsonSchemaEmitter::default()
+    }
+
+    pub fn with_naming(self, naming: Naming) -> JsonSchemaEmitter {
+        JsonSchemaEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> JsonSchemaEmitter {
+        JsonSchemaEmitter { docs, ..self }
+    }
+
+    /// Write a single `ontology.schema.json` with every type under `$defs` (the default), or one
+    /// `<Type>.schema.json` document per type that refer to each other by file name.
+    pub fn with_bundle(self, bundle: bool) -> JsonSchemaEmitter {
+        JsonSchemaEmitter { bundle, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let names = store
+            .attributes()
+            .into_iter()
+            .map(|attr| (&attr.name, &attr.fields))
+            .chain(
+                store
+                    .kinds()
+                    .into_iter()
+                    .map(|kind| (&kind.name, &kind.fields)),
+            );
+
+        let names: Vec<_> = names.collect();
+
+        // every reference points at the name claimed here, so refs and defs cannot drift apart
+        let mut scope = Scope::for_store(store);
+        let mut type_names = HashMap::new();
+        for (name, _) in &names {
+            type_names.insert(
+                name.to_uri(),
+                scope.claim(self.naming.type_name(name), name)?,
+            );
+        }
+
+        // objects that are neither declared nor primitive still need a definition to refer to
+        let mut undeclared = vec![];
+        for (name, _) in &names {
+            for rel in store
+                .relations_by_subject
+                .get(&name.to_uri())
+                .into_iter()
+                .flatten()
+            {
+                let uri = rel.object.to_uri();
+                if lore_ast::Primitive::from_uri(&uri).is_none() && !type_names.contains_key(&uri) {
+                    let type_name = scope.claim(self.naming.type_name(&rel.object), &rel.object)?;
+                    type_names.insert(uri, type_name.clone());
+                    undeclared.push((type_name, json!({ "title": rel.object.to_string() })));
+                }
+            }
+        }
+
+        let mut schemas = vec![];
+        for (name, fields) in names {
+            let type_name = type_names[&name.to_uri()].clone();
+            schemas.push((type_name, self.schema(store, &type_names, name, fields)));
+        }
+        schemas.extend(undeclared);
+
+        if self.bundle {
+            let defs: Map<String, Value> = schemas.into_iter().collect();
+            let document = json!({
+                "$schema": DIALECT,
+                "$defs": defs,
+            });
+            return Ok(SourceSet::from_sources(vec![Source::new(
+                PathBuf::from("ontology.schema.json"),
+                JsonSchemaEmitter::print(&document),
+            )]));
+        }
+
+        let sources = schemas
+            .into_iter()
+            .map(|(type_name, mut schema)| {
+                let filename = self.file_name(&type_name);
+                if let Value::Object(schema) = &mut schema {
+                    schema.insert("$schema".to_string(), json!(DIALECT));
+                    schema.insert("$id".to_string(), json!(filename));
+                }
+                Source::new(PathBuf::from(filename), JsonSchemaEmitter::print(&schema))
+            })
+            .collect();
+
+        Ok(SourceSet::from_sources(sources))
+    }
+
+    fn print(value: &Value) -> String {
+        format!("{}\n", serde_json::to_string_pretty(value).unwrap())
+    }
+
+    fn file_name(&self, type_name: &str) -> String {
+        format!("{}.schema.json", type_name)
+    }
+
+    fn reference(&self, type_name: &str) -> Value {
+        if self.bundle {
+            json!({ "$ref": format!("#/$defs/{}", type_name) })
+        } else {
+            json!({ "$ref": self.file_name(type_name) })
+        }
+    }
+
+    fn primitive(primitive: lore_ast::Primitive) -> Value {
+        match primitive {
+            lore_ast::Primitive::String => json!({ "type": "string" }),
+            lore_ast::Primitive::Int => json!({ "type": "integer" }),
+            lore_ast::Primitive::Float => json!({ "type": "number" }),
+            lore_ast::Primitive::Bool => json!({ "type": "boolean" }),
+            lore_ast::Primitive::Date => json!({ "type": "string", "format": "date" }),
+            lore_ast::Primitive::DateTime => json!({ "type": "string", "format": "date-time" }),
+            lore_ast::Primitive::URI => json!({ "type": "string", "format": "uri" }),
+        }
+    }
+
+    /// Names without relations have no known structure, so any JSON value is accepted for them.
+    fn schema(
+        &self,
+        store: &lore_store::Store,
+        type_names: &HashMap<lore_ast::URI, String>,
+        name: &lore_ast::Name,
+        fields: &[lore_ast::Field],
+    ) -> Value {
+        let mut schema = Map::new();
+        schema.insert("title".to_string(), json!(name.to_string()));
+        if let Some(doc) = self.docs.doc(fields) {
+            schema.insert("description".to_string(), json!(doc));
+        }
+
+        let rels = match store.relations_by_subject.get(&name.to_uri()) {
+            None => return Value::Object(schema),
+            Some(rels) => rels,
+        };
+
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for rel in rels {
+            let key = rel.predicate.to_string();
+            let mut value = match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                Some(primitive) => JsonSchemaEmitter::primitive(primitive),
+                None => self.reference(&type_names[&rel.object.to_uri()]),
+            };
+
+            let cardinality = rel.cardinality();
+            value = match cardinality.multiplicity() {
+                lore_ast::Multiplicity::One => value,
+                lore_ast::Multiplicity::Optional => {
+                    json!({ "anyOf": [value, { "type": "null" }] })
+                }
+                lore_ast::Multiplicity::List | lore_ast::Multiplicity::NonEmptyList => {
+                    let mut array = json!({ "type": "array", "items": value });
+                    if cardinality.min > 0 {
+                        array["minItems"] = json!(cardinality.min);
+                    }
+                    if let Some(max) = cardinality.max {
+                        array["maxItems"] = json!(max);
+                    }
+                    array
+                }
+            };
+
+            if let Some(doc) = self.docs.doc(&rel.fields) {
+                if let Value::Object(value) = &mut value {
+                    value.insert("description".to_string(), json!(doc));
+                }
+            }
+
+            if cardinality.multiplicity() != lore_ast::Multiplicity::Optional {
+                required.push(key.clone());
+            }
+            properties.insert(key, value);
+        }
+
+        schema.insert("type".to_string(), json!("object"));
+        schema.insert("properties".to_string(), Value::Object(properties));
+        schema.insert("required".to_string(), json!(required));
+        schema.insert("additionalProperties".to_string(), json!(false));
+        Value::Object(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            test!($name, JsonSchemaEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let emitter = $emitter;
+                let sources = emitter.translate(&store).unwrap();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{:#?}
+"#,
+                    $src, sources
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    test!(
+        kinds_to_bundled_schema,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character."
+}
+
+kind Ability
+
+attr Name
+
+rel Hero mainName Name
+rel Hero movementSpeed @lore/Int
+rel Hero releasedOn @lore/Date
+
+rel Hero hasAbility Ability {
+  @lore/doc/en "Abilities this hero can cast."
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Ability {
+  @lore/cardinality/max 1
+}
+
+rel Ability manaCost @lore/Float
+
+        "#
+    );
+
+    test!(
+        kinds_to_schema_per_file,
+        JsonSchemaEmitter::new()
+            .with_naming(Naming::json_schema().with_strip_namespace(true))
+            .with_bundle(false),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+
+rel Hero hasAbility Ability {
+  @lore/cardinality @lore/many
+}
+
+rel Ability isUltimate @lore/Bool
+
+        "#
+    );
+
+    #[test]
+    fn undeclared_objects_to_defs() {
+        let src = r#"
+using dota:v2022
+
+kind Hero
+
+rel Hero mainName Name
+rel Hero homeTeam dota:v2022/teams/Team
+"#;
+        let mut store = lore_store::Store::new();
+        let store = store.add_from_string(src).unwrap();
+        let sources = JsonSchemaEmitter::new().translate(store).unwrap();
+        let document: Value = serde_json::from_str(sources.sources()[0].contents()).unwrap();
+
+        fn refs(value: &Value, found: &mut Vec<String>) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(reference)) = map.get("$ref") {
+                        found.push(reference.clone());
+                    }
+                    map.values().for_each(|value| refs(value, found));
+                }
+                Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+                _ => (),
+            }
+        }
+        let mut found = vec![];
+        refs(&document, &mut found);
+        assert_eq!(found.len(), 2);
+        for reference in found {
+            let def = reference.trim_start_matches("#/$defs/");
+            assert!(
+                document["$defs"].get(def).is_some(),
+                "{} is dangling",
+                reference
+            );
+        }
+
+        assert_snapshot!(sources.sources()[0].contents());
+    }
+}
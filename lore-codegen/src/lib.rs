@@ -3,12 +3,32 @@ pub use emitter_error::*;
 pub mod source_set;
 pub use source_set::*;
 
-// pub mod elixir;
-// pub mod erlang;
-// pub mod graphql;
+pub mod diagram;
+pub mod docs;
+pub mod elixir;
+pub mod erlang;
+pub mod graphql;
+pub mod jsonschema;
+pub mod markdown;
+pub mod naming;
 pub mod ocaml;
+pub mod protobuf;
+pub mod rescript;
+pub mod shacl;
+pub mod site;
+pub mod sql;
 
-// pub use elixir::ElixirEmitter;
-// pub use erlang::ErlangEmitter;
-// pub use graphql::GraphQLEmitter;
+pub use diagram::{DiagramEmitter, DiagramFormat};
+pub use docs::Docs;
+pub use elixir::ElixirEmitter;
+pub use erlang::ErlangEmitter;
+pub use graphql::GraphQLEmitter;
+pub use jsonschema::JsonSchemaEmitter;
+pub use markdown::MarkdownEmitter;
+pub use naming::Naming;
 pub use ocaml::OCamlEmitter;
+pub use protobuf::{FieldNumbers, ProtobufEmitter};
+pub use rescript::ReScriptEmitter;
+pub use shacl::ShaclEmitter;
+pub use site::SiteEmitter;
+pub use sql::{SqlDialect, SqlEmitter};
@@ -0,0 +1,341 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use lore_ast::Localized;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Keeps table cells on one line and stops text from closing them early.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+        .lines()
+        .collect::<Vec<&str>>()
+        .join("<br>")
+}
+
+/// The anchor most Markdown renderers derive from a heading like `### Hero`.
+fn anchor(heading: &str) -> String {
+    heading
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == ' ')
+        .map(|c| {
+            if c == ' ' {
+                '-'
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Where an entry is documented: its namespace file and the anchor within it.
+struct Location {
+    path: String,
+    anchor: String,
+}
+
+/// Emits one Markdown file per namespace with tables of its kinds, attributes, and relations,
+/// meant to be committed next to the ontology and diffed on review.
+pub struct MarkdownEmitter {
+    docs: Docs,
+}
+
+impl Default for MarkdownEmitter {
+    fn default() -> MarkdownEmitter {
+        MarkdownEmitter { docs: Docs::new() }
+    }
+}
+
+impl MarkdownEmitter {
+    pub fn new() -> MarkdownEmitter {
+        MarkdownEmitter::default()
+    }
+
+    /// The languages used for summaries in overview tables. Entries always show every language.
+    pub fn with_docs(self, docs: Docs) -> MarkdownEmitter {
+        MarkdownEmitter { docs }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let mut namespaces: BTreeMap<String, (Vec<&lore_ast::Kind>, Vec<&lore_ast::Attribute>)> =
+            BTreeMap::new();
+        let mut locations: HashMap<lore_ast::URI, Location> = HashMap::new();
+        for kind in store.kinds() {
+            namespaces
+                .entry(namespace(&kind.name))
+                .or_default()
+                .0
+                .push(kind);
+            locations.insert(kind.name.to_uri(), MarkdownEmitter::location(&kind.name));
+        }
+        for attr in store.attributes() {
+            namespaces
+                .entry(namespace(&attr.name))
+                .or_default()
+                .1
+                .push(attr);
+            locations.insert(attr.name.to_uri(), MarkdownEmitter::location(&attr.name));
+        }
+
+        let mut sources = vec![];
+        for (namespace, (kinds, attrs)) in namespaces {
+            let path = match kinds.first() {
+                Some(kind) => MarkdownEmitter::location(&kind.name).path,
+                None => MarkdownEmitter::location(&attrs[0].name).path,
+            };
+            let file = self.namespace_file(store, &namespace, &path, &kinds, &attrs, &locations);
+            sources.push(Source::new(PathBuf::from(path), file));
+        }
+
+        Ok(SourceSet::from_sources(sources))
+    }
+
+    /// `dota:v2022/Hero` is documented in `dota/v2022.md#hero`.
+    fn location(name: &lore_ast::Name) -> Location {
+        let segments = namespace_segments(name);
+        let path = if segments.is_empty() {
+            "ontology.md".to_string()
+        } else {
+            format!("{}.md", segments.join("/"))
+        };
+        Location {
+            path,
+            anchor: anchor(&local_name(name)),
+        }
+    }
+
+    fn namespace_file(
+        &self,
+        store: &lore_store::Store,
+        namespace: &str,
+        path: &str,
+        kinds: &[&lore_ast::Kind],
+        attrs: &[&lore_ast::Attribute],
+        locations: &HashMap<lore_ast::URI, Location>,
+    ) -> String {
+        let root = "../".repeat(path.matches('/').count());
+        let link = |name: &lore_ast::Name| match locations.get(&name.to_uri()) {
+            Some(location) if location.path == path => {
+                format!("[{}](#{})", local_name(name), location.anchor)
+            }
+            Some(location) => format!(
+                "[{}]({}{}#{})",
+                local_name(name),
+                root,
+                location.path,
+                location.anchor
+            ),
+            None => match lore_ast::Primitive::from_uri(&name.uri) {
+                Some(primitive) => format!("`{}`", primitive.name()),
+                None => format!("`{}`", name),
+            },
+        };
+
+        let title = if namespace.is_empty() {
+            "Ontology"
+        } else {
+            namespace
+        };
+        let mut out = format!("# `{}`\n", title);
+
+        for (heading, names) in [
+            (
+                "Kinds",
+                kinds
+                    .iter()
+                    .map(|kind| (&kind.name, &kind.fields))
+                    .collect(),
+            ),
+            (
+                "Attributes",
+                attrs
+                    .iter()
+                    .map(|attr| (&attr.name, &attr.fields))
+                    .collect(),
+            ),
+        ]
+            as [(&str, Vec<(&lore_ast::Name, &Vec<lore_ast::Field>)>); 2]
+        {
+            if names.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n## {}\n\n", heading));
+            out.push_str("| Name | Label | Documentation |\n");
+            out.push_str("| --- | --- | --- |\n");
+            for (name, fields) in names {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    link(name),
+                    cell(&self.docs.label(fields).unwrap_or_default()),
+                    cell(&self.docs.doc(fields).unwrap_or_default())
+                ));
+            }
+        }
+
+        for kind in kinds {
+            out.push_str(&format!("\n### {}\n\n", local_name(&kind.name)));
+            self.describe(&mut out, &kind.name, &kind.fields);
+
+            let variants = kind.variants();
+            if !variants.is_empty() {
+                out.push_str("\nVariants:\n\n");
+                for variant in variants {
+                    out.push_str(&format!("- {} (`{}`)\n", local_name(variant), variant));
+                }
+            }
+
+            if let Some(rels) = store.relations_by_subject.get(&kind.name.to_uri()) {
+                out.push_str("\n| Relation | Object | Cardinality | Documentation |\n");
+                out.push_str("| --- | --- | --- | --- |\n");
+                for rel in rels {
+                    out.push_str(&format!(
+                        "| `{}` | {} | `{}` | {} |\n",
+                        local_name(&rel.predicate),
+                        link(&rel.object),
+                        rel.cardinality(),
+                        cell(&self.docs.doc(&rel.fields).unwrap_or_default())
+                    ));
+                }
+            }
+
+            self.used_by(&mut out, store, &kind.name, &link);
+        }
+
+        for attr in attrs {
+            out.push_str(&format!("\n### {}\n\n", local_name(&attr.name)));
+            self.describe(&mut out, &attr.name, &attr.fields);
+            self.used_by(&mut out, store, &attr.name, &link);
+        }
+
+        out
+    }
+
+    /// The URI, then every label and doc in every language they were written in.
+    fn describe(&self, out: &mut String, name: &lore_ast::Name, fields: &[lore_ast::Field]) {
+        out.push_str(&format!("`{}`\n", name));
+        for (localized, heading) in [(Localized::Label, "Label"), (Localized::Doc, "")] {
+            for (lang, text) in localized.texts(fields) {
+                let text = text.lines().collect::<Vec<&str>>().join("\n> ");
+                out.push('\n');
+                match (heading, lang.as_str()) {
+                    ("", "") => out.push_str(&format!("> {}\n", text)),
+                    ("", lang) => out.push_str(&format!("> _({})_ {}\n", lang, text)),
+                    (heading, "") => out.push_str(&format!("**{}:** {}\n", heading, text)),
+                    (heading, lang) => {
+                        out.push_str(&format!("**{} ({}):** {}\n", heading, lang, text))
+                    }
+                }
+            }
+        }
+    }
+
+    fn used_by(
+        &self,
+        out: &mut String,
+        store: &lore_store::Store,
+        name: &lore_ast::Name,
+        link: &dyn Fn(&lore_ast::Name) -> String,
+    ) {
+        let mut incoming: Vec<&lore_ast::Relation> = store
+            .relations_by_subject
+            .values()
+            .flatten()
+            .filter(|rel| rel.object.uri == name.uri)
+            .collect();
+        if incoming.is_empty() {
+            return;
+        }
+        incoming.sort_by_key(|rel| (rel.subject.to_string(), rel.predicate.to_string()));
+        out.push_str("\nUsed by:\n\n");
+        for rel in incoming {
+            out.push_str(&format!(
+                "- {} `{}`\n",
+                link(&rel.subject),
+                local_name(&rel.predicate)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let sources = MarkdownEmitter::new().translate(&store).unwrap();
+                let files: Vec<String> = sources
+                    .sources()
+                    .iter()
+                    .map(|source| format!("== {}\n{}", source.name().display(), source.contents()))
+                    .collect();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{}
+"#,
+                    $src,
+                    files.join("\n")
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    test!(
+        namespaces,
+        r#"
+
+prefix lore:v1 as @lore
+prefix dota:v2022/items as @items
+
+using dota:v2022
+
+kind Hero {
+  @lore/label/en "Hero"
+  @lore/label/es "Héroe"
+  @lore/doc/en "A playable character.
+Picked at the start of a match."
+  @lore/doc/es "Un personaje jugable."
+}
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+}
+
+attr Name {
+  @lore/doc "A name | with a pipe."
+}
+
+rel Hero mainName Name
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+
+rel Hero hasItem @items/Item {
+  @lore/doc/en "Items in the inventory."
+  @lore/cardinality/min 0
+  @lore/cardinality/max 6
+}
+
+kind @items/Item {
+  @lore/doc/en "Something a hero can carry."
+}
+
+rel @items/Item boughtBy Hero {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
+}
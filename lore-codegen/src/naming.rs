@@ -0,0 +1,711 @@
+use crate::emitter_error::*;
+use std::collections::HashMap;
+
+/// The case convention used for a kind of identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// `main_name`
+    Snake,
+    /// `Main_name`, as OCaml and ReScript derive module names from file names
+    CapitalizedSnake,
+    /// `MainName`
+    Pascal,
+    /// `mainName`
+    Camel,
+    /// `MAIN_NAME`
+    ScreamingSnake,
+}
+
+impl Case {
+    pub fn apply(&self, words: &[String]) -> String {
+        let capitalize = |word: &String| {
+            let mut word = word.clone();
+            if let Some(first) = word.get_mut(0..1) {
+                first.make_ascii_uppercase();
+            }
+            word
+        };
+
+        match self {
+            Case::Snake => words.join("_"),
+            Case::CapitalizedSnake => capitalize(&words.join("_")),
+            Case::Pascal => words.iter().map(capitalize).collect(),
+            Case::Camel => match words.split_first() {
+                None => "".to_string(),
+                Some((first, rest)) => {
+                    let rest: String = rest.iter().map(capitalize).collect();
+                    format!("{}{}", first, rest)
+                }
+            },
+            Case::ScreamingSnake => words.join("_").to_ascii_uppercase(),
+        }
+    }
+}
+
+/// Splits an identifier into lowercase words at punctuation and at case changes, so that
+/// `mainName`, `main-name` and `MAIN_NAME` all become `["main", "name"]`. Runs of capitals are
+/// kept together (`URIValue` is `["uri", "value"]`), and so are letters followed by digits
+/// (`v2022`).
+pub fn words(identifier: &str) -> Vec<String> {
+    let chars: Vec<char> = identifier.chars().collect();
+    let mut words = vec![];
+    let mut current = String::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(current.clone());
+                current.clear();
+            }
+            continue;
+        }
+
+        let prev = if i > 0 { chars.get(i - 1) } else { None };
+        let next = chars.get(i + 1);
+        let starts_word = match prev {
+            Some(prev) if c.is_ascii_uppercase() => {
+                prev.is_ascii_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_ascii_uppercase()
+                        && next.map(|n| n.is_ascii_lowercase()).unwrap_or(false))
+            }
+            _ => false,
+        };
+
+        if starts_word && !current.is_empty() {
+            words.push(current.clone());
+            current.clear();
+        }
+        current.push(c.to_ascii_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// The namespace segments of a name: `dota:v2022/hello/Hero` is in `["dota", "v2022", "hello"]`.
+pub fn namespace_segments(name: &lore_ast::Name) -> Vec<String> {
+    let uri = name.to_string();
+    let namespace = match uri.rfind(['/', ':']) {
+        Some(i) => &uri[..i],
+        None => "",
+    };
+    namespace
+        .split(['/', ':'])
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+/// The namespace of a name as written: `dota:v2022/hello/Hero` is in `dota:v2022/hello`.
+pub fn namespace(name: &lore_ast::Name) -> String {
+    name.to_uri().namespace().to_string()
+}
+
+/// The last segment of a name: `dota:v2022/hello/Hero` is `Hero`.
+pub fn local_name(name: &lore_ast::Name) -> String {
+    name.to_uri().local_name()
+}
+
+/// How a target turns Lore names into identifiers. Every emitter gets one of these and asks it
+/// for type, module, and value names instead of mangling URIs on its own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Naming {
+    strip_namespace: bool,
+    type_case: Case,
+    value_case: Case,
+    keywords: &'static [&'static str],
+}
+
+impl Naming {
+    pub fn ocaml() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::CapitalizedSnake,
+            value_case: Case::Snake,
+            keywords: &[
+                "and",
+                "as",
+                "assert",
+                "asr",
+                "begin",
+                "class",
+                "constraint",
+                "do",
+                "done",
+                "downto",
+                "else",
+                "end",
+                "exception",
+                "external",
+                "false",
+                "for",
+                "fun",
+                "function",
+                "functor",
+                "if",
+                "in",
+                "include",
+                "inherit",
+                "initializer",
+                "land",
+                "lazy",
+                "let",
+                "lor",
+                "lsl",
+                "lsr",
+                "lxor",
+                "match",
+                "method",
+                "mod",
+                "module",
+                "mutable",
+                "new",
+                "nonrec",
+                "object",
+                "of",
+                "open",
+                "or",
+                "private",
+                "rec",
+                "sig",
+                "struct",
+                "then",
+                "to",
+                "true",
+                "try",
+                "type",
+                "val",
+                "virtual",
+                "when",
+                "while",
+                "with",
+            ],
+        }
+    }
+
+    pub fn rescript() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::CapitalizedSnake,
+            value_case: Case::Camel,
+            keywords: &[
+                "and",
+                "as",
+                "assert",
+                "async",
+                "await",
+                "constraint",
+                "else",
+                "exception",
+                "external",
+                "false",
+                "for",
+                "if",
+                "in",
+                "include",
+                "lazy",
+                "let",
+                "module",
+                "mutable",
+                "of",
+                "open",
+                "private",
+                "rec",
+                "switch",
+                "true",
+                "try",
+                "type",
+                "when",
+                "while",
+            ],
+        }
+    }
+
+    pub fn graphql() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::Pascal,
+            value_case: Case::Camel,
+            // built-in scalars, the scalars we declare for primitives, and the root types
+            keywords: &[
+                "String",
+                "Int",
+                "Float",
+                "Boolean",
+                "ID",
+                "Date",
+                "DateTime",
+                "URI",
+                "Query",
+                "Mutation",
+                "Subscription",
+            ],
+        }
+    }
+
+    pub fn erlang() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::Snake,
+            value_case: Case::Snake,
+            // reserved words, and built-in types that cannot be redefined
+            keywords: &[
+                "after",
+                "and",
+                "andalso",
+                "band",
+                "begin",
+                "bnot",
+                "bor",
+                "bsl",
+                "bsr",
+                "bxor",
+                "case",
+                "catch",
+                "cond",
+                "div",
+                "end",
+                "fun",
+                "if",
+                "let",
+                "not",
+                "of",
+                "or",
+                "orelse",
+                "receive",
+                "rem",
+                "try",
+                "when",
+                "xor",
+                "any",
+                "arity",
+                "atom",
+                "binary",
+                "bitstring",
+                "boolean",
+                "byte",
+                "char",
+                "float",
+                "function",
+                "identifier",
+                "integer",
+                "iodata",
+                "iolist",
+                "list",
+                "map",
+                "mfa",
+                "module",
+                "neg_integer",
+                "no_return",
+                "node",
+                "non_neg_integer",
+                "none",
+                "number",
+                "pid",
+                "port",
+                "pos_integer",
+                "reference",
+                "string",
+                "term",
+                "timeout",
+                "tuple",
+            ],
+        }
+    }
+
+    /// Names are only used as keys under `$defs` or as file names, so nothing is reserved.
+    pub fn json_schema() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::Pascal,
+            value_case: Case::Camel,
+            keywords: &[],
+        }
+    }
+
+    /// Messages live in a package named after their namespace, so they only need the last
+    /// segment of their URI.
+    pub fn protobuf() -> Naming {
+        Naming {
+            strip_namespace: true,
+            type_case: Case::Pascal,
+            value_case: Case::Snake,
+            // scalar types and the keywords that can start a field or a definition
+            keywords: &[
+                "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64",
+                "fixed32", "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes", "message",
+                "enum", "service", "rpc", "option", "optional", "repeated", "reserved", "oneof",
+                "map", "import", "package", "syntax", "extend",
+            ],
+        }
+    }
+
+    /// Tables, columns, and types are all lowercase, and reserved words are escaped instead of
+    /// quoted so the schema is easy to query by hand.
+    pub fn sql() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::Snake,
+            value_case: Case::Snake,
+            keywords: &[
+                "all",
+                "and",
+                "any",
+                "as",
+                "asc",
+                "between",
+                "by",
+                "case",
+                "check",
+                "collate",
+                "column",
+                "constraint",
+                "create",
+                "cross",
+                "current_date",
+                "current_time",
+                "current_timestamp",
+                "current_user",
+                "default",
+                "delete",
+                "desc",
+                "distinct",
+                "do",
+                "drop",
+                "else",
+                "end",
+                "except",
+                "exists",
+                "false",
+                "fetch",
+                "for",
+                "foreign",
+                "from",
+                "full",
+                "grant",
+                "group",
+                "having",
+                "in",
+                "index",
+                "inner",
+                "insert",
+                "intersect",
+                "into",
+                "is",
+                "join",
+                "key",
+                "left",
+                "like",
+                "limit",
+                "natural",
+                "not",
+                "null",
+                "offset",
+                "on",
+                "or",
+                "order",
+                "outer",
+                "primary",
+                "references",
+                "right",
+                "select",
+                "set",
+                "table",
+                "then",
+                "to",
+                "true",
+                "union",
+                "unique",
+                "update",
+                "user",
+                "using",
+                "values",
+                "when",
+                "where",
+                "window",
+                "with",
+            ],
+        }
+    }
+
+    /// Elixir modules are nested under the library's root module, so they cannot shadow the
+    /// standard library and need no escaping.
+    pub fn elixir() -> Naming {
+        Naming {
+            strip_namespace: false,
+            type_case: Case::Pascal,
+            value_case: Case::Snake,
+            keywords: &[],
+        }
+    }
+
+    /// Use only the last segment of every URI (`dota:v2022/Hero` becomes `Hero`) instead of the
+    /// whole URI. Names from different namespaces may then collide, which is reported as an
+    /// error.
+    pub fn with_strip_namespace(self, strip_namespace: bool) -> Naming {
+        Naming {
+            strip_namespace,
+            ..self
+        }
+    }
+
+    pub fn strips_namespace(&self) -> bool {
+        self.strip_namespace
+    }
+
+    fn words_of(&self, name: &lore_ast::Name) -> Vec<String> {
+        if self.strip_namespace {
+            self.words_of_str(&local_name(name))
+        } else {
+            self.words_of_str(&name.to_string())
+        }
+    }
+
+    fn words_of_str(&self, identifier: &str) -> Vec<String> {
+        let mut words = words(identifier);
+        if words
+            .first()
+            .map(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(true)
+        {
+            words.insert(0, "lore".to_string());
+        }
+        words
+    }
+
+    fn escape(&self, identifier: String) -> String {
+        if self.keywords.contains(&identifier.as_str()) {
+            format!("{}_", identifier)
+        } else {
+            identifier
+        }
+    }
+
+    /// The name of the type or module generated for a kind or an attribute.
+    pub fn type_name(&self, name: &lore_ast::Name) -> String {
+        self.escape(self.type_case.apply(&self.words_of(name)))
+    }
+
+    /// The name of the module for a single namespace segment, like `V2022` in `Dota.V2022`.
+    pub fn segment_name(&self, segment: &str) -> String {
+        self.escape(self.type_case.apply(&self.words_of_str(segment)))
+    }
+
+    /// The name of the module that gathers everything in a namespace, like `Dota_v2022`. It is
+    /// always built from the full namespace, so it is unique within a library.
+    pub fn namespace_name(&self, segments: &[String]) -> String {
+        self.escape(
+            self.type_case
+                .apply(&self.words_of_str(&segments.join("/"))),
+        )
+    }
+
+    /// The name of a package for a single namespace segment, like `v2022` in `dota.v2022`.
+    pub fn package_segment_name(&self, segment: &str) -> String {
+        self.escape(self.value_case.apply(&self.words_of_str(segment)))
+    }
+
+    /// The name of a field, accessor, or other value generated for a relation.
+    pub fn value_name(&self, name: &lore_ast::Name) -> String {
+        self.escape(self.value_case.apply(&self.words_of(name)))
+    }
+}
+
+/// The identifiers already handed out in one namespace of the generated code, like the modules
+/// of a library or the fields of a record.
+#[derive(Clone, Default)]
+pub struct Scope<'a> {
+    claimed: HashMap<String, Option<lore_ast::Name>>,
+    /// Where the names were declared, so collisions name the earlier declaration first.
+    store: Option<&'a lore_store::Store>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new() -> Scope<'a> {
+        Scope::default()
+    }
+
+    pub fn for_store(store: &'a lore_store::Store) -> Scope<'a> {
+        Scope {
+            store: Some(store),
+            ..Scope::default()
+        }
+    }
+
+    /// Keep an identifier for something the emitter generates on its own, like `make`.
+    pub fn reserve(mut self, identifier: &str) -> Scope<'a> {
+        self.claimed.insert(identifier.to_string(), None);
+        self
+    }
+
+    pub fn claim(
+        &mut self,
+        identifier: String,
+        name: &lore_ast::Name,
+    ) -> Result<String, EmitterError> {
+        match self.claimed.get(&identifier) {
+            Some(Some(claimed)) if claimed == name => Ok(identifier),
+            Some(Some(claimed)) => {
+                let (first, second) = if self.declared_before(name, claimed) {
+                    (name, claimed)
+                } else {
+                    (claimed, name)
+                };
+                Err(EmitterError::NameCollision {
+                    identifier,
+                    first: first.to_string(),
+                    second: second.to_string(),
+                })
+            }
+            Some(None) => Err(EmitterError::ReservedName {
+                identifier,
+                name: name.to_string(),
+            }),
+            None => {
+                self.claimed.insert(identifier.clone(), Some(name.clone()));
+                Ok(identifier)
+            }
+        }
+    }
+
+    fn declared_before(&self, a: &lore_ast::Name, b: &lore_ast::Name) -> bool {
+        let order = |name: &lore_ast::Name| {
+            self.store
+                .and_then(|store| store.declaration_order(&name.uri))
+        };
+        match (order(a), order(b)) {
+            (Some(a), Some(b)) => a < b,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(uri: &str) -> lore_ast::Name {
+        lore_ast::Name::of_uri(&lore_ast::URI::from_string(uri.to_string()))
+    }
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(words("mainName"), vec!["main", "name"]);
+        assert_eq!(words("URIValue"), vec!["uri", "value"]);
+        assert_eq!(
+            words("dota:v2022/hasAbility"),
+            vec!["dota", "v2022", "has", "ability"]
+        );
+        assert_eq!(words("movement-speed"), vec!["movement", "speed"]);
+    }
+
+    #[test]
+    fn applies_cases_per_target() {
+        let hero = name("dota:v2022/mainName");
+        assert_eq!(Naming::ocaml().type_name(&hero), "Dota_v2022_main_name");
+        assert_eq!(Naming::ocaml().value_name(&hero), "dota_v2022_main_name");
+        assert_eq!(Naming::rescript().value_name(&hero), "dotaV2022MainName");
+        assert_eq!(Naming::graphql().type_name(&hero), "DotaV2022MainName");
+        assert_eq!(Naming::protobuf().type_name(&hero), "MainName");
+        assert_eq!(Naming::protobuf().value_name(&hero), "main_name");
+        assert_eq!(Case::ScreamingSnake.apply(&words("mainName")), "MAIN_NAME");
+
+        let naming = Naming::ocaml().with_strip_namespace(true);
+        assert_eq!(naming.type_name(&hero), "Main_name");
+        assert_eq!(naming.value_name(&hero), "main_name");
+    }
+
+    #[test]
+    fn splits_namespaces() {
+        let world = name("dota:v2022/hello/world/Hero");
+        assert_eq!(
+            namespace_segments(&world),
+            vec!["dota", "v2022", "hello", "world"]
+        );
+        assert_eq!(local_name(&world), "Hero");
+        assert_eq!(namespace(&world), "dota:v2022/hello/world");
+        assert_eq!(
+            Naming::ocaml().namespace_name(&namespace_segments(&world)),
+            "Dota_v2022_hello_world"
+        );
+        assert_eq!(Naming::ocaml().segment_name("v2022"), "V2022");
+        assert_eq!(Naming::protobuf().package_segment_name("2022"), "lore_2022");
+    }
+
+    #[test]
+    fn escapes_keywords() {
+        let naming = Naming::ocaml().with_strip_namespace(true);
+        assert_eq!(naming.value_name(&name("dota:v2022/type")), "type_");
+        assert_eq!(
+            Naming::graphql()
+                .with_strip_namespace(true)
+                .type_name(&name("dota:v2022/Date")),
+            "Date_"
+        );
+        assert_eq!(
+            Naming::erlang()
+                .with_strip_namespace(true)
+                .type_name(&name("dota:v2022/string")),
+            "string_"
+        );
+    }
+
+    #[test]
+    fn detects_collisions() {
+        let naming = Naming::ocaml().with_strip_namespace(true);
+        let mut scope = Scope::new().reserve("make");
+
+        let hero = name("dota:v2022/Hero");
+        let other_hero = name("dota:v2023/Hero");
+
+        assert!(scope.claim(naming.type_name(&hero), &hero).is_ok());
+        assert!(scope.claim(naming.type_name(&hero), &hero).is_ok());
+        assert_eq!(
+            scope
+                .claim(naming.type_name(&other_hero), &other_hero)
+                .unwrap_err()
+                .to_string(),
+            "The identifier `Hero` would be generated for both `dota:v2022/Hero` and `dota:v2023/Hero`"
+        );
+        assert_eq!(
+            scope
+                .claim(
+                    naming.value_name(&name("dota:v2022/make")),
+                    &name("dota:v2022/make")
+                )
+                .unwrap_err()
+                .to_string(),
+            "The identifier `make` for `dota:v2022/make` is already used by generated code"
+        );
+    }
+
+    #[test]
+    fn collisions_follow_declaration_order() {
+        let mut store = lore_store::Store::new();
+        store
+            .add_from_string("using dota:v2023\nkind Hero\nkind dota:v2022/Hero\n")
+            .unwrap();
+        let naming = Naming::ocaml().with_strip_namespace(true);
+        let mut scope = Scope::for_store(&store);
+
+        let hero = name("dota:v2022/Hero");
+        let other_hero = name("dota:v2023/Hero");
+
+        assert!(scope.claim(naming.type_name(&hero), &hero).is_ok());
+        assert_eq!(
+            scope
+                .claim(naming.type_name(&other_hero), &other_hero)
+                .unwrap_err(),
+            EmitterError::NameCollision {
+                identifier: "Hero".to_string(),
+                first: "dota:v2023/Hero".to_string(),
+                second: "dota:v2022/Hero".to_string(),
+            }
+        );
+    }
+}
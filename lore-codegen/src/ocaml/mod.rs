@@ -1,5 +1,6 @@
 pub mod ocaml_ast;
 pub mod ocaml_emitter;
+pub mod ocaml_json;
 
 pub use ocaml_ast::*;
 pub use ocaml_emitter::*;
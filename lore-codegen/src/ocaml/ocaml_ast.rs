@@ -8,31 +8,20 @@ use std::path::PathBuf;
 ///
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct CamlModuleName(String);
+pub struct CamlModuleName(pub(crate) String);
 
 impl CamlModuleName {
+    pub fn new(name: String) -> CamlModuleName {
+        CamlModuleName(name)
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.0 == ""
+        self.0.is_empty()
     }
 
     pub fn local_module() -> CamlModuleName {
         CamlModuleName("".to_string())
     }
-
-    pub fn from_name(name: &lore_ast::Name) -> CamlModuleName {
-        let mut parts = vec![];
-        for part in name.to_string().replace("/", ":").split(":") {
-            parts.push(part.to_string())
-        }
-
-        let mut name = parts.join("_").to_lowercase();
-
-        if let Some(first) = name.get_mut(0..1) {
-            first.make_ascii_uppercase();
-        };
-
-        CamlModuleName(name)
-    }
 }
 
 impl Display for CamlModuleName {
@@ -43,14 +32,14 @@ impl Display for CamlModuleName {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CamlModule {
-    name: CamlModuleName,
-    filename: PathBuf,
-    structure: Vec<CamlValue>,
+    pub(crate) name: CamlModuleName,
+    pub(crate) filename: PathBuf,
+    pub(crate) structure: Vec<CamlValue>,
 }
 
-impl Into<Source> for CamlModule {
-    fn into(self) -> Source {
-        Source::new(self.filename.clone(), format!("{}", self))
+impl From<CamlModule> for Source {
+    fn from(val: CamlModule) -> Self {
+        Source::new(val.filename.clone(), format!("{}", val))
     }
 }
 
@@ -74,6 +63,15 @@ impl CamlModule {
         }
     }
 
+    pub fn implementation(name: CamlModuleName) -> CamlModule {
+        let filename = PathBuf::from(format!("{}.ml", name.to_string().to_lowercase()));
+        CamlModule {
+            name,
+            filename,
+            structure: vec![],
+        }
+    }
+
     pub fn with_structure(self, structure: Vec<CamlValue>) -> CamlModule {
         CamlModule { structure, ..self }
     }
@@ -86,9 +84,9 @@ impl CamlModule {
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CamlField {
-    name: String,
-    doc: Option<String>,
-    type_: CamlType,
+    pub(crate) name: String,
+    pub(crate) doc: Option<String>,
+    pub(crate) type_: CamlType,
 }
 
 impl Display for CamlField {
@@ -101,15 +99,7 @@ impl Display for CamlField {
 }
 
 impl CamlField {
-    pub fn from_name(name: &lore_ast::Name, type_: CamlType) -> CamlField {
-        let mut parts = vec![];
-        for part in name.to_string().replace("/", ":").split(":") {
-            parts.push(part.to_string())
-        }
-        let mut name = parts.join("_");
-        if let Some(first) = name.get_mut(0..1) {
-            first.make_ascii_lowercase();
-        };
+    pub fn new(name: String, type_: CamlType) -> CamlField {
         CamlField {
             name,
             type_,
@@ -124,13 +114,13 @@ impl CamlField {
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CamlRecord {
-    fields: Vec<CamlField>,
+    pub(crate) fields: Vec<CamlField>,
 }
 
 impl Display for CamlRecord {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         let mut fields = self.fields.clone();
-        fields.sort_by(|a, b| a.cmp(b));
+        fields.sort();
 
         write!(f, "{{ {}", fields[0])?;
         for field in fields[1..].iter() {
@@ -174,6 +164,19 @@ pub enum CamlType {
         type_name: String,
     },
     Abstract(String),
+    Alias {
+        name: String,
+        type_: Box<CamlType>,
+    },
+    Labeled {
+        label: String,
+        type_: Box<CamlType>,
+    },
+    Apply {
+        constructor: String,
+        arg: Box<CamlType>,
+    },
+    Tuple(Vec<CamlType>),
     Variant {
         name: String,
         variants: Vec<CamlVariant>,
@@ -192,10 +195,57 @@ impl CamlType {
         }
     }
 
+    pub fn primitive(primitive: lore_ast::Primitive) -> CamlType {
+        let type_name = match primitive {
+            lore_ast::Primitive::Int => "int",
+            lore_ast::Primitive::Float => "float",
+            lore_ast::Primitive::Bool => "bool",
+            lore_ast::Primitive::String
+            | lore_ast::Primitive::Date
+            | lore_ast::Primitive::DateTime
+            | lore_ast::Primitive::URI => "string",
+        };
+        CamlType::reference(CamlModuleName::local_module(), type_name.to_string())
+    }
+
+    pub fn apply(constructor: &str, arg: CamlType) -> CamlType {
+        CamlType::Apply {
+            constructor: constructor.to_string(),
+            arg: Box::new(arg),
+        }
+    }
+
+    /// Wraps the type of a relation's object according to the relation's cardinality. Non-empty
+    /// lists are a pair of the first element and the rest.
+    pub fn with_multiplicity(self, multiplicity: lore_ast::Multiplicity) -> CamlType {
+        match multiplicity {
+            lore_ast::Multiplicity::One => self,
+            lore_ast::Multiplicity::Optional => CamlType::apply("option", self),
+            lore_ast::Multiplicity::List => CamlType::apply("list", self),
+            lore_ast::Multiplicity::NonEmptyList => {
+                CamlType::Tuple(vec![self.clone(), CamlType::apply("list", self)])
+            }
+        }
+    }
+
     pub fn abstract_type(name: String) -> CamlType {
         CamlType::Abstract(name)
     }
 
+    pub fn alias(name: String, type_: CamlType) -> CamlType {
+        CamlType::Alias {
+            name,
+            type_: Box::new(type_),
+        }
+    }
+
+    pub fn labeled(label: String, type_: CamlType) -> CamlType {
+        CamlType::Labeled {
+            label,
+            type_: Box::new(type_),
+        }
+    }
+
     pub fn record(name: String, fields: Vec<CamlField>) -> CamlType {
         CamlType::Record {
             name,
@@ -218,11 +268,21 @@ impl Display for CamlType {
                 type_name,
             } => write!(f, "{}.{}", module_path, type_name),
             CamlType::Abstract(name) => write!(f, "type {}", name),
+            CamlType::Alias { name, type_ } => write!(f, "type {} = {}", name, type_),
+            CamlType::Labeled { label, type_ } => write!(f, "{}:{}", label, type_),
+            CamlType::Apply { constructor, arg } => match arg.as_ref() {
+                CamlType::Tuple(_) => write!(f, "({}) {}", arg, constructor),
+                _ => write!(f, "{} {}", arg, constructor),
+            },
+            CamlType::Tuple(types) => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", types.join(" * "))
+            }
             CamlType::Record { name, record } => {
-                write!(f, "type {} = {} \n", name, record)
+                writeln!(f, "type {} = {} ", name, record)
             }
             CamlType::Variant { name, variants } => {
-                write!(f, "type {} = \n", name)?;
+                writeln!(f, "type {} = ", name)?;
                 for v in variants {
                     write!(f, "| {}", v)?;
                 }
@@ -238,8 +298,8 @@ impl Display for CamlType {
 ///
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CamlFun {
-    args: Vec<CamlType>,
-    return_: CamlType,
+    pub(crate) args: Vec<CamlType>,
+    pub(crate) return_: CamlType,
 }
 
 impl CamlFun {
@@ -264,20 +324,12 @@ impl Display for CamlFun {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CamlBinding {
-    name: String,
-    val: CamlFun,
+    pub(crate) name: String,
+    pub(crate) val: CamlFun,
 }
 
 impl CamlBinding {
-    pub fn bind(name: lore_ast::Name, val: CamlFun) -> CamlBinding {
-        let mut parts = vec![];
-        for part in name.to_string().replace("/", ":").split(":") {
-            parts.push(part.to_string())
-        }
-        let mut name = parts.join("_");
-        if let Some(first) = name.get_mut(0..1) {
-            first.make_ascii_lowercase();
-        };
+    pub fn named(name: String, val: CamlFun) -> CamlBinding {
         CamlBinding { name, val }
     }
 }
@@ -288,6 +340,209 @@ impl Display for CamlBinding {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////////////////////////
+///
+/// An OCaml Expression
+///
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CamlExpr {
+    Var(String),
+    String(String),
+    Field {
+        expr: Box<CamlExpr>,
+        field: String,
+    },
+    Record(Vec<(String, CamlExpr)>),
+    Tuple(Vec<CamlExpr>),
+    List(Vec<CamlExpr>),
+    Apply {
+        fun: Box<CamlExpr>,
+        args: Vec<CamlExpr>,
+    },
+    PolyVariant {
+        tag: String,
+        arg: Box<CamlExpr>,
+    },
+    LetOp {
+        op: String,
+        name: String,
+        value: Box<CamlExpr>,
+        body: Box<CamlExpr>,
+    },
+    LetOpen {
+        module_path: CamlModuleName,
+        body: Box<CamlExpr>,
+    },
+}
+
+impl CamlExpr {
+    pub fn var(name: String) -> CamlExpr {
+        CamlExpr::Var(name)
+    }
+
+    pub fn field(self, field: String) -> CamlExpr {
+        CamlExpr::Field {
+            expr: Box::new(self),
+            field,
+        }
+    }
+
+    pub fn record(fields: Vec<(String, CamlExpr)>) -> CamlExpr {
+        CamlExpr::Record(fields)
+    }
+
+    pub fn string(s: String) -> CamlExpr {
+        CamlExpr::String(s)
+    }
+
+    pub fn tuple(exprs: Vec<CamlExpr>) -> CamlExpr {
+        CamlExpr::Tuple(exprs)
+    }
+
+    pub fn list(exprs: Vec<CamlExpr>) -> CamlExpr {
+        CamlExpr::List(exprs)
+    }
+
+    pub fn apply(self, args: Vec<CamlExpr>) -> CamlExpr {
+        CamlExpr::Apply {
+            fun: Box::new(self),
+            args,
+        }
+    }
+
+    pub fn poly_variant(tag: String, arg: CamlExpr) -> CamlExpr {
+        CamlExpr::PolyVariant {
+            tag,
+            arg: Box::new(arg),
+        }
+    }
+
+    pub fn let_op(op: String, name: String, value: CamlExpr, body: CamlExpr) -> CamlExpr {
+        CamlExpr::LetOp {
+            op,
+            name,
+            value: Box::new(value),
+            body: Box::new(body),
+        }
+    }
+
+    pub fn let_open(module_path: CamlModuleName, body: CamlExpr) -> CamlExpr {
+        CamlExpr::LetOpen {
+            module_path,
+            body: Box::new(body),
+        }
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        matches!(self, CamlExpr::LetOp { .. } | CamlExpr::LetOpen { .. })
+    }
+
+    pub fn is_atomic(&self) -> bool {
+        !matches!(
+            self,
+            CamlExpr::Apply { .. }
+                | CamlExpr::PolyVariant { .. }
+                | CamlExpr::LetOp { .. }
+                | CamlExpr::LetOpen { .. }
+        )
+    }
+}
+
+impl Display for CamlExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CamlExpr::Var(name) => write!(f, "{}", name),
+            CamlExpr::String(s) => write!(f, "{:?}", s),
+            CamlExpr::Field { expr, field } => write!(f, "{}.{}", expr, field),
+            CamlExpr::Record(fields) => {
+                write!(f, "{{ {} = {}", fields[0].0, fields[0].1)?;
+                for (name, expr) in fields[1..].iter() {
+                    write!(f, "; {} = {}", name, expr)?;
+                }
+                write!(f, " }}")
+            }
+            CamlExpr::Tuple(exprs) => {
+                let exprs: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", exprs.join(", "))
+            }
+            CamlExpr::List(exprs) => {
+                let exprs: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "[ {} ]", exprs.join("; "))
+            }
+            CamlExpr::Apply { fun, args } => {
+                write!(f, "{}", fun)?;
+                for arg in args {
+                    if arg.is_atomic() {
+                        write!(f, " {}", arg)?;
+                    } else {
+                        write!(f, " ({})", arg)?;
+                    }
+                }
+                Ok(())
+            }
+            CamlExpr::PolyVariant { tag, arg } if arg.is_atomic() => write!(f, "`{} {}", tag, arg),
+            CamlExpr::PolyVariant { tag, arg } => write!(f, "`{} ({})", tag, arg),
+            CamlExpr::LetOp {
+                op,
+                name,
+                value,
+                body,
+            } => write!(f, "{} {} = {} in\n  {}", op, name, value, body),
+            CamlExpr::LetOpen { module_path, body } => {
+                write!(f, "let open {} in\n  {}", module_path, body)
+            }
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+///
+/// An OCaml Let Binding
+///
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CamlLet {
+    pub(crate) name: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) body: CamlExpr,
+    pub(crate) recursive: bool,
+}
+
+impl CamlLet {
+    pub fn named(name: String, args: Vec<String>, body: CamlExpr) -> CamlLet {
+        CamlLet {
+            name,
+            args,
+            body,
+            recursive: false,
+        }
+    }
+
+    /// Lets the body call the function being defined, as `let rec`.
+    pub fn with_recursive(self, recursive: bool) -> CamlLet {
+        CamlLet { recursive, ..self }
+    }
+}
+
+impl Display for CamlLet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if self.recursive {
+            write!(f, "let rec {}", self.name)?;
+        } else {
+            write!(f, "let {}", self.name)?;
+        }
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        if self.body.is_multiline() {
+            write!(f, " =\n  {}", self.body)
+        } else {
+            write!(f, " = {}", self.body)
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 ///
 /// An OCaml Value
@@ -296,24 +551,31 @@ impl Display for CamlBinding {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CamlValueDesc {
     Module(CamlModule),
+    Alias {
+        name: CamlModuleName,
+        target: CamlModuleName,
+    },
     Type(CamlType),
     Binding(CamlBinding),
+    Let(CamlLet),
 }
 
 impl Display for CamlValueDesc {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
             CamlValueDesc::Module(m) => write!(f, "{}", m),
+            CamlValueDesc::Alias { name, target } => write!(f, "module {} = {}", name, target),
             CamlValueDesc::Type(t) => write!(f, "{}", t),
             CamlValueDesc::Binding(b) => write!(f, "{}", b),
+            CamlValueDesc::Let(l) => write!(f, "{}", l),
         }
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CamlValue {
-    value: CamlValueDesc,
-    doc: Option<String>,
+    pub(crate) value: CamlValueDesc,
+    pub(crate) doc: Option<String>,
 }
 
 impl CamlValue {
@@ -324,6 +586,20 @@ impl CamlValue {
         }
     }
 
+    pub fn definition(l: CamlLet) -> CamlValue {
+        CamlValue {
+            value: CamlValueDesc::Let(l),
+            doc: None,
+        }
+    }
+
+    pub fn module_alias(name: CamlModuleName, target: CamlModuleName) -> CamlValue {
+        CamlValue {
+            value: CamlValueDesc::Alias { name, target },
+            doc: None,
+        }
+    }
+
     pub fn new_type(t: CamlType) -> CamlValue {
         CamlValue {
             value: CamlValueDesc::Type(t),
@@ -1,9 +1,29 @@
+use crate::docs::*;
 use crate::emitter_error::*;
+use crate::naming::*;
 use crate::ocaml::ocaml_ast::*;
+use crate::ocaml::ocaml_json;
 use crate::source_set::*;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
-#[derive(Default)]
-pub struct OCamlEmitter {}
+pub struct OCamlEmitter {
+    library_name: String,
+    naming: Naming,
+    docs: Docs,
+    json: bool,
+}
+
+impl Default for OCamlEmitter {
+    fn default() -> OCamlEmitter {
+        OCamlEmitter {
+            library_name: "ontology".to_string(),
+            naming: Naming::ocaml(),
+            docs: Docs::new(),
+            json: false,
+        }
+    }
+}
 
 // impl LoreEmitter<Document> for OCamlEmitter {
 impl OCamlEmitter {
@@ -11,94 +31,294 @@ impl OCamlEmitter {
         OCamlEmitter::default()
     }
 
+    pub fn with_library_name(self, library_name: String) -> OCamlEmitter {
+        OCamlEmitter {
+            library_name,
+            ..self
+        }
+    }
+
+    pub fn with_naming(self, naming: Naming) -> OCamlEmitter {
+        OCamlEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> OCamlEmitter {
+        OCamlEmitter { docs, ..self }
+    }
+
+    /// Also generate Yojson-based `to_json`/`of_json` functions for every module, along with a
+    /// round-trip test suite for them.
+    pub fn with_json(self, json: bool) -> OCamlEmitter {
+        OCamlEmitter { json, ..self }
+    }
+
     pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
         let mut sources = vec![];
+        let mut module_names = vec![];
 
-        for attribute in store.attributes() {
-            let mut fields = vec![];
-            let subject = attribute.name.to_uri();
-            if let Some(rels) = store.relations_by_subject.get(&subject) {
-                for rel in rels {
-                    let module_name = CamlModuleName::from_name(&rel.object);
-                    let type_ref = CamlType::reference(module_name, "t".to_string());
-                    let field = CamlField::from_name(&rel.predicate, type_ref);
-                    fields.push(field);
-                }
-            };
+        for (name, interface, implementation) in
+            module_pairs(store, &self.naming, &self.docs, self.json)?
+        {
+            module_names.push((name, interface.name.clone()));
+            sources.push(interface.into());
+            sources.push(implementation.into());
+        }
 
-            let type_name = "t".to_string();
-            let main_type = if fields.is_empty() {
-                CamlType::abstract_type(type_name)
-            } else {
-                CamlType::record(type_name, fields)
-            };
+        for namespace in namespace_modules(&self.naming, &module_names)? {
+            sources.push(namespace.into());
+        }
 
-            let module = CamlModule::new(CamlModuleName::from_name(&attribute.name))
-                .with_structure(vec![CamlValue::new_type(main_type)]);
-            sources.push(module.into())
+        if self.json {
+            sources.push(ocaml_json::runtime());
+            sources.extend(ocaml_json::round_trip_tests(
+                store,
+                &module_names,
+                &self.library_name,
+            ));
+            sources.push(Source::new(
+                PathBuf::from("dune"),
+                format!(
+                    "(library\n (name {})\n (libraries yojson))\n",
+                    self.library_name
+                ),
+            ));
+        } else {
+            sources.push(Source::new(
+                PathBuf::from("dune"),
+                format!("(library\n (name {}))\n", self.library_name),
+            ));
         }
 
-        for kind in store.kinds() {
-            let mut doc_string = None;
-            let mut fields = vec![];
+        Ok(SourceSet::from_sources(sources))
+    }
+}
+
+/// Builds the interface and the implementation of a module for every attribute and kind in the
+/// store. The ReScript emitter prints these same modules with its own syntax.
+pub(crate) fn module_pairs(
+    store: &lore_store::Store,
+    naming: &Naming,
+    docs: &Docs,
+    json: bool,
+) -> Result<Vec<(lore_ast::Name, CamlModule, CamlModule)>, EmitterError> {
+    let mut modules = vec![];
+    let mut module_scope = Scope::for_store(store).reserve("Lore_json");
+
+    let names = store
+        .attributes()
+        .into_iter()
+        .map(|attr| (&attr.name, &attr.fields))
+        .chain(
+            store
+                .kinds()
+                .into_iter()
+                .map(|kind| (&kind.name, &kind.fields)),
+        );
+
+    for (name, fields) in names {
+        let module_name = CamlModuleName::new(module_scope.claim(naming.type_name(name), name)?);
+        let (interface, implementation) =
+            module_pair(store, naming, docs, module_name, name, fields, json)?;
+        modules.push((name.clone(), interface, implementation));
+    }
+
+    Ok(modules)
+}
 
-            let mut accessors = vec![];
+/// Builds one module per namespace that re-exports what it contains under short names, so
+/// `dota:v2022/hello/Hero` is also reachable as `Dota.V2022.Hello.Hero`. Every kind and
+/// attribute keeps its own top-level module, which is what these aliases point to.
+pub(crate) fn namespace_modules(
+    naming: &Naming,
+    modules: &[(lore_ast::Name, CamlModuleName)],
+) -> Result<Vec<CamlModule>, EmitterError> {
+    let short_names = naming.clone().with_strip_namespace(true);
 
-            for field in &kind.fields {
-                if field.name.uri == lore_ast::URI::from_string("lore:v1/doc/en".to_string()) {
-                    doc_string = Some(field.value.to_string())
-                };
+    let mut module_scope = Scope::new();
+    for (name, module_name) in modules {
+        module_scope.claim(module_name.to_string(), name)?;
+    }
+
+    let mut namespaces: BTreeMap<Vec<String>, (Scope, BTreeMap<String, CamlModuleName>)> =
+        BTreeMap::new();
+
+    for (name, module_name) in modules {
+        let segments = namespace_segments(name);
+        if segments.is_empty() {
+            continue;
+        }
+
+        for depth in 1..=segments.len() {
+            let path = segments[..depth].to_vec();
+            let namespace = lore_ast::Name::of_uri(&lore_ast::URI::from_string(path.join("/")));
+            let namespace_module =
+                CamlModuleName::new(module_scope.claim(naming.namespace_name(&path), &namespace)?);
+
+            if depth > 1 {
+                let (scope, members) = namespaces
+                    .entry(segments[..depth - 1].to_vec())
+                    .or_default();
+                let segment = scope.claim(naming.segment_name(&segments[depth - 1]), &namespace)?;
+                members.insert(segment, namespace_module);
+            } else {
+                namespaces.entry(path).or_default();
             }
+        }
 
-            let subject = kind.name.to_uri();
-            if let Some(rels) = store.relations_by_subject.get(&subject) {
-                for rel in rels {
-                    let module_name = CamlModuleName::from_name(&rel.object);
-                    let type_ref = CamlType::reference(module_name, "t".to_string());
-
-                    let doc = rel
-                        .fields
-                        .iter()
-                        .find(|f| {
-                            f.name.uri == lore_ast::URI::from_string("lore:v1/doc/en".to_string())
-                        })
-                        .map(|f| f.value.to_string());
-
-                    let accessor = CamlValue::binding(CamlBinding::bind(
-                        rel.predicate.clone(),
-                        CamlFun::new(
-                            vec![CamlType::reference(
-                                CamlModuleName::local_module(),
-                                "t".to_string(),
-                            )],
-                            type_ref.clone(),
-                        ),
-                    ))
-                    .with_doc(doc);
-
-                    accessors.push(accessor);
+        let (scope, members) = namespaces.entry(segments).or_default();
+        let short_name = scope.claim(short_names.type_name(name), name)?;
+        members.insert(short_name, module_name.clone());
+    }
+
+    Ok(namespaces
+        .into_iter()
+        .map(|(path, (_, members))| {
+            let structure = members
+                .into_iter()
+                .map(|(short_name, target)| {
+                    CamlValue::module_alias(CamlModuleName::new(short_name), target)
+                })
+                .collect();
+            CamlModule::implementation(CamlModuleName::new(naming.namespace_name(&path)))
+                .with_structure(structure)
+        })
+        .collect())
+}
+
+fn module_pair(
+    store: &lore_store::Store,
+    naming: &Naming,
+    docs: &Docs,
+    module_name: CamlModuleName,
+    name: &lore_ast::Name,
+    fields: &[lore_ast::Field],
+    json: bool,
+) -> Result<(CamlModule, CamlModule), EmitterError> {
+    let local_t = CamlType::reference(CamlModuleName::local_module(), "t".to_string());
+
+    let mut record_fields = vec![];
+    let mut make_args = vec![];
+    let mut make_params = vec![];
+    let mut make_body = vec![];
+    let mut accessors = vec![];
+    let mut accessor_impls = vec![];
+    let mut json_fields = vec![];
+    let mut recursive = false;
+    // the JSON decoder binds every field by name next to these helpers
+    let mut field_scope = Scope::for_store(store)
+        .reserve("make")
+        .reserve("to_json")
+        .reserve("of_json")
+        .reserve("assoc")
+        .reserve("field")
+        .reserve("optional_field")
+        .reserve("fields")
+        .reserve("json");
+
+    if let Some(rels) = store.relations_by_subject.get(&name.to_uri()) {
+        for rel in rels {
+            let doc = docs.doc(&rel.fields);
+            let key = rel.predicate.to_string();
+            let field_name =
+                field_scope.claim(naming.value_name(&rel.predicate), &rel.predicate)?;
+
+            let (type_ref, json_field) = match lore_ast::Primitive::from_uri(&rel.object.uri) {
+                Some(primitive) => (
+                    CamlType::primitive(primitive),
+                    ocaml_json::JsonField::for_primitive(key, field_name.clone(), primitive),
+                ),
+                // a module cannot name itself, so a relation back to it uses what it is defining
+                None if rel.object.to_uri() == name.to_uri() => {
+                    recursive = true;
+                    (
+                        local_t.clone(),
+                        ocaml_json::JsonField::for_self(key, field_name.clone()),
+                    )
+                }
+                None => {
+                    let module_name = CamlModuleName::new(naming.type_name(&rel.object));
+                    (
+                        CamlType::reference(module_name.clone(), "t".to_string()),
+                        ocaml_json::JsonField::for_module(key, field_name.clone(), &module_name),
+                    )
                 }
             };
 
-            let type_name = "t".to_string();
-            let main_type = if fields.is_empty() {
-                CamlType::abstract_type(type_name)
-            } else {
-                CamlType::record(type_name, fields)
-            };
+            let multiplicity = rel.cardinality().multiplicity();
+            let type_ref = type_ref.with_multiplicity(multiplicity);
+            let field = CamlField::new(field_name.clone(), type_ref.clone());
+            json_fields.push(json_field.with_multiplicity(multiplicity));
+
+            make_args.push(CamlType::labeled(field_name.clone(), type_ref.clone()));
+            make_params.push(format!("~{}", field_name));
+            make_body.push((field_name.clone(), CamlExpr::var(field_name.clone())));
 
-            let module = CamlModule::new(CamlModuleName::from_name(&kind.name)).with_structure(
-                vec![
-                    vec![CamlValue::new_type(main_type).with_doc(doc_string)],
-                    accessors,
-                ]
-                .concat(),
-            );
-            sources.push(module.into())
+            let accessor = CamlValue::binding(CamlBinding::named(
+                field_name.clone(),
+                CamlFun::new(vec![local_t.clone()], type_ref),
+            ))
+            .with_doc(doc);
+
+            let accessor_impl = CamlValue::definition(CamlLet::named(
+                field_name.clone(),
+                vec!["t".to_string()],
+                CamlExpr::var("t".to_string()).field(field_name),
+            ));
+
+            record_fields.push(field);
+            accessors.push(accessor);
+            accessor_impls.push(accessor_impl);
         }
+    };
 
-        Ok(SourceSet::from_sources(sources))
+    let doc_string = docs.doc(fields);
+    let abstract_type =
+        CamlValue::new_type(CamlType::abstract_type("t".to_string())).with_doc(doc_string.clone());
+
+    if record_fields.is_empty() && json {
+        let opaque_type =
+            CamlValue::new_type(ocaml_json::opaque_type()).with_doc(doc_string.clone());
+        let interface = CamlModule::new(module_name.clone())
+            .with_structure([vec![abstract_type], ocaml_json::signatures()].concat());
+        let implementation = CamlModule::implementation(module_name)
+            .with_structure([vec![opaque_type], ocaml_json::opaque_codecs()].concat());
+        return Ok((interface, implementation));
     }
+
+    if record_fields.is_empty() {
+        let interface =
+            CamlModule::new(module_name.clone()).with_structure(vec![abstract_type.clone()]);
+        let implementation =
+            CamlModule::implementation(module_name).with_structure(vec![abstract_type]);
+        return Ok((interface, implementation));
+    }
+
+    let record_type =
+        CamlValue::new_type(CamlType::record("t".to_string(), record_fields)).with_doc(doc_string);
+
+    let make = CamlValue::binding(CamlBinding::named(
+        "make".to_string(),
+        CamlFun::new(make_args, local_t),
+    ));
+
+    let make_impl = CamlValue::definition(CamlLet::named(
+        "make".to_string(),
+        make_params,
+        CamlExpr::record(make_body),
+    ));
+
+    let mut interface = [vec![abstract_type, make], accessors].concat();
+    let mut implementation = [vec![record_type, make_impl], accessor_impls].concat();
+
+    if json {
+        interface.extend(ocaml_json::signatures());
+        implementation.extend(ocaml_json::record_codecs(name, &json_fields, recursive));
+    }
+
+    let interface = CamlModule::new(module_name.clone()).with_structure(interface);
+    let implementation = CamlModule::implementation(module_name).with_structure(implementation);
+
+    Ok((interface, implementation))
 }
 
 #[cfg(test)]
@@ -108,11 +328,14 @@ mod tests {
 
     macro_rules! test {
         ($name:ident, $src:expr) => {
+            test!($name, OCamlEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
             #[test]
             fn $name() {
                 let mut store = lore_store::Store::new();
                 let store = store.add_from_string($src).unwrap();
-                let emitter = OCamlEmitter::new();
+                let emitter = $emitter;
                 let caml_value = emitter.translate(&store).unwrap();
                 let snapshot = format!(
                     r#"
@@ -146,4 +369,164 @@ rel Hero @lore/hasOne Name
 
         "#
     );
+
+    test!(
+        kind_to_implementation,
+        r#"
+
+using dota:v2022
+
+kind Hero {
+  lore:v1/doc/en "A playable character."
+}
+
+kind Ability
+
+attr Name
+
+rel Hero mainName Name {
+  lore:v1/doc/en "The name this hero is known by."
+}
+
+rel Hero hasAbility Ability
+
+        "#
+    );
+
+    test!(
+        kind_to_json,
+        OCamlEmitter::new().with_json(true),
+        r#"
+
+using dota:v2022
+
+kind Hero
+
+kind Ability
+
+attr Name
+
+rel Hero mainName Name
+
+rel Hero hasAbility Ability
+
+        "#
+    );
+
+    test!(
+        primitives_to_native_types,
+        OCamlEmitter::new().with_json(true),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+
+rel Hero name @lore/String
+rel Hero movementSpeed @lore/Int
+rel Hero turnRate @lore/Float
+rel Hero isMelee @lore/Bool
+rel Hero releasedOn @lore/Date
+
+        "#
+    );
+
+    test!(
+        cardinality_to_types,
+        OCamlEmitter::new().with_json(true),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+kind Item
+kind Talent
+
+rel Hero mainName @lore/String
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max @lore/many
+}
+
+rel Hero carries Item {
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Talent {
+  @lore/cardinality/min 0
+  @lore/cardinality/max 1
+}
+
+rel Ability name @lore/String
+
+rel Ability upgrades Ability {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
+
+    test!(
+        strip_namespace_from_names,
+        OCamlEmitter::new().with_naming(Naming::ocaml().with_strip_namespace(true)),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+
+rel Hero mainName @lore/String
+rel Hero type @lore/String
+rel Hero hasAbility Ability
+
+        "#
+    );
+
+    #[test]
+    fn stripped_names_collide() {
+        let mut store = lore_store::Store::new();
+        let store = store
+            .add_from_string(
+                r#"
+using dota:v2022
+
+kind Hero
+kind dota:v2023/Hero
+"#,
+            )
+            .unwrap();
+        let emitter = OCamlEmitter::new().with_naming(Naming::ocaml().with_strip_namespace(true));
+        assert_eq!(
+            emitter.translate(store).unwrap_err(),
+            EmitterError::NameCollision {
+                identifier: "Hero".to_string(),
+                first: "dota:v2022/Hero".to_string(),
+                second: "dota:v2023/Hero".to_string(),
+            }
+        );
+    }
+
+    test!(
+        namespaces_to_module_hierarchy,
+        r#"
+
+using dota:v2022/hello/world
+
+kind Hero
+kind dota:v2022/Item
+kind dota:v2022/hello/Greeting
+
+rel Hero carries dota:v2022/Item
+
+        "#
+    );
 }
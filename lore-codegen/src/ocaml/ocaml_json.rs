@@ -0,0 +1,384 @@
+use crate::ocaml::ocaml_ast::*;
+use crate::source_set::*;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonField {
+    pub key: String,
+    pub field_name: String,
+    pub to_json: String,
+    pub of_json: String,
+    pub optional: bool,
+}
+
+impl JsonField {
+    pub fn for_module(key: String, field_name: String, module_name: &CamlModuleName) -> JsonField {
+        JsonField {
+            key,
+            field_name,
+            to_json: format!("{}.to_json", module_name),
+            of_json: format!("{}.of_json", module_name),
+            optional: false,
+        }
+    }
+
+    /// A field that holds a value of the module it belongs to, so its codecs are the ones being
+    /// defined.
+    pub fn for_self(key: String, field_name: String) -> JsonField {
+        JsonField {
+            key,
+            field_name,
+            to_json: "to_json".to_string(),
+            of_json: "of_json".to_string(),
+            optional: false,
+        }
+    }
+
+    pub fn for_primitive(
+        key: String,
+        field_name: String,
+        primitive: lore_ast::Primitive,
+    ) -> JsonField {
+        let codec = match primitive {
+            lore_ast::Primitive::Int => "int",
+            lore_ast::Primitive::Float => "float",
+            lore_ast::Primitive::Bool => "bool",
+            lore_ast::Primitive::String
+            | lore_ast::Primitive::Date
+            | lore_ast::Primitive::DateTime
+            | lore_ast::Primitive::URI => "string",
+        };
+        JsonField {
+            key,
+            field_name,
+            to_json: format!("{}.{}_to_json", runtime_module(), codec),
+            of_json: format!("{}.{}_of_json", runtime_module(), codec),
+            optional: false,
+        }
+    }
+
+    pub fn with_multiplicity(self, multiplicity: lore_ast::Multiplicity) -> JsonField {
+        let codec = match multiplicity {
+            lore_ast::Multiplicity::One => return self,
+            lore_ast::Multiplicity::Optional => "option",
+            lore_ast::Multiplicity::List => "list",
+            lore_ast::Multiplicity::NonEmptyList => "non_empty",
+        };
+        JsonField {
+            to_json: format!("({}.{}_to_json {})", runtime_module(), codec, self.to_json),
+            of_json: format!("({}.{}_of_json {})", runtime_module(), codec, self.of_json),
+            optional: multiplicity == lore_ast::Multiplicity::Optional,
+            ..self
+        }
+    }
+}
+
+fn json_type() -> CamlType {
+    CamlType::reference(
+        CamlModuleName::new("Yojson.Safe".to_string()),
+        "t".to_string(),
+    )
+}
+
+fn runtime_module() -> CamlModuleName {
+    CamlModuleName::new("Lore_json".to_string())
+}
+
+pub fn signatures() -> Vec<CamlValue> {
+    let local_t = CamlType::reference(CamlModuleName::local_module(), "t".to_string());
+    let result = CamlType::reference(
+        CamlModuleName::local_module(),
+        "(t, string) result".to_string(),
+    );
+
+    vec![
+        CamlValue::binding(CamlBinding::named(
+            "to_json".to_string(),
+            CamlFun::new(vec![local_t], json_type()),
+        )),
+        CamlValue::binding(CamlBinding::named(
+            "of_json".to_string(),
+            CamlFun::new(vec![json_type()], result),
+        )),
+    ]
+}
+
+/// Modules without relations have no known structure, so their values are kept as raw JSON.
+pub fn opaque_type() -> CamlType {
+    CamlType::alias("t".to_string(), json_type())
+}
+
+pub fn opaque_codecs() -> Vec<CamlValue> {
+    vec![
+        CamlValue::definition(CamlLet::named(
+            "to_json".to_string(),
+            vec!["t".to_string()],
+            CamlExpr::var("t".to_string()),
+        )),
+        CamlValue::definition(CamlLet::named(
+            "of_json".to_string(),
+            vec!["json".to_string()],
+            CamlExpr::var("Ok".to_string()).apply(vec![CamlExpr::var("json".to_string())]),
+        )),
+    ]
+}
+
+/// Codecs of a record, which are `let rec` when the record refers to itself.
+pub fn record_codecs(
+    name: &lore_ast::Name,
+    fields: &[JsonField],
+    recursive: bool,
+) -> Vec<CamlValue> {
+    let mut pairs = vec![];
+    for field in fields {
+        let to_json = CamlExpr::var(field.to_json.clone());
+        let value = CamlExpr::var("t".to_string()).field(field.field_name.clone());
+        pairs.push(CamlExpr::tuple(vec![
+            CamlExpr::string(field.key.clone()),
+            to_json.apply(vec![value]),
+        ]));
+    }
+
+    let to_json = CamlExpr::poly_variant("Assoc".to_string(), CamlExpr::list(pairs));
+
+    let record = fields
+        .iter()
+        .map(|field| {
+            (
+                field.field_name.clone(),
+                CamlExpr::var(field.field_name.clone()),
+            )
+        })
+        .collect();
+
+    let mut of_json = CamlExpr::var("Ok".to_string()).apply(vec![CamlExpr::record(record)]);
+    for field in fields.iter().rev() {
+        let accessor = if field.optional {
+            "optional_field"
+        } else {
+            "field"
+        };
+        let value = CamlExpr::var(accessor.to_string()).apply(vec![
+            CamlExpr::string(field.key.clone()),
+            CamlExpr::var(field.of_json.clone()),
+            CamlExpr::var("fields".to_string()),
+        ]);
+        of_json = CamlExpr::let_op("let*".to_string(), field.field_name.clone(), value, of_json);
+    }
+
+    let object = CamlExpr::var("assoc".to_string()).apply(vec![
+        CamlExpr::string(name.to_string()),
+        CamlExpr::var("json".to_string()),
+    ]);
+    let of_json = CamlExpr::let_open(
+        runtime_module(),
+        CamlExpr::let_op("let*".to_string(), "fields".to_string(), object, of_json),
+    );
+
+    vec![
+        CamlValue::definition(
+            CamlLet::named("to_json".to_string(), vec!["t".to_string()], to_json)
+                .with_recursive(recursive),
+        ),
+        CamlValue::definition(
+            CamlLet::named("of_json".to_string(), vec!["json".to_string()], of_json)
+                .with_recursive(recursive),
+        ),
+    ]
+}
+
+pub fn runtime() -> Source {
+    Source::new(
+        PathBuf::from(format!(
+            "{}.ml",
+            runtime_module().to_string().to_lowercase()
+        )),
+        r#"let ( let* ) = Result.bind
+
+let assoc name = function
+  | `Assoc fields -> Ok fields
+  | _ -> Error ("expected a JSON object for " ^ name)
+
+let field key of_json fields =
+  match List.assoc_opt key fields with
+  | Some json -> of_json json
+  | None -> Error ("missing field " ^ key)
+
+let optional_field key of_json fields =
+  match List.assoc_opt key fields with
+  | Some json -> of_json json
+  | None -> of_json `Null
+
+let string_to_json s = `String s
+
+let string_of_json = function
+  | `String s -> Ok s
+  | _ -> Error "expected a string"
+
+let int_to_json i = `Int i
+
+let int_of_json = function
+  | `Int i -> Ok i
+  | _ -> Error "expected an int"
+
+let float_to_json f = `Float f
+
+let float_of_json = function
+  | `Float f -> Ok f
+  | `Int i -> Ok (float_of_int i)
+  | _ -> Error "expected a float"
+
+let bool_to_json b = `Bool b
+
+let bool_of_json = function
+  | `Bool b -> Ok b
+  | _ -> Error "expected a bool"
+
+let option_to_json to_json = function
+  | None -> `Null
+  | Some x -> to_json x
+
+let option_of_json of_json = function
+  | `Null -> Ok None
+  | json -> Result.map Option.some (of_json json)
+
+let list_to_json to_json xs = `List (List.map to_json xs)
+
+let list_of_json of_json = function
+  | `List xs ->
+      List.fold_right
+        (fun x acc ->
+          let* x = of_json x in
+          let* acc = acc in
+          Ok (x :: acc))
+        xs (Ok [])
+  | _ -> Error "expected a list"
+
+let non_empty_to_json to_json (x, xs) = list_to_json to_json (x :: xs)
+
+let non_empty_of_json of_json json =
+  let* xs = list_of_json of_json json in
+  match xs with
+  | x :: xs -> Ok (x, xs)
+  | [] -> Error "expected a non-empty list"
+"#
+        .to_string(),
+    )
+}
+
+/// Builds a JSON value that every generated `of_json` accepts. Relations that loop back to a
+/// module we are already describing have no finite sample unless an optional or list
+/// cardinality lets us stop there, otherwise those modules are not tested.
+fn sample(
+    store: &lore_store::Store,
+    uri: &lore_ast::URI,
+    visiting: &mut Vec<lore_ast::URI>,
+) -> Option<String> {
+    if visiting.contains(uri) {
+        return None;
+    }
+
+    if let Some(primitive) = lore_ast::Primitive::from_uri(uri) {
+        let sample = match primitive {
+            lore_ast::Primitive::String => "\"lore\"",
+            lore_ast::Primitive::Int => "42",
+            lore_ast::Primitive::Float => "0.5",
+            lore_ast::Primitive::Bool => "true",
+            lore_ast::Primitive::Date => "\"2022-01-01\"",
+            lore_ast::Primitive::DateTime => "\"2022-01-01T00:00:00Z\"",
+            lore_ast::Primitive::URI => "\"lore:v1/URI\"",
+        };
+        return Some(sample.to_string());
+    }
+
+    match store.relations_by_subject.get(uri) {
+        None => Some("null".to_string()),
+        Some(rels) => {
+            visiting.push(uri.clone());
+            let mut pairs = Some(vec![]);
+            for rel in rels {
+                let value = sample(store, &rel.object.to_uri(), visiting);
+                let value = match (rel.cardinality().multiplicity(), value) {
+                    (lore_ast::Multiplicity::Optional, value) => {
+                        Some(value.unwrap_or_else(|| "null".to_string()))
+                    }
+                    (lore_ast::Multiplicity::List, value) => Some(
+                        value
+                            .map(|v| format!("[{}]", v))
+                            .unwrap_or_else(|| "[]".to_string()),
+                    ),
+                    (lore_ast::Multiplicity::NonEmptyList, value) => {
+                        value.map(|v| format!("[{}]", v))
+                    }
+                    (lore_ast::Multiplicity::One, value) => value,
+                };
+                match (value, pairs.as_mut()) {
+                    (Some(value), Some(pairs)) => {
+                        pairs.push(format!("{:?}: {}", rel.predicate.to_string(), value))
+                    }
+                    _ => pairs = None,
+                }
+            }
+            visiting.pop();
+            pairs.map(|pairs| format!("{{{}}}", pairs.join(", ")))
+        }
+    }
+}
+
+pub fn round_trip_tests(
+    store: &lore_store::Store,
+    modules: &[(lore_ast::Name, CamlModuleName)],
+    library_name: &str,
+) -> Vec<Source> {
+    let mut cases = vec![];
+    for (name, module_name) in modules {
+        match sample(store, &name.to_uri(), &mut vec![]) {
+            Some(json) => cases.push(format!(
+                "  round_trip {:?} {}.of_json {}.to_json {{|{}|}};",
+                name.to_string(),
+                module_name,
+                module_name,
+                json
+            )),
+            None => cases.push(format!(
+                "  (* {} is recursive and has no finite sample *)",
+                name
+            )),
+        }
+    }
+
+    let mut library = library_name.to_string();
+    if let Some(first) = library.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    };
+
+    let test = format!(
+        r#"open {}
+
+let round_trip name of_json to_json sample =
+  let json = Yojson.Safe.from_string sample in
+  match of_json json with
+  | Error e -> failwith (name ^ ": " ^ e)
+  | Ok t ->
+      if not (Yojson.Safe.equal json (to_json t)) then
+        failwith (name ^ ": JSON round-trip changed the value")
+
+let () =
+{}
+  print_endline "ok"
+"#,
+        library,
+        cases.join("\n")
+    );
+
+    vec![
+        Source::new(
+            PathBuf::from("test/dune"),
+            format!(
+                "(test\n (name test_json)\n (libraries {} yojson))\n",
+                library_name
+            ),
+        ),
+        Source::new(PathBuf::from("test/test_json.ml"), test),
+    ]
+}
@@ -0,0 +1,112 @@
+use crate::emitter_error::*;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// The numbers handed out to the fields of every message and the values of every enum, keyed by
+/// their URIs. Keeping them in a lockfile between runs means reordering or removing relations
+/// never changes the number of an existing field:
+///
+/// ```json
+/// {
+///   "dota:v2022/Hero": {
+///     "dota:v2022/hasAbility": 1,
+///     "dota:v2022/movementSpeed": 2
+///   }
+/// }
+/// ```
+///
+/// Fields that no longer exist stay in the lockfile so their numbers are reserved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldNumbers {
+    numbers: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl FieldNumbers {
+    pub fn new() -> FieldNumbers {
+        FieldNumbers::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<FieldNumbers, EmitterError> {
+        let invalid = |reason: String| EmitterError::InvalidLockfile { reason };
+
+        let value: Value = serde_json::from_str(json).map_err(|err| invalid(err.to_string()))?;
+        let messages = value
+            .as_object()
+            .ok_or_else(|| invalid("expected an object of messages".to_string()))?;
+
+        let mut numbers = FieldNumbers::new();
+        for (message, fields) in messages {
+            let fields = fields.as_object().ok_or_else(|| {
+                invalid(format!("expected an object of fields for `{}`", message))
+            })?;
+            for (field, number) in fields {
+                let number = number.as_u64().ok_or_else(|| {
+                    invalid(format!(
+                        "expected a number for `{}` in `{}`",
+                        field, message
+                    ))
+                })?;
+                numbers.insert(message, field, number);
+            }
+        }
+        Ok(numbers)
+    }
+
+    pub fn to_json(&self) -> String {
+        let messages: Map<String, Value> = self
+            .numbers
+            .iter()
+            .map(|(message, fields)| (message.clone(), json!(fields)))
+            .collect();
+        format!(
+            "{}\n",
+            serde_json::to_string_pretty(&Value::Object(messages)).unwrap()
+        )
+    }
+
+    pub fn get(&self, message: &str, field: &str) -> Option<u64> {
+        self.numbers
+            .get(message)
+            .and_then(|fields| fields.get(field))
+            .copied()
+    }
+
+    pub fn insert(&mut self, message: &str, field: &str, number: u64) {
+        self.numbers
+            .entry(message.to_string())
+            .or_default()
+            .insert(field.to_string(), number);
+    }
+
+    /// Every field of a message with its number, sorted by field URI.
+    pub fn fields(&self, message: &str) -> Vec<(&String, u64)> {
+        self.numbers
+            .get(message)
+            .map(|fields| fields.iter().map(|(f, n)| (f, *n)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_through_json() {
+        let mut numbers = FieldNumbers::new();
+        numbers.insert("dota:v2022/Hero", "dota:v2022/movementSpeed", 2);
+        numbers.insert("dota:v2022/Hero", "dota:v2022/hasAbility", 1);
+
+        let json = numbers.to_json();
+        assert_eq!(FieldNumbers::from_json(&json), Ok(numbers));
+    }
+
+    #[test]
+    fn rejects_invalid_lockfiles() {
+        assert!(FieldNumbers::from_json("[]").is_err());
+        assert!(FieldNumbers::from_json(r#"{"dota:v2022/Hero": 1}"#).is_err());
+        assert!(
+            FieldNumbers::from_json(r#"{"dota:v2022/Hero": {"dota:v2022/name": "one"}}"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod field_numbers;
+pub mod proto_ast;
+pub mod protobuf_emitter;
+
+pub use field_numbers::*;
+pub use protobuf_emitter::*;
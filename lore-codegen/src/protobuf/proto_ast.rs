@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter, Result};
+
+fn write_doc(f: &mut Formatter<'_>, indent: &str, doc: &Option<String>) -> Result {
+    if let Some(doc) = doc {
+        for line in doc.lines() {
+            writeln!(f, "{}// {}", indent, line)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtoLabel {
+    Singular,
+    Optional,
+    Repeated,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtoField {
+    pub label: ProtoLabel,
+    pub type_: String,
+    pub name: String,
+    pub number: u64,
+    pub doc: Option<String>,
+}
+
+impl Display for ProtoField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_doc(f, "  ", &self.doc)?;
+        let label = match self.label {
+            ProtoLabel::Singular => "",
+            ProtoLabel::Optional => "optional ",
+            ProtoLabel::Repeated => "repeated ",
+        };
+        writeln!(
+            f,
+            "  {}{} {} = {};",
+            label, self.type_, self.name, self.number
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtoMessage {
+    pub name: String,
+    pub fields: Vec<ProtoField>,
+    pub reserved: Vec<u64>,
+    pub doc: Option<String>,
+}
+
+impl Display for ProtoMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_doc(f, "", &self.doc)?;
+        if self.fields.is_empty() && self.reserved.is_empty() {
+            return writeln!(f, "message {} {{}}", self.name);
+        }
+        writeln!(f, "message {} {{", self.name)?;
+        if !self.reserved.is_empty() {
+            let reserved: Vec<String> = self.reserved.iter().map(|n| n.to_string()).collect();
+            writeln!(f, "  reserved {};", reserved.join(", "))?;
+        }
+        for field in &self.fields {
+            write!(f, "{}", field)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtoEnumValue {
+    pub name: String,
+    pub number: u64,
+    pub doc: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtoEnum {
+    pub name: String,
+    pub values: Vec<ProtoEnumValue>,
+    pub reserved: Vec<u64>,
+    pub doc: Option<String>,
+}
+
+impl Display for ProtoEnum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_doc(f, "", &self.doc)?;
+        writeln!(f, "enum {} {{", self.name)?;
+        if !self.reserved.is_empty() {
+            let reserved: Vec<String> = self.reserved.iter().map(|n| n.to_string()).collect();
+            writeln!(f, "  reserved {};", reserved.join(", "))?;
+        }
+        for value in &self.values {
+            write_doc(f, "  ", &value.doc)?;
+            writeln!(f, "  {} = {};", value.name, value.number)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProtoDefinition {
+    Message(ProtoMessage),
+    Enum(ProtoEnum),
+}
+
+impl Display for ProtoDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ProtoDefinition::Message(message) => write!(f, "{}", message),
+            ProtoDefinition::Enum(enum_) => write!(f, "{}", enum_),
+        }
+    }
+}
+
+/// A single `.proto` file with every definition of one package.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtoFile {
+    pub package: Vec<String>,
+    pub imports: BTreeSet<String>,
+    pub definitions: Vec<ProtoDefinition>,
+}
+
+impl ProtoFile {
+    pub fn path(&self) -> String {
+        if self.package.is_empty() {
+            "ontology.proto".to_string()
+        } else {
+            format!("{}/ontology.proto", self.package.join("/"))
+        }
+    }
+}
+
+impl Display for ProtoFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "syntax = \"proto3\";")?;
+        if !self.package.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "package {};", self.package.join("."))?;
+        }
+        if !self.imports.is_empty() {
+            writeln!(f)?;
+            for import in &self.imports {
+                writeln!(f, "import \"{}\";", import)?;
+            }
+        }
+        for definition in &self.definitions {
+            writeln!(f)?;
+            write!(f, "{}", definition)?;
+        }
+        Ok(())
+    }
+}
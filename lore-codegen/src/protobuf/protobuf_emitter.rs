@@ -0,0 +1,655 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::protobuf::field_numbers::*;
+use crate::protobuf::proto_ast::*;
+use crate::source_set::*;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+const MAX_FIELD_NUMBER: u64 = 536_870_911;
+const RESERVED_FIELD_NUMBERS: std::ops::RangeInclusive<u64> = 19_000..=19_999;
+
+/// Emits one `.proto` file per namespace. Field numbers are taken from a `lore:v1/proto/field`
+/// field on the relation, then from the lockfile, and are otherwise handed out after the largest
+/// number the message has ever used.
+pub struct ProtobufEmitter {
+    naming: Naming,
+    docs: Docs,
+    field_numbers: FieldNumbers,
+}
+
+impl Default for ProtobufEmitter {
+    fn default() -> ProtobufEmitter {
+        ProtobufEmitter {
+            naming: Naming::protobuf(),
+            docs: Docs::new(),
+            field_numbers: FieldNumbers::new(),
+        }
+    }
+}
+
+impl ProtobufEmitter {
+    pub fn new() -> ProtobufEmitter {
+        ProtobufEmitter::default()
+    }
+
+    pub fn with_naming(self, naming: Naming) -> ProtobufEmitter {
+        ProtobufEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> ProtobufEmitter {
+        ProtobufEmitter { docs, ..self }
+    }
+
+    /// The field numbers handed out in a previous run, usually read from a lockfile.
+    pub fn with_field_numbers(self, field_numbers: FieldNumbers) -> ProtobufEmitter {
+        ProtobufEmitter {
+            field_numbers,
+            ..self
+        }
+    }
+
+    pub fn field_number_uri() -> lore_ast::URI {
+        lore_ast::URI::from_string("lore:v1/proto/field".to_string())
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let (files, _) = self.files(store)?;
+        let sources = files
+            .iter()
+            .map(|file| Source::new(PathBuf::from(file.path()), file.to_string()))
+            .collect();
+        Ok(SourceSet::from_sources(sources))
+    }
+
+    /// The field numbers to write back to the lockfile after translating the store.
+    pub fn field_numbers(&self, store: &lore_store::Store) -> Result<FieldNumbers, EmitterError> {
+        let (_, field_numbers) = self.files(store)?;
+        Ok(field_numbers)
+    }
+
+    fn files(
+        &self,
+        store: &lore_store::Store,
+    ) -> Result<(Vec<ProtoFile>, FieldNumbers), EmitterError> {
+        let mut files: BTreeMap<Vec<String>, ProtoFile> = BTreeMap::new();
+        let mut scopes: BTreeMap<Vec<String>, Scope> = BTreeMap::new();
+        let mut field_numbers = FieldNumbers::new();
+
+        let names = store
+            .attributes()
+            .into_iter()
+            .map(|attr| (&attr.name, &attr.fields, vec![]))
+            .chain(
+                store
+                    .kinds()
+                    .into_iter()
+                    .map(|kind| (&kind.name, &kind.fields, kind.variants())),
+            );
+
+        for (name, fields, variants) in names {
+            let package = self.package(name);
+            let scope = scopes
+                .entry(package.clone())
+                .or_insert_with(|| Scope::for_store(store));
+            let file = files.entry(package.clone()).or_insert_with(|| ProtoFile {
+                package: package.clone(),
+                ..ProtoFile::default()
+            });
+
+            let type_name = scope.claim(self.naming.type_name(name), name)?;
+            let doc = self.docs.doc(fields);
+
+            let definition = if variants.is_empty() {
+                let rels = store
+                    .relations_by_subject
+                    .get(&name.to_uri())
+                    .cloned()
+                    .unwrap_or_default();
+                ProtoDefinition::Message(self.message(
+                    name,
+                    type_name,
+                    doc,
+                    &rels,
+                    file,
+                    &mut field_numbers,
+                )?)
+            } else {
+                ProtoDefinition::Enum(self.enumeration(
+                    name,
+                    type_name,
+                    doc,
+                    &variants,
+                    scope,
+                    &mut field_numbers,
+                )?)
+            };
+            file.definitions.push(definition);
+        }
+
+        // objects that are neither declared nor primitive are wrapped, so every field has a type
+        let mut undeclared = BTreeMap::new();
+        for rel in store.relations_by_subject.values().flatten() {
+            let uri = rel.object.to_uri();
+            if lore_ast::Primitive::from_uri(&uri).is_none()
+                && !store.kinds.contains_key(&uri)
+                && !store.attributes.contains_key(&uri)
+            {
+                undeclared.insert(uri, rel.object.clone());
+            }
+        }
+        for name in undeclared.values() {
+            let package = self.package(name);
+            let scope = scopes
+                .entry(package.clone())
+                .or_insert_with(|| Scope::for_store(store));
+            let file = files.entry(package.clone()).or_insert_with(|| ProtoFile {
+                package: package.clone(),
+                ..ProtoFile::default()
+            });
+            file.definitions
+                .push(ProtoDefinition::Message(ProtoMessage {
+                    name: scope.claim(self.naming.type_name(name), name)?,
+                    fields: vec![ProtoField {
+                        label: ProtoLabel::Singular,
+                        type_: "string".to_string(),
+                        name: "value".to_string(),
+                        number: 1,
+                        doc: None,
+                    }],
+                    reserved: vec![],
+                    doc: None,
+                }));
+        }
+
+        Ok((files.into_values().collect(), field_numbers))
+    }
+
+    fn package(&self, name: &lore_ast::Name) -> Vec<String> {
+        namespace_segments(name)
+            .iter()
+            .map(|segment| self.naming.package_segment_name(segment))
+            .collect()
+    }
+
+    fn message(
+        &self,
+        name: &lore_ast::Name,
+        type_name: String,
+        doc: Option<String>,
+        rels: &[lore_ast::Relation],
+        file: &mut ProtoFile,
+        field_numbers: &mut FieldNumbers,
+    ) -> Result<ProtoMessage, EmitterError> {
+        let mut explicit = vec![];
+        for rel in rels {
+            explicit.push(ProtobufEmitter::explicit_number(rel)?);
+        }
+
+        let keys: Vec<String> = rels.iter().map(|rel| rel.predicate.to_string()).collect();
+        let (numbers, reserved) = self.number(name, &keys, &explicit, field_numbers)?;
+
+        let mut field_scope = Scope::new();
+        let mut proto_fields = vec![];
+        for (rel, number) in rels.iter().zip(numbers) {
+            let label = match rel.cardinality().multiplicity() {
+                lore_ast::Multiplicity::One => ProtoLabel::Singular,
+                lore_ast::Multiplicity::Optional => ProtoLabel::Optional,
+                lore_ast::Multiplicity::List | lore_ast::Multiplicity::NonEmptyList => {
+                    ProtoLabel::Repeated
+                }
+            };
+            proto_fields.push(ProtoField {
+                label,
+                type_: self.type_reference(&file.package, &rel.object, &mut file.imports),
+                name: field_scope.claim(self.naming.value_name(&rel.predicate), &rel.predicate)?,
+                number,
+                doc: self.docs.doc(&rel.fields),
+            });
+        }
+
+        Ok(ProtoMessage {
+            name: type_name,
+            fields: proto_fields,
+            reserved,
+            doc,
+        })
+    }
+
+    /// Enum values are prefixed with the name of the enum, since they share a scope with every
+    /// other definition in the package. The first value is always the unspecified `0`.
+    fn enumeration(
+        &self,
+        name: &lore_ast::Name,
+        type_name: String,
+        doc: Option<String>,
+        variants: &[&lore_ast::Name],
+        scope: &mut Scope,
+        field_numbers: &mut FieldNumbers,
+    ) -> Result<ProtoEnum, EmitterError> {
+        let keys: Vec<String> = variants.iter().map(|v| v.to_string()).collect();
+        let explicit = vec![None; variants.len()];
+        let (numbers, reserved) = self.number(name, &keys, &explicit, field_numbers)?;
+
+        let value_name =
+            |suffix: Vec<String>| Case::ScreamingSnake.apply(&[words(&type_name), suffix].concat());
+
+        let mut values = vec![ProtoEnumValue {
+            name: scope.claim(value_name(vec!["unspecified".to_string()]), name)?,
+            number: 0,
+            doc: None,
+        }];
+        for (variant, number) in variants.iter().zip(numbers) {
+            values.push(ProtoEnumValue {
+                name: scope.claim(value_name(words(&local_name(variant))), variant)?,
+                number,
+                doc: None,
+            });
+        }
+
+        Ok(ProtoEnum {
+            name: type_name,
+            values,
+            reserved,
+            doc,
+        })
+    }
+
+    /// Hands out a number to every key and records it. Returns the numbers in the same order as
+    /// the keys, and the numbers in the lockfile that are no longer used.
+    fn number(
+        &self,
+        name: &lore_ast::Name,
+        keys: &[String],
+        explicit: &[Option<u64>],
+        field_numbers: &mut FieldNumbers,
+    ) -> Result<(Vec<u64>, Vec<u64>), EmitterError> {
+        let message = name.to_string();
+        let locked = self.field_numbers.fields(&message);
+        let mut used: BTreeMap<u64, &String> = BTreeMap::new();
+        let mut numbers: Vec<Option<u64>> = vec![None; keys.len()];
+
+        for (i, key) in keys.iter().enumerate() {
+            if let Some(number) = explicit[i] {
+                // a number locked to another field must never be handed to a new one
+                let first = locked
+                    .iter()
+                    .find(|(locked_key, locked)| *locked == number && *locked_key != key)
+                    .map(|(locked_key, _)| *locked_key)
+                    .or_else(|| used.insert(number, key));
+                if let Some(first) = first {
+                    return Err(EmitterError::FieldNumberCollision {
+                        message,
+                        number,
+                        first: first.clone(),
+                        second: key.clone(),
+                    });
+                }
+                numbers[i] = Some(number);
+            }
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            if numbers[i].is_some() {
+                continue;
+            }
+            if let Some(number) = self.field_numbers.get(&message, key) {
+                if let Entry::Vacant(entry) = used.entry(number) {
+                    entry.insert(key);
+                    numbers[i] = Some(number);
+                }
+            }
+        }
+
+        let mut next = locked
+            .iter()
+            .map(|(_, number)| *number)
+            .chain(used.keys().copied())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for number in numbers.iter_mut().filter(|n| n.is_none()) {
+            if RESERVED_FIELD_NUMBERS.contains(&next) {
+                next = RESERVED_FIELD_NUMBERS.end() + 1;
+            }
+            *number = Some(next);
+            next += 1;
+        }
+        let numbers: Vec<u64> = numbers.into_iter().flatten().collect();
+
+        let used: BTreeSet<u64> = numbers.iter().copied().collect();
+        let mut reserved = vec![];
+        for (key, number) in locked {
+            if !used.contains(&number) {
+                reserved.push(number);
+                field_numbers.insert(&message, key, number);
+            }
+        }
+        reserved.sort_unstable();
+        reserved.dedup();
+
+        for (key, number) in keys.iter().zip(&numbers) {
+            field_numbers.insert(&message, key, *number);
+        }
+
+        Ok((numbers, reserved))
+    }
+
+    fn explicit_number(rel: &lore_ast::Relation) -> Result<Option<u64>, EmitterError> {
+        let field = match rel
+            .fields
+            .iter()
+            .find(|field| field.name.uri == ProtobufEmitter::field_number_uri())
+        {
+            None => return Ok(None),
+            Some(field) => field,
+        };
+
+        let invalid = |reason: &str| EmitterError::InvalidFieldNumber {
+            relation: format!("{} {} {}", rel.subject, rel.predicate, rel.object),
+            number: field.value.to_string(),
+            reason: reason.to_string(),
+        };
+
+        match field.value {
+            lore_ast::Literal::Number(0) => Err(invalid("field numbers start at 1")),
+            lore_ast::Literal::Number(n) if n > MAX_FIELD_NUMBER => {
+                Err(invalid("it is larger than the largest field number"))
+            }
+            lore_ast::Literal::Number(n) if RESERVED_FIELD_NUMBERS.contains(&n) => {
+                Err(invalid("it is reserved by Protocol Buffers"))
+            }
+            lore_ast::Literal::Number(n) => Ok(Some(n)),
+            _ => Err(invalid("expected a number")),
+        }
+    }
+
+    fn type_reference(
+        &self,
+        package: &[String],
+        object: &lore_ast::Name,
+        imports: &mut BTreeSet<String>,
+    ) -> String {
+        if let Some(primitive) = lore_ast::Primitive::from_uri(&object.uri) {
+            return match primitive {
+                lore_ast::Primitive::String
+                | lore_ast::Primitive::Date
+                | lore_ast::Primitive::URI => "string".to_string(),
+                lore_ast::Primitive::Int => "int64".to_string(),
+                lore_ast::Primitive::Float => "double".to_string(),
+                lore_ast::Primitive::Bool => "bool".to_string(),
+                lore_ast::Primitive::DateTime => {
+                    imports.insert("google/protobuf/timestamp.proto".to_string());
+                    "google.protobuf.Timestamp".to_string()
+                }
+            };
+        }
+
+        let type_name = self.naming.type_name(object);
+        let object_package = self.package(object);
+        if object_package == package {
+            return type_name;
+        }
+
+        let file = ProtoFile {
+            package: object_package.clone(),
+            ..ProtoFile::default()
+        };
+        imports.insert(file.path());
+        if object_package.is_empty() {
+            format!(".{}", type_name)
+        } else {
+            format!(".{}.{}", object_package.join("."), type_name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            test!($name, ProtobufEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let emitter = $emitter;
+                let sources = emitter.translate(&store).unwrap();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{:#?}
+"#,
+                    $src, sources
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    test!(
+        kinds_to_messages,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character."
+}
+
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+  @lore/variant Intelligence
+}
+
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+rel Hero releasedAt @lore/DateTime
+
+rel Hero hasAbility Ability {
+  @lore/doc/en "Abilities this hero can cast."
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Ability {
+  @lore/cardinality/max 1
+}
+
+rel Ability manaCost @lore/Float
+
+        "#
+    );
+
+    test!(
+        explicit_field_numbers,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+
+rel Hero hasAbility Ability {
+  @lore/proto/field 3
+  @lore/cardinality @lore/many
+}
+
+rel Hero movementSpeed @lore/Int
+
+rel Hero name @lore/String {
+  @lore/proto/field 1
+}
+
+        "#
+    );
+
+    test!(
+        locked_field_numbers,
+        ProtobufEmitter::new().with_field_numbers(
+            FieldNumbers::from_json(
+                r#"{
+                  "dota:v2022/Hero": {
+                    "dota:v2022/name": 2,
+                    "dota:v2022/attackRange": 3,
+                    "dota:v2022/movementSpeed": 1
+                  }
+                }"#
+            )
+            .unwrap()
+        ),
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+
+rel Hero armor @lore/Int
+rel Hero name @lore/String
+rel Hero movementSpeed @lore/Int
+
+        "#
+    );
+
+    test!(
+        namespaces_to_packages,
+        r#"
+
+prefix lore:v1 as @lore
+prefix dota:v2021 as @old
+
+using dota:v2022
+
+kind Hero
+kind @old/Hero
+
+rel Hero previousVersion @old/Hero {
+  @lore/cardinality/max 1
+}
+
+rel @old/Hero name @lore/String
+
+        "#
+    );
+
+    #[test]
+    fn field_numbers_are_recorded() {
+        let mut store = lore_store::Store::new();
+        let store = store
+            .add_from_string(
+                r#"
+prefix lore:v1 as @lore
+using dota:v2022
+
+kind Hero
+rel Hero movementSpeed @lore/Int
+rel Hero name @lore/String
+            "#,
+            )
+            .unwrap();
+
+        let mut locked = FieldNumbers::new();
+        locked.insert("dota:v2022/Hero", "dota:v2022/attackRange", 1);
+
+        let emitter = ProtobufEmitter::new().with_field_numbers(locked);
+        let mut expected = FieldNumbers::new();
+        expected.insert("dota:v2022/Hero", "dota:v2022/attackRange", 1);
+        expected.insert("dota:v2022/Hero", "dota:v2022/movementSpeed", 2);
+        expected.insert("dota:v2022/Hero", "dota:v2022/name", 3);
+        assert_eq!(emitter.field_numbers(store), Ok(expected));
+    }
+
+    #[test]
+    fn field_number_collision() {
+        let mut store = lore_store::Store::new();
+        let store = store
+            .add_from_string(
+                r#"
+prefix lore:v1 as @lore
+using dota:v2022
+
+kind Hero
+rel Hero movementSpeed @lore/Int { @lore/proto/field 1 }
+rel Hero name @lore/String { @lore/proto/field 1 }
+            "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            ProtobufEmitter::new().translate(store),
+            Err(EmitterError::FieldNumberCollision {
+                message: "dota:v2022/Hero".to_string(),
+                number: 1,
+                first: "dota:v2022/movementSpeed".to_string(),
+                second: "dota:v2022/name".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn explicit_number_is_locked() {
+        let mut store = lore_store::Store::new();
+        let store = store
+            .add_from_string(
+                r#"
+prefix lore:v1 as @lore
+using dota:v2022
+
+kind Hero
+rel Hero name @lore/String { @lore/proto/field 1 }
+            "#,
+            )
+            .unwrap();
+
+        let mut locked = FieldNumbers::new();
+        locked.insert("dota:v2022/Hero", "dota:v2022/attackRange", 1);
+
+        assert_eq!(
+            ProtobufEmitter::new()
+                .with_field_numbers(locked)
+                .translate(store),
+            Err(EmitterError::FieldNumberCollision {
+                message: "dota:v2022/Hero".to_string(),
+                number: 1,
+                first: "dota:v2022/attackRange".to_string(),
+                second: "dota:v2022/name".to_string(),
+            })
+        );
+    }
+
+    test!(
+        undeclared_objects_to_wrappers,
+        r#"
+
+prefix dota:v2021 as @old
+
+using dota:v2022
+
+kind Hero
+
+rel Hero mainName Name
+rel Hero previousName @old/Name
+
+        "#
+    );
+}
@@ -0,0 +1,5 @@
+pub mod rescript_emitter;
+pub mod rescript_printer;
+
+pub use rescript_emitter::*;
+pub use rescript_printer::*;
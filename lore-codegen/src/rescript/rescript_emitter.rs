@@ -0,0 +1,168 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::ocaml::ocaml_emitter::{module_pairs, namespace_modules};
+use crate::rescript::rescript_printer::*;
+use crate::source_set::*;
+use std::path::PathBuf;
+
+pub struct ReScriptEmitter {
+    naming: Naming,
+    docs: Docs,
+}
+
+impl Default for ReScriptEmitter {
+    fn default() -> ReScriptEmitter {
+        ReScriptEmitter {
+            naming: Naming::rescript(),
+            docs: Docs::new(),
+        }
+    }
+}
+
+impl ReScriptEmitter {
+    pub fn new() -> ReScriptEmitter {
+        ReScriptEmitter::default()
+    }
+
+    pub fn with_naming(self, naming: Naming) -> ReScriptEmitter {
+        ReScriptEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> ReScriptEmitter {
+        ReScriptEmitter { docs, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let mut sources = vec![];
+
+        let mut module_names = vec![];
+
+        for (name, interface, implementation) in
+            module_pairs(store, &self.naming, &self.docs, false)?
+        {
+            module_names.push((name, interface.name.clone()));
+            sources.push(Source::new(
+                PathBuf::from(format!("{}.resi", interface.name)),
+                format!("{}", ReScript(&interface)),
+            ));
+            sources.push(Source::new(
+                PathBuf::from(format!("{}.res", implementation.name)),
+                format!("{}", ReScript(&implementation)),
+            ));
+        }
+
+        for namespace in namespace_modules(&self.naming, &module_names)? {
+            sources.push(Source::new(
+                PathBuf::from(format!("{}.res", namespace.name)),
+                format!("{}", ReScript(&namespace)),
+            ));
+        }
+
+        Ok(SourceSet::from_sources(sources))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let emitter = ReScriptEmitter::new();
+                let sources = emitter.translate(&store).unwrap();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{:#?}
+"#,
+                    $src, sources
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    test!(
+        kind_to_type,
+        r#"
+
+prefix lore:rel:v1 as @lore
+
+using dota:ontology:2022
+
+kind Hero
+
+attr Name
+
+rel Hero @lore/hasOne Name
+
+        "#
+    );
+
+    test!(
+        module_names_follow_rescript_rules,
+        r#"
+
+using dota-2:ontology.v2022
+
+kind Hero {
+  lore:v1/doc/en "A playable character."
+}
+
+kind Ability
+
+rel Hero hasAbility Ability {
+  lore:v1/doc/en "An ability this hero can cast."
+}
+
+        "#
+    );
+
+    test!(
+        cardinality_to_types,
+        r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+kind Item
+kind Talent
+
+rel Hero mainName @lore/String
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max @lore/many
+}
+
+rel Hero carries Item {
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Talent {
+  @lore/cardinality/min 0
+  @lore/cardinality/max 1
+}
+
+rel Ability name @lore/String
+
+rel Ability upgrades Ability {
+  @lore/cardinality @lore/many
+}
+
+        "#
+    );
+}
@@ -0,0 +1,215 @@
+use crate::ocaml::ocaml_ast::*;
+use std::fmt::{Display, Error, Formatter};
+
+/// ReScript shares the OCaml module language, so we print the very same `ocaml_ast` with
+/// ReScript's concrete syntax instead of building a second tree.
+pub trait ToReScript {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error>;
+}
+
+pub struct ReScript<'a, T: ToReScript>(pub &'a T);
+
+impl<'a, T: ToReScript> Display for ReScript<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.0.fmt_rescript(f)
+    }
+}
+
+impl ToReScript for CamlModule {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        for item in &self.structure {
+            write!(f, "{}", ReScript(item))?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToReScript for CamlField {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if let Some(doc) = &self.doc {
+            write!(f, "/* {} */ ", doc.trim())?;
+        }
+        write!(f, "{}: {}", self.name, ReScript(&self.type_))
+    }
+}
+
+impl ToReScript for CamlRecord {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut fields = self.fields.clone();
+        fields.sort();
+
+        writeln!(f, "{{")?;
+        for field in fields {
+            writeln!(f, "  {},", ReScript(&field))?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl ToReScript for CamlVariant {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CamlVariant::InlineRecord(r) => write!(f, "({})", ReScript(r)),
+
+            CamlVariant::Constructor(cs) => {
+                if !cs.is_empty() {
+                    write!(f, "({}", ReScript(&cs[0]))?;
+                    for c in cs[1..].iter() {
+                        write!(f, ", {}", ReScript(c))?;
+                    }
+                    write!(f, ")")?;
+                };
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ToReScript for CamlType {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CamlType::Reference {
+                type_name,
+                module_path,
+            } if module_path.is_empty() => {
+                write!(f, "{}", type_name)
+            }
+            CamlType::Reference {
+                module_path,
+                type_name,
+            } => write!(f, "{}.{}", module_path, type_name),
+            CamlType::Abstract(name) => write!(f, "type {}", name),
+            CamlType::Alias { name, type_ } => {
+                write!(f, "type {} = {}", name, ReScript(type_.as_ref()))
+            }
+            CamlType::Labeled { label, type_ } => {
+                write!(f, "~{}: {}", label, ReScript(type_.as_ref()))
+            }
+            CamlType::Apply { constructor, arg } => {
+                write!(f, "{}<{}>", constructor, ReScript(arg.as_ref()))
+            }
+            CamlType::Tuple(types) => {
+                let types: Vec<String> = types.iter().map(|t| ReScript(t).to_string()).collect();
+                write!(f, "({})", types.join(", "))
+            }
+            CamlType::Record { name, record } => {
+                writeln!(f, "type {} = {}", name, ReScript(record))
+            }
+            CamlType::Variant { name, variants } => {
+                writeln!(f, "type {} =", name)?;
+                for v in variants {
+                    writeln!(f, "  | {}", ReScript(v))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ToReScript for CamlFun {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.args.as_slice() {
+            [] => write!(f, "unit")?,
+            [arg] if !matches!(arg, CamlType::Labeled { .. }) => write!(f, "{}", ReScript(arg))?,
+            [first, rest @ ..] => {
+                write!(f, "({}", ReScript(first))?;
+                for arg in rest {
+                    write!(f, ", {}", ReScript(arg))?;
+                }
+                write!(f, ")")?;
+            }
+        }
+        write!(f, " => {}", ReScript(&self.return_))
+    }
+}
+
+impl ToReScript for CamlBinding {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "let {}: {}", self.name, ReScript(&self.val))
+    }
+}
+
+impl ToReScript for CamlExpr {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CamlExpr::Var(name) => write!(f, "{}", name),
+            CamlExpr::String(s) => write!(f, "{:?}", s),
+            CamlExpr::Field { expr, field } => write!(f, "{}.{}", ReScript(expr.as_ref()), field),
+            CamlExpr::Record(fields) => {
+                write!(f, "{{{}: {}", fields[0].0, ReScript(&fields[0].1))?;
+                for (name, expr) in fields[1..].iter() {
+                    write!(f, ", {}: {}", name, ReScript(expr))?;
+                }
+                write!(f, "}}")
+            }
+            CamlExpr::Tuple(exprs) => {
+                let exprs: Vec<String> = exprs.iter().map(|e| ReScript(e).to_string()).collect();
+                write!(f, "({})", exprs.join(", "))
+            }
+            CamlExpr::List(exprs) => {
+                let exprs: Vec<String> = exprs.iter().map(|e| ReScript(e).to_string()).collect();
+                write!(f, "list{{{}}}", exprs.join(", "))
+            }
+            CamlExpr::Apply { fun, args } => {
+                let args: Vec<String> = args.iter().map(|e| ReScript(e).to_string()).collect();
+                write!(f, "{}({})", ReScript(fun.as_ref()), args.join(", "))
+            }
+            CamlExpr::PolyVariant { tag, arg } => write!(f, "#{}({})", tag, ReScript(arg.as_ref())),
+            // ReScript has no binding operators, so `let*` is spelled out as a switch on the result
+            CamlExpr::LetOp {
+                name, value, body, ..
+            } => write!(
+                f,
+                "switch {} {{\n  | Error(e) => Error(e)\n  | Ok({}) =>\n  {}\n  }}",
+                ReScript(value.as_ref()),
+                name,
+                ReScript(body.as_ref())
+            ),
+            CamlExpr::LetOpen { module_path, body } => {
+                write!(
+                    f,
+                    "{{\n  open {}\n  {}\n}}",
+                    module_path,
+                    ReScript(body.as_ref())
+                )
+            }
+        }
+    }
+}
+
+impl ToReScript for CamlLet {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "let {}{} = ({}) => {}",
+            if self.recursive { "rec " } else { "" },
+            self.name,
+            self.args.join(", "),
+            ReScript(&self.body)
+        )
+    }
+}
+
+impl ToReScript for CamlValueDesc {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            CamlValueDesc::Module(m) => write!(f, "{}", ReScript(m)),
+            CamlValueDesc::Alias { name, target } => write!(f, "module {} = {}", name, target),
+            CamlValueDesc::Type(t) => write!(f, "{}", ReScript(t)),
+            CamlValueDesc::Binding(b) => write!(f, "{}", ReScript(b)),
+            CamlValueDesc::Let(l) => write!(f, "{}", ReScript(l)),
+        }
+    }
+}
+
+impl ToReScript for CamlValue {
+    fn fmt_rescript(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        if let Some(doc) = &self.doc {
+            writeln!(f, "/*")?;
+            writeln!(f, "  {}", doc.trim())?;
+            writeln!(f, "*/")?;
+        }
+        write!(f, "{}", ReScript(&self.value))
+    }
+}
@@ -0,0 +1,20 @@
+use crate::emitter_error::*;
+use crate::source_set::*;
+use std::path::PathBuf;
+
+/// Writes the SHACL shapes the store derives from its kinds and relations as Turtle.
+#[derive(Default)]
+pub struct ShaclEmitter {}
+
+impl ShaclEmitter {
+    pub fn new() -> ShaclEmitter {
+        ShaclEmitter::default()
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from("shapes.ttl"),
+            store.shapes().to_turtle(),
+        )]))
+    }
+}
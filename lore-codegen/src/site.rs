@@ -0,0 +1,428 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use lore_ast::Localized;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+const SEARCH_JS: &str = include_str!("site/search.js");
+const STYLE_CSS: &str = include_str!("site/style.css");
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Everything a page needs to know about one kind or attribute.
+struct Entry<'a> {
+    name: &'a lore_ast::Name,
+    kind: Option<&'a lore_ast::Kind>,
+    fields: &'a [lore_ast::Field],
+    path: String,
+}
+
+impl<'a> Entry<'a> {
+    fn type_name(&self) -> &'static str {
+        match self.kind {
+            Some(kind) if kind.is_enumeration() => "enumeration",
+            Some(_) => "kind",
+            None => "attribute",
+        }
+    }
+}
+
+/// Emits a static HTML site with a page per kind and attribute, a namespace index, and a search
+/// index that works without a server.
+pub struct SiteEmitter {
+    title: String,
+    docs: Docs,
+}
+
+impl Default for SiteEmitter {
+    fn default() -> SiteEmitter {
+        SiteEmitter {
+            title: "Ontology".to_string(),
+            docs: Docs::new(),
+        }
+    }
+}
+
+impl SiteEmitter {
+    pub fn new() -> SiteEmitter {
+        SiteEmitter::default()
+    }
+
+    pub fn with_title(self, title: String) -> SiteEmitter {
+        SiteEmitter { title, ..self }
+    }
+
+    /// The languages used for short summaries. Pages always show every language.
+    pub fn with_docs(self, docs: Docs) -> SiteEmitter {
+        SiteEmitter { docs, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let mut scope = Scope::for_store(store);
+        let mut entries = vec![];
+        for attr in store.attributes() {
+            entries.push(Entry {
+                name: &attr.name,
+                kind: None,
+                fields: &attr.fields,
+                path: scope.claim(SiteEmitter::page_path(&attr.name), &attr.name)?,
+            });
+        }
+        for kind in store.kinds() {
+            entries.push(Entry {
+                name: &kind.name,
+                kind: Some(kind),
+                fields: &kind.fields,
+                path: scope.claim(SiteEmitter::page_path(&kind.name), &kind.name)?,
+            });
+        }
+        let paths: HashMap<lore_ast::URI, &String> = entries
+            .iter()
+            .map(|entry| (entry.name.to_uri(), &entry.path))
+            .collect();
+
+        let mut sources = vec![Source::new(
+            PathBuf::from("index.html"),
+            self.index(&entries),
+        )];
+        for entry in &entries {
+            sources.push(Source::new(
+                PathBuf::from(&entry.path),
+                self.page(store, entry, &paths),
+            ));
+        }
+        sources.push(Source::new(
+            PathBuf::from("search-index.js"),
+            self.search_index(&entries),
+        ));
+        sources.push(Source::new(
+            PathBuf::from("search.js"),
+            SEARCH_JS.to_string(),
+        ));
+        sources.push(Source::new(
+            PathBuf::from("style.css"),
+            STYLE_CSS.to_string(),
+        ));
+
+        Ok(SourceSet::from_sources(sources))
+    }
+
+    /// `dota:v2022/Hero` lives in `dota/v2022/Hero.html`.
+    fn page_path(name: &lore_ast::Name) -> String {
+        let file_name = |segment: &str| -> String {
+            segment
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        };
+        let mut segments: Vec<String> = namespace_segments(name)
+            .iter()
+            .map(|segment| file_name(segment))
+            .collect();
+        segments.push(format!("{}.html", file_name(&local_name(name))));
+        segments.join("/")
+    }
+
+    fn root(path: &str) -> String {
+        "../".repeat(path.matches('/').count())
+    }
+
+    fn layout(&self, root: &str, title: &str, main: &str) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<link rel="stylesheet" href="{root}style.css">
+</head>
+<body>
+<header>
+<a href="{root}index.html">{site}</a>
+<input id="search" type="search" placeholder="Search" autocomplete="off">
+<ul id="search-results"></ul>
+</header>
+<main>
+{main}</main>
+<script>window.LORE_ROOT = "{root}";</script>
+<script src="{root}search-index.js"></script>
+<script src="{root}search.js"></script>
+</body>
+</html>
+"#,
+            title = escape(title),
+            root = root,
+            site = escape(&self.title),
+            main = main,
+        )
+    }
+
+    fn index(&self, entries: &[Entry]) -> String {
+        let mut namespaces: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+        for entry in entries {
+            namespaces
+                .entry(namespace(entry.name))
+                .or_default()
+                .push(entry);
+        }
+
+        let mut main = format!("<h1>{}</h1>\n", escape(&self.title));
+        for (namespace, mut entries) in namespaces {
+            entries.sort_by_key(|entry| entry.name.to_string());
+            main.push_str(&format!(
+                "<section>\n<h2><code>{}</code></h2>\n<table>\n",
+                escape(&namespace)
+            ));
+            for entry in entries {
+                main.push_str(&format!(
+                    "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                    escape(&entry.path),
+                    escape(&local_name(entry.name)),
+                    entry.type_name(),
+                    escape(&self.docs.doc(entry.fields).unwrap_or_default())
+                ));
+            }
+            main.push_str("</table>\n</section>\n");
+        }
+
+        self.layout("", &self.title, &main)
+    }
+
+    fn page(
+        &self,
+        store: &lore_store::Store,
+        entry: &Entry,
+        paths: &HashMap<lore_ast::URI, &String>,
+    ) -> String {
+        let root = SiteEmitter::root(&entry.path);
+        let link = |name: &lore_ast::Name| match paths.get(&name.to_uri()) {
+            Some(path) => format!(
+                "<a href=\"{}{}\">{}</a>",
+                root,
+                escape(path),
+                escape(&local_name(name))
+            ),
+            None => match lore_ast::Primitive::from_uri(&name.uri) {
+                Some(primitive) => format!("<code>{}</code>", primitive.name()),
+                None => format!("<code>{}</code>", escape(&name.to_string())),
+            },
+        };
+        let texts = |localized: Localized, fields: &[lore_ast::Field]| -> String {
+            localized
+                .texts(fields)
+                .iter()
+                .map(|(lang, text)| match lang.as_str() {
+                    "" => escape(text),
+                    lang => format!("<span lang=\"{}\">{}</span>", escape(lang), escape(text)),
+                })
+                .collect::<Vec<String>>()
+                .join("<br>")
+        };
+
+        let mut main = format!(
+            "<h1>{} <span class=\"badge\">{}</span></h1>\n<p><code>{}</code></p>\n",
+            escape(&local_name(entry.name)),
+            entry.type_name(),
+            escape(&entry.name.to_string())
+        );
+        if let Some(file) = store.declared_in.get(&entry.name.to_uri()) {
+            main.push_str(&format!(
+                "<p>Declared in <code>{}</code></p>\n",
+                escape(&file.display().to_string())
+            ));
+        }
+
+        for (localized, heading) in [
+            (Localized::Label, "Labels"),
+            (Localized::Doc, "Documentation"),
+        ] {
+            let texts = localized.texts(entry.fields);
+            if texts.is_empty() {
+                continue;
+            }
+            main.push_str(&format!("<section>\n<h2>{}</h2>\n<dl>\n", heading));
+            for (lang, text) in texts {
+                let lang = if lang.is_empty() { "any" } else { &lang };
+                main.push_str(&format!(
+                    "<dt>{}</dt>\n<dd lang=\"{}\">{}</dd>\n",
+                    escape(lang),
+                    escape(lang),
+                    escape(&text)
+                ));
+            }
+            main.push_str("</dl>\n</section>\n");
+        }
+
+        if let Some(kind) = entry.kind {
+            let variants = kind.variants();
+            if !variants.is_empty() {
+                main.push_str("<section>\n<h2>Variants</h2>\n<ul>\n");
+                for variant in variants {
+                    main.push_str(&format!(
+                        "<li>{} <code>{}</code></li>\n",
+                        escape(&local_name(variant)),
+                        escape(&variant.to_string())
+                    ));
+                }
+                main.push_str("</ul>\n</section>\n");
+            }
+        }
+
+        if let Some(rels) = store.relations_by_subject.get(&entry.name.to_uri()) {
+            main.push_str("<section>\n<h2>Relations</h2>\n<table>\n");
+            main.push_str("<tr><th>Predicate</th><th>Object</th><th>Cardinality</th><th>Documentation</th></tr>\n");
+            for rel in rels {
+                main.push_str(&format!(
+                    "<tr><td><code title=\"{}\">{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape(&rel.predicate.to_string()),
+                    escape(&local_name(&rel.predicate)),
+                    link(&rel.object),
+                    rel.cardinality(),
+                    texts(Localized::Doc, &rel.fields)
+                ));
+            }
+            main.push_str("</table>\n</section>\n");
+        }
+
+        let mut incoming: Vec<&lore_ast::Relation> = store
+            .relations_by_subject
+            .values()
+            .flatten()
+            .filter(|rel| rel.object.uri == entry.name.uri)
+            .collect();
+        incoming.sort_by_key(|rel| (rel.subject.to_string(), rel.predicate.to_string()));
+        if !incoming.is_empty() {
+            main.push_str("<section>\n<h2>Used by</h2>\n<table>\n");
+            main.push_str("<tr><th>Subject</th><th>Predicate</th></tr>\n");
+            for rel in incoming {
+                main.push_str(&format!(
+                    "<tr><td>{}</td><td><code title=\"{}\">{}</code></td></tr>\n",
+                    link(&rel.subject),
+                    escape(&rel.predicate.to_string()),
+                    escape(&local_name(&rel.predicate))
+                ));
+            }
+            main.push_str("</table>\n</section>\n");
+        }
+
+        let title = format!("{} · {}", local_name(entry.name), namespace(entry.name));
+        self.layout(&root, &title, &main)
+    }
+
+    /// Loaded with a `<script>` tag instead of fetched, so the site also works from `file://`.
+    fn search_index(&self, entries: &[Entry]) -> String {
+        let index: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "name": local_name(entry.name),
+                    "uri": entry.name.to_string(),
+                    "type": entry.type_name(),
+                    "doc": self.docs.doc(entry.fields).unwrap_or_default(),
+                    "path": entry.path,
+                })
+            })
+            .collect();
+        format!(
+            "window.LORE_SEARCH_INDEX = {};\n",
+            serde_json::to_string_pretty(&index).unwrap()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $files:expr, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let sources = SiteEmitter::new().translate(&store).unwrap();
+                let pages: Vec<String> = sources
+                    .sources()
+                    .iter()
+                    .filter(|source| $files.contains(&source.name().to_str().unwrap()))
+                    .map(|source| format!("== {}\n{}", source.name().display(), source.contents()))
+                    .collect();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{}
+"#,
+                    $src,
+                    pages.join("\n")
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    const HEROES: &str = r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/label/en "Hero"
+  @lore/label/es "Héroe"
+  @lore/doc/en "A playable character. Heroes <3 items & abilities."
+  @lore/doc/es "Un personaje jugable."
+}
+
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+}
+
+attr Name
+
+rel Hero mainName Name
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+
+rel Hero hasAbility Ability {
+  @lore/doc/en "Abilities this hero can cast."
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Ability ownedBy Hero {
+  @lore/cardinality @lore/many
+}
+
+        "#;
+
+    test!(index_and_search, ["index.html", "search-index.js"], HEROES);
+
+    test!(
+        kind_pages,
+        ["dota/v2022/Hero.html", "dota/v2022/PrimaryAttribute.html"],
+        HEROES
+    );
+}
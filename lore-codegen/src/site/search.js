@@ -0,0 +1,37 @@
+(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("search-results");
+  var index = window.LORE_SEARCH_INDEX || [];
+
+  function matches(entry, query) {
+    return [entry.name, entry.uri, entry.doc].some(function (text) {
+      return text.toLowerCase().indexOf(query) !== -1;
+    });
+  }
+
+  input.addEventListener("input", function () {
+    var query = input.value.trim().toLowerCase();
+    results.innerHTML = "";
+    if (query === "") {
+      return;
+    }
+
+    index
+      .filter(function (entry) {
+        return matches(entry, query);
+      })
+      .slice(0, 20)
+      .forEach(function (entry) {
+        var item = document.createElement("li");
+        var link = document.createElement("a");
+        link.href = window.LORE_ROOT + entry.path;
+        link.textContent = entry.name;
+        var uri = document.createElement("code");
+        uri.textContent = entry.uri;
+        item.appendChild(link);
+        item.appendChild(document.createTextNode(" "));
+        item.appendChild(uri);
+        results.appendChild(item);
+      });
+  });
+})();
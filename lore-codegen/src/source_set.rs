@@ -1,5 +1,5 @@
 use miette::Diagnostic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -23,7 +23,15 @@ impl Source {
         Source { name, contents }
     }
 
-    pub fn write(&self, prefix: &PathBuf) -> Result<(), SourceError> {
+    pub fn name(&self) -> &PathBuf {
+        &self.name
+    }
+
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    pub fn write(&self, prefix: &Path) -> Result<(), SourceError> {
         let path = prefix.join(self.name.clone());
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).map_err(|error| SourceError {
@@ -0,0 +1,519 @@
+use crate::docs::*;
+use crate::emitter_error::*;
+use crate::naming::*;
+use crate::source_set::*;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    SQLite,
+}
+
+impl std::fmt::Display for SqlDialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SqlDialect::Postgres => "postgres",
+            SqlDialect::SQLite => "sqlite",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for SqlDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<SqlDialect, String> {
+        match s {
+            "postgres" | "postgresql" => Ok(SqlDialect::Postgres),
+            "sqlite" => Ok(SqlDialect::SQLite),
+            _ => Err(format!(
+                "Could not find SQL dialect: {}. Try one of: postgres | sqlite",
+                s
+            )),
+        }
+    }
+}
+
+impl SqlDialect {
+    pub fn primitive(&self, primitive: lore_ast::Primitive) -> String {
+        match (self, primitive) {
+            (_, lore_ast::Primitive::String) | (_, lore_ast::Primitive::URI) => "TEXT",
+            (SqlDialect::Postgres, lore_ast::Primitive::Int) => "BIGINT",
+            (SqlDialect::Postgres, lore_ast::Primitive::Float) => "DOUBLE PRECISION",
+            (SqlDialect::Postgres, lore_ast::Primitive::Bool) => "BOOLEAN",
+            (SqlDialect::Postgres, lore_ast::Primitive::Date) => "DATE",
+            (SqlDialect::Postgres, lore_ast::Primitive::DateTime) => "TIMESTAMPTZ",
+            // SQLite only has storage classes: booleans are integers, and dates are ISO 8601 text
+            (SqlDialect::SQLite, lore_ast::Primitive::Int)
+            | (SqlDialect::SQLite, lore_ast::Primitive::Bool) => "INTEGER",
+            (SqlDialect::SQLite, lore_ast::Primitive::Float) => "REAL",
+            (SqlDialect::SQLite, lore_ast::Primitive::Date)
+            | (SqlDialect::SQLite, lore_ast::Primitive::DateTime) => "TEXT",
+        }
+        .to_string()
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlColumn {
+    name: String,
+    type_: String,
+    not_null: bool,
+    references: Option<String>,
+    one_of: Vec<String>,
+    doc: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlTable {
+    name: String,
+    columns: Vec<SqlColumn>,
+    primary_key: Vec<String>,
+    doc: Option<String>,
+}
+
+/// A kind with variants. Postgres gets an enum type, while SQLite checks the values in every
+/// column of this type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlEnum {
+    name: String,
+    values: Vec<String>,
+    doc: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlSchema {
+    dialect: SqlDialect,
+    enums: Vec<SqlEnum>,
+    tables: Vec<SqlTable>,
+    join_tables: Vec<SqlTable>,
+}
+
+impl SqlSchema {
+    /// Postgres cannot reference a table before it exists, so the foreign keys of the tables for
+    /// kinds are added once every table is created. SQLite only checks them when writing rows.
+    fn inline_references(&self, table: &SqlTable) -> bool {
+        self.dialect == SqlDialect::SQLite || self.join_tables.contains(table)
+    }
+
+    /// Rows of a join table go away with either side, while a missing optional value is
+    /// cleared. Required values keep their instance from being deleted.
+    fn foreign_key(&self, table: &SqlTable, column: &SqlColumn, references: &str) -> String {
+        let on_delete = if self.join_tables.contains(table) {
+            " ON DELETE CASCADE"
+        } else if !column.not_null {
+            " ON DELETE SET NULL"
+        } else {
+            ""
+        };
+        format!(" REFERENCES {} (id){}", references, on_delete)
+    }
+
+    fn write_table(&self, f: &mut std::fmt::Formatter<'_>, table: &SqlTable) -> std::fmt::Result {
+        if self.dialect == SqlDialect::SQLite {
+            if let Some(doc) = &table.doc {
+                for line in doc.lines() {
+                    writeln!(f, "-- {}", line)?;
+                }
+            }
+        }
+
+        writeln!(f, "CREATE TABLE {} (", table.name)?;
+        let mut lines = vec![];
+        for column in &table.columns {
+            let mut line = String::new();
+            if self.dialect == SqlDialect::SQLite {
+                if let Some(doc) = &column.doc {
+                    for doc_line in doc.lines() {
+                        line.push_str(&format!("  -- {}\n", doc_line));
+                    }
+                }
+            }
+            line.push_str(&format!("  {} {}", column.name, column.type_));
+            if column.not_null {
+                line.push_str(" NOT NULL");
+            }
+            if !column.one_of.is_empty() {
+                let values: Vec<String> = column.one_of.iter().map(|v| quote(v)).collect();
+                line.push_str(&format!(
+                    " CHECK ({} IN ({}))",
+                    column.name,
+                    values.join(", ")
+                ));
+            }
+            if let Some(references) = &column.references {
+                if self.inline_references(table) {
+                    line.push_str(&self.foreign_key(table, column, references));
+                }
+            }
+            lines.push(line);
+        }
+        lines.push(format!("  PRIMARY KEY ({})", table.primary_key.join(", ")));
+        writeln!(f, "{}", lines.join(",\n"))?;
+        writeln!(f, ");")?;
+
+        if self.dialect == SqlDialect::Postgres {
+            if let Some(doc) = &table.doc {
+                writeln!(f, "COMMENT ON TABLE {} IS {};", table.name, quote(doc))?;
+            }
+            for column in &table.columns {
+                if let Some(doc) = &column.doc {
+                    writeln!(
+                        f,
+                        "COMMENT ON COLUMN {}.{} IS {};",
+                        table.name,
+                        column.name,
+                        quote(doc)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SqlSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut first = true;
+        let mut separate = |f: &mut std::fmt::Formatter<'_>| {
+            if first {
+                first = false;
+                Ok(())
+            } else {
+                writeln!(f)
+            }
+        };
+
+        if self.dialect == SqlDialect::Postgres {
+            for enum_ in &self.enums {
+                separate(f)?;
+                let values: Vec<String> = enum_.values.iter().map(|v| quote(v)).collect();
+                writeln!(
+                    f,
+                    "CREATE TYPE {} AS ENUM ({});",
+                    enum_.name,
+                    values.join(", ")
+                )?;
+                if let Some(doc) = &enum_.doc {
+                    writeln!(f, "COMMENT ON TYPE {} IS {};", enum_.name, quote(doc))?;
+                }
+            }
+        }
+
+        for table in self.tables.iter().chain(self.join_tables.iter()) {
+            separate(f)?;
+            self.write_table(f, table)?;
+        }
+
+        if self.dialect == SqlDialect::Postgres {
+            let mut foreign_keys = vec![];
+            for table in &self.tables {
+                for column in &table.columns {
+                    if let Some(references) = &column.references {
+                        foreign_keys.push(format!(
+                            "ALTER TABLE {} ADD FOREIGN KEY ({}){};",
+                            table.name,
+                            column.name,
+                            self.foreign_key(table, column, references)
+                        ));
+                    }
+                }
+            }
+            if !foreign_keys.is_empty() {
+                separate(f)?;
+                for foreign_key in foreign_keys {
+                    writeln!(f, "{}", foreign_key)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Emits a `schema.sql` with a table per kind, keyed by the URI of each instance. Relations to
+/// a single value become columns, and relations to many values become join tables.
+pub struct SqlEmitter {
+    naming: Naming,
+    docs: Docs,
+    dialect: SqlDialect,
+}
+
+impl Default for SqlEmitter {
+    fn default() -> SqlEmitter {
+        SqlEmitter {
+            naming: Naming::sql(),
+            docs: Docs::new(),
+            dialect: SqlDialect::Postgres,
+        }
+    }
+}
+
+impl SqlEmitter {
+    pub fn new() -> SqlEmitter {
+        SqlEmitter::default()
+    }
+
+    pub fn with_naming(self, naming: Naming) -> SqlEmitter {
+        SqlEmitter { naming, ..self }
+    }
+
+    pub fn with_docs(self, docs: Docs) -> SqlEmitter {
+        SqlEmitter { docs, ..self }
+    }
+
+    pub fn with_dialect(self, dialect: SqlDialect) -> SqlEmitter {
+        SqlEmitter { dialect, ..self }
+    }
+
+    pub fn translate(&self, store: &lore_store::Store) -> Result<SourceSet, EmitterError> {
+        let schema = self.schema(store)?;
+        Ok(SourceSet::from_sources(vec![Source::new(
+            PathBuf::from("schema.sql"),
+            schema.to_string(),
+        )]))
+    }
+
+    pub fn schema(&self, store: &lore_store::Store) -> Result<SqlSchema, EmitterError> {
+        // tables and types share a namespace in Postgres
+        let mut scope = Scope::for_store(store);
+        let mut enums = vec![];
+        let mut tables = vec![];
+        let mut join_tables = vec![];
+
+        for kind in store.kinds() {
+            let name = scope.claim(self.naming.type_name(&kind.name), &kind.name)?;
+            if kind.is_enumeration() {
+                enums.push(SqlEnum {
+                    name,
+                    values: kind.variants().iter().map(|v| v.to_string()).collect(),
+                    doc: self.docs.doc(&kind.fields),
+                });
+                continue;
+            }
+
+            let mut column_scope = Scope::for_store(store).reserve("id");
+            let mut columns = vec![SqlColumn {
+                name: "id".to_string(),
+                type_: "TEXT".to_string(),
+                not_null: true,
+                references: None,
+                one_of: vec![],
+                doc: None,
+            }];
+
+            let rels = store
+                .relations_by_subject
+                .get(&kind.name.to_uri())
+                .cloned()
+                .unwrap_or_default();
+            for rel in rels {
+                let doc = self.docs.doc(&rel.fields);
+                let multiplicity = rel.cardinality().multiplicity();
+                let value = self.column_type(store, &rel.object);
+
+                match multiplicity {
+                    lore_ast::Multiplicity::One | lore_ast::Multiplicity::Optional => {
+                        columns.push(SqlColumn {
+                            name: column_scope
+                                .claim(self.naming.value_name(&rel.predicate), &rel.predicate)?,
+                            not_null: multiplicity == lore_ast::Multiplicity::One,
+                            doc,
+                            ..value
+                        });
+                    }
+                    lore_ast::Multiplicity::List | lore_ast::Multiplicity::NonEmptyList => {
+                        let join_name = self.naming.value_name(&rel.predicate);
+                        let join_table = scope.claim(
+                            format!("{}_{}", name, join_name.trim_end_matches('_')),
+                            &rel.predicate,
+                        )?;
+                        let subject_column = format!("{}_id", name.trim_end_matches('_'));
+                        let object_column = if value.references.is_some() {
+                            let object = self.naming.type_name(&rel.object);
+                            let object = format!("{}_id", object.trim_end_matches('_'));
+                            if object == subject_column {
+                                format!("{}_id", join_name.trim_end_matches('_'))
+                            } else {
+                                object
+                            }
+                        } else {
+                            "value".to_string()
+                        };
+
+                        join_tables.push(SqlTable {
+                            name: join_table,
+                            columns: vec![
+                                SqlColumn {
+                                    name: subject_column.clone(),
+                                    type_: "TEXT".to_string(),
+                                    not_null: true,
+                                    references: Some(name.clone()),
+                                    one_of: vec![],
+                                    doc: None,
+                                },
+                                SqlColumn {
+                                    name: object_column.clone(),
+                                    not_null: true,
+                                    doc: None,
+                                    ..value
+                                },
+                            ],
+                            primary_key: vec![subject_column, object_column],
+                            doc,
+                        });
+                    }
+                }
+            }
+
+            tables.push(SqlTable {
+                name,
+                columns,
+                primary_key: vec!["id".to_string()],
+                doc: self.docs.doc(&kind.fields),
+            });
+        }
+
+        Ok(SqlSchema {
+            dialect: self.dialect,
+            enums,
+            tables,
+            join_tables,
+        })
+    }
+
+    /// The column for a value of some type, without a name. Kinds are referenced by their id, and
+    /// attributes are stored as text.
+    fn column_type(&self, store: &lore_store::Store, object: &lore_ast::Name) -> SqlColumn {
+        let column = SqlColumn {
+            name: "".to_string(),
+            type_: "TEXT".to_string(),
+            not_null: false,
+            references: None,
+            one_of: vec![],
+            doc: None,
+        };
+
+        if let Some(primitive) = lore_ast::Primitive::from_uri(&object.uri) {
+            return SqlColumn {
+                type_: self.dialect.primitive(primitive),
+                ..column
+            };
+        }
+
+        match store.kinds.get(&object.to_uri()) {
+            Some(kind) if kind.is_enumeration() => match self.dialect {
+                SqlDialect::Postgres => SqlColumn {
+                    type_: self.naming.type_name(object),
+                    ..column
+                },
+                SqlDialect::SQLite => SqlColumn {
+                    one_of: kind.variants().iter().map(|v| v.to_string()).collect(),
+                    ..column
+                },
+            },
+            Some(_) => SqlColumn {
+                references: Some(self.naming.type_name(object)),
+                ..column
+            },
+            None => column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            test!($name, SqlEmitter::new(), $src);
+        };
+        ($name:ident, $emitter:expr, $src:expr) => {
+            #[test]
+            fn $name() {
+                let mut store = lore_store::Store::new();
+                let store = store.add_from_string($src).unwrap();
+                let emitter = $emitter;
+                let sources = emitter.translate(&store).unwrap();
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{:#?}
+"#,
+                    $src, sources
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    const HEROES: &str = r#"
+
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero {
+  @lore/doc/en "A playable character. Don't feed."
+}
+
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+  @lore/variant Intelligence
+}
+
+attr Name
+
+rel Hero mainName Name
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+rel Hero releasedAt @lore/DateTime
+
+rel Hero hasAbility Ability {
+  @lore/doc/en "Abilities this hero can cast."
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Hero hasTalent Ability {
+  @lore/cardinality/max 1
+}
+
+rel Hero counters Hero {
+  @lore/cardinality @lore/many
+}
+
+rel Ability manaCost @lore/Float {
+  @lore/doc/en "Mana spent on every cast."
+}
+
+rel Ability tags @lore/String {
+  @lore/cardinality @lore/many
+}
+
+        "#;
+
+    test!(kinds_to_postgres_tables, HEROES);
+
+    test!(
+        kinds_to_sqlite_tables,
+        SqlEmitter::new()
+            .with_naming(Naming::sql().with_strip_namespace(true))
+            .with_dialect(SqlDialect::SQLite),
+        HEROES
+    );
+}
@@ -0,0 +1,9 @@
+pub mod lsp_error;
+pub use lsp_error::*;
+
+pub mod position;
+pub mod server;
+pub mod workspace;
+
+pub use server::run;
+pub use workspace::Workspace;
@@ -0,0 +1,21 @@
+use miette::Diagnostic;
+use thiserror::Error;
+
+#[derive(Diagnostic, Error, Debug)]
+pub enum LspError {
+    #[error("The language server could not talk to the editor: {0}")]
+    #[diagnostic(code(lore::lsp::protocol))]
+    Protocol(#[from] lsp_server::ProtocolError),
+
+    #[error("The editor sent a message we could not read: {0}")]
+    #[diagnostic(code(lore::lsp::invalid_message))]
+    InvalidMessage(#[from] serde_json::Error),
+
+    #[error("The editor went away before we could answer")]
+    #[diagnostic(code(lore::lsp::disconnected))]
+    Disconnected,
+
+    #[error(transparent)]
+    #[diagnostic(code(lore::lsp::io))]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,73 @@
+use lore_ast::Span;
+use lsp_types::{Position, Range};
+
+/// Converts between byte offsets, which the lexer uses, and the lines and UTF-16 columns the
+/// protocol uses.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        for (i, c) in text.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        LineIndex { text, line_starts }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let start = self.line_starts[line];
+        let character: usize = self.text[start..offset]
+            .chars()
+            .map(|c| c.len_utf16())
+            .sum();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn offset(&self, position: Position) -> usize {
+        let start = match self.line_starts.get(position.line as usize) {
+            Some(start) => *start,
+            None => return self.text.len(),
+        };
+        let mut column = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if column >= position.character as usize || c == '\n' {
+                return start + i;
+            }
+            column += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        Range::new(
+            self.position(span.offset),
+            self.position(span.offset + span.length),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_and_offsets() {
+        let index = LineIndex::new("kind Héroe\nattr Name\n");
+        assert_eq!(index.position(0), Position::new(0, 0));
+        assert_eq!(index.position(12), Position::new(1, 0));
+        assert_eq!(index.position(17), Position::new(1, 5));
+        assert_eq!(index.offset(Position::new(0, 7)), 8);
+        assert_eq!(index.offset(Position::new(1, 5)), 17);
+        assert_eq!(index.offset(Position::new(0, 99)), 11);
+    }
+}
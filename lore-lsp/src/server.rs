@@ -0,0 +1,236 @@
+use crate::lsp_error::*;
+use crate::workspace::Workspace;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::Notification as _;
+use lsp_types::request::Request as _;
+use lsp_types::*;
+
+/// Speaks the Language Server Protocol over stdin and stdout until the editor shuts us down.
+pub fn run() -> Result<(), LspError> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["@".to_string(), "/".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let mut server = Server {
+        connection,
+        workspace: Workspace::new(),
+    };
+    #[allow(deprecated)]
+    let roots: Vec<Url> = match (params.workspace_folders, params.root_uri) {
+        (Some(folders), _) => folders.into_iter().map(|folder| folder.uri).collect(),
+        (None, Some(root)) => vec![root],
+        (None, None) => vec![],
+    };
+    for root in roots {
+        if let Ok(path) = root.to_file_path() {
+            server.workspace.scan(&path);
+        }
+    }
+    server.publish_diagnostics()?;
+    server.serve()?;
+
+    // the writer thread only stops once nothing can send to it
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server {
+    connection: Connection,
+    workspace: Workspace,
+}
+
+impl Server {
+    fn serve(&mut self) -> Result<(), LspError> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.respond(request);
+                    self.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => self.notify(notification)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: Message) -> Result<(), LspError> {
+        self.connection
+            .sender
+            .send(message)
+            .map_err(|_| LspError::Disconnected)
+    }
+
+    /// Answers a request. One the server cannot read gets an error back, and the server keeps
+    /// going.
+    fn respond(&self, request: Request) -> Response {
+        let (id, method) = (request.id.clone(), request.method.clone());
+        match self.answer(request) {
+            Ok(Some(result)) => Response::new_ok(id, result),
+            Ok(None) => Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("lore does not know how to answer {}", method),
+            ),
+            Err(error) => Response::new_err(
+                id,
+                lsp_server::ErrorCode::InvalidParams as i32,
+                format!("lore could not read the params of {}: {}", method, error),
+            ),
+        }
+    }
+
+    fn answer(&self, request: Request) -> Result<Option<serde_json::Value>, serde_json::Error> {
+        let result = match request.method.as_str() {
+            request::GotoDefinition::METHOD => {
+                let params: GotoDefinitionParams = serde_json::from_value(request.params)?;
+                let position = params.text_document_position_params;
+                let locations = self
+                    .workspace
+                    .definition(&position.text_document.uri, position.position);
+                serde_json::to_value(GotoDefinitionResponse::Array(locations))?
+            }
+            request::HoverRequest::METHOD => {
+                let params: HoverParams = serde_json::from_value(request.params)?;
+                let position = params.text_document_position_params;
+                serde_json::to_value(
+                    self.workspace
+                        .hover(&position.text_document.uri, position.position),
+                )?
+            }
+            request::Completion::METHOD => {
+                let params: CompletionParams = serde_json::from_value(request.params)?;
+                let items = self
+                    .workspace
+                    .completion(&params.text_document_position.text_document.uri);
+                serde_json::to_value(CompletionResponse::Array(items))?
+            }
+            request::DocumentSymbolRequest::METHOD => {
+                let params: DocumentSymbolParams = serde_json::from_value(request.params)?;
+                let symbols = self.workspace.symbols(&params.text_document.uri);
+                serde_json::to_value(DocumentSymbolResponse::Nested(symbols))?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+
+    /// Applies a notification. Notifications get no answer, so one the server cannot read is
+    /// only logged.
+    fn notify(&mut self, notification: Notification) -> Result<(), LspError> {
+        let method = notification.method.clone();
+        match self.update(notification) {
+            Ok(true) => self.publish_diagnostics(),
+            Ok(false) => Ok(()),
+            Err(error) => {
+                eprintln!("lore could not read the params of {}: {}", method, error);
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether the notification changed any document.
+    fn update(&mut self, notification: Notification) -> Result<bool, serde_json::Error> {
+        match notification.method.as_str() {
+            notification::DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.workspace
+                    .update(params.text_document.uri, params.text_document.text);
+            }
+            notification::DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // we only ask for full syncs, so the last change has the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.workspace.update(params.text_document.uri, change.text);
+                }
+            }
+            notification::DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // go back to what is on disk, since other files may still use it
+                let url = params.text_document.uri;
+                match url.to_file_path().map(std::fs::read_to_string) {
+                    Ok(Ok(text)) => self.workspace.update(url, text),
+                    _ => self.workspace.remove(&url),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Things are checked against kinds in other files, so a change anywhere may fix or break
+    /// any document.
+    fn publish_diagnostics(&self) -> Result<(), LspError> {
+        for (uri, diagnostics) in self.workspace.diagnostics() {
+            let params = PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            };
+            self.send(Message::Notification(Notification::new(
+                notification::PublishDiagnostics::METHOD.to_string(),
+                params,
+            )))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> (Server, Connection) {
+        let (connection, client) = Connection::memory();
+        let server = Server {
+            connection,
+            workspace: Workspace::new(),
+        };
+        (server, client)
+    }
+
+    #[test]
+    fn answers_bad_params_with_an_error() {
+        let (server, _client) = server();
+        let request = Request::new(
+            1.into(),
+            request::HoverRequest::METHOD.to_string(),
+            serde_json::json!({ "position": "nowhere" }),
+        );
+        let response = server.respond(request);
+        assert_eq!(
+            response.error.map(|error| error.code),
+            Some(lsp_server::ErrorCode::InvalidParams as i32)
+        );
+    }
+
+    #[test]
+    fn skips_bad_notifications() {
+        let (mut server, client) = server();
+        let notification = Notification::new(
+            notification::DidOpenTextDocument::METHOD.to_string(),
+            serde_json::json!({ "textDocument": 42 }),
+        );
+        assert!(server.notify(notification).is_ok());
+        assert!(client.receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,809 @@
+use crate::position::LineIndex;
+use logos::Logos;
+use lore_ast::{Localized, Span, URI};
+use lore_parser::lexer::Token;
+use lore_parser::{Import, Parser, SemanticError, Validator};
+use lsp_types::*;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// What a name is doing where it is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Namespace,
+    Prefix,
+    Kind,
+    Attribute,
+    Relation,
+    Thing,
+    Reference,
+}
+
+impl Role {
+    fn describe(&self) -> &'static str {
+        match self {
+            Role::Namespace => "namespace",
+            Role::Prefix => "prefix",
+            Role::Kind => "kind",
+            Role::Attribute => "attribute",
+            Role::Relation => "relation",
+            Role::Thing => "thing",
+            Role::Reference => "name",
+        }
+    }
+
+    fn is_declaration(&self) -> bool {
+        !matches!(self, Role::Namespace | Role::Reference)
+    }
+}
+
+/// A name written in a document, and the URI it stands for when it can be resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Occurrence {
+    pub text: String,
+    pub uri: Option<URI>,
+    pub span: Span,
+    pub role: Role,
+}
+
+/// A `.lore` file as the editor sees it, which may not have been saved yet.
+pub struct Document {
+    pub path: PathBuf,
+    pub text: String,
+    pub occurrences: Vec<Occurrence>,
+    pub namespace: Option<URI>,
+    pub prefixes: Vec<(String, URI)>,
+    pub structure: Option<lore_ast::Structure>,
+    errors: Vec<(Span, String, &'static str)>,
+}
+
+impl Document {
+    pub fn new(path: PathBuf, text: String) -> Document {
+        let mut document = Document {
+            path,
+            text,
+            occurrences: vec![],
+            namespace: None,
+            prefixes: vec![],
+            structure: None,
+            errors: vec![],
+        };
+        document.scan();
+        document.check();
+        document
+    }
+
+    /// Finds every name in the document by lexing it, so that documents that do not parse can
+    /// still be navigated.
+    fn scan(&mut self) {
+        let mut occurrences = vec![];
+        // the keyword we are in, and how many names we have seen since
+        let mut context: Option<(Token, usize)> = None;
+        let mut prefixed_uri: Option<URI> = None;
+
+        for (token, range) in Token::lexer(&self.text).spanned() {
+            let span = Span {
+                offset: range.start,
+                length: range.end - range.start,
+            };
+            let text = match &token {
+                Token::URI(text) | Token::Text(text) => text.clone(),
+                Token::Using
+                | Token::Prefix
+                | Token::Kind
+                | Token::Attribute
+                | Token::Relation
+                | Token::Thing => {
+                    context = Some((token, 0));
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let seen = match &mut context {
+                Some((_, seen)) => {
+                    *seen += 1;
+                    *seen
+                }
+                None => 0,
+            };
+            let role = match (context.as_ref().map(|(keyword, _)| keyword), seen) {
+                (Some(Token::Using), 1) => {
+                    self.namespace = Some(URI::from_string(text.clone()));
+                    Role::Namespace
+                }
+                (Some(Token::Prefix), 1) => {
+                    prefixed_uri = Some(URI::from_string(text));
+                    continue;
+                }
+                (Some(Token::Prefix), 2) => match prefixed_uri.take() {
+                    Some(uri) => {
+                        self.prefixes.push((text.clone(), uri));
+                        Role::Prefix
+                    }
+                    None => continue,
+                },
+                (Some(Token::Kind), 1) => Role::Kind,
+                (Some(Token::Attribute), 1) => Role::Attribute,
+                (Some(Token::Relation), 2) => Role::Relation,
+                (Some(Token::Thing), 1) => Role::Thing,
+                _ => Role::Reference,
+            };
+            occurrences.push(Occurrence {
+                text,
+                uri: None,
+                span,
+                role,
+            });
+        }
+
+        for occurrence in &mut occurrences {
+            occurrence.uri = match occurrence.role {
+                Role::Namespace => Some(URI::from_string(occurrence.text.clone())),
+                Role::Prefix => self
+                    .prefixes
+                    .iter()
+                    .find(|(prefix, _)| *prefix == occurrence.text)
+                    .map(|(_, uri)| uri.clone()),
+                _ => self.resolve(&occurrence.text),
+            };
+        }
+        self.occurrences = occurrences;
+    }
+
+    /// Resolves a name the way the validator does.
+    fn resolve(&self, text: &str) -> Option<URI> {
+        let uri = URI::from_string(text.to_string());
+        if uri.is_prefixed() {
+            return self
+                .prefixes
+                .iter()
+                .find(|(prefix, _)| uri.has_prefix(prefix))
+                .map(|(prefix, expanded)| uri.expand_prefix(prefix, expanded));
+        }
+        if text.contains(':') {
+            return Some(uri);
+        }
+        self.namespace
+            .as_ref()
+            .map(|namespace| namespace.join(text))
+    }
+
+    fn check(&mut self) {
+        let filename = self.path.display().to_string();
+        let mut parser = match Parser::for_string(&filename, &self.text) {
+            Ok(parser) => parser,
+            Err(_) => return,
+        };
+        let parsetree = match parser.parse() {
+            Ok(parsetree) => parsetree,
+            Err(error) => {
+                let span = miette::Diagnostic::labels(&error)
+                    .and_then(|mut labels| labels.next())
+                    .map(|label| Span {
+                        offset: label.offset(),
+                        length: label.len(),
+                    })
+                    .unwrap_or_default();
+                let message = std::error::Error::source(&error)
+                    .map(|source| source.to_string())
+                    .unwrap_or_else(|| error.to_string());
+                self.errors.push((span, message, "lore::parser"));
+                return;
+            }
+        };
+
+        let imported = match self.imported_names(&parsetree) {
+            Ok(imported) => imported,
+            Err(error) => {
+                self.errors
+                    .push((Span::default(), error.to_string(), "lore::store::imports"));
+                return;
+            }
+        };
+
+        match Validator::new().with_imports(imported).validate(parsetree) {
+            Ok(structure) => self.structure = Some(structure),
+            Err(error) => {
+                for error in error.errors() {
+                    self.semantic_error(error);
+                }
+            }
+        }
+    }
+
+    /// What the files and packages the document imports declare, read from disk the way
+    /// `lore validate` reads them.
+    fn imported_names(
+        &self,
+        parsetree: &lore_parser::Structure,
+    ) -> Result<HashSet<URI>, lore_store::StoreError> {
+        let mut store = lore_store::Store::new();
+        let imports = parsetree.imports();
+        if imports
+            .iter()
+            .any(|import| matches!(import, Import::Namespace(_)))
+        {
+            let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+            if let Some(manifest) = lore_store::Manifest::find(dir)? {
+                store = store.with_packages(manifest.packages()?);
+            }
+        }
+        store.load_imports(&self.path, parsetree)
+    }
+
+    /// Points a semantic error at the names it is about, or at the start of the file.
+    fn semantic_error(&mut self, error: &SemanticError) {
+        const CODE: &str = "lore::validator::semantic";
+        if let SemanticError::UnresolvedNames(names) = error {
+            // names come once for every time they are used, and we find every use ourselves
+            let mut texts: Vec<String> = names
+                .iter()
+                .map(|name| name.alias.clone().unwrap_or_else(|| name.uri.to_string()))
+                .collect();
+            texts.sort();
+            texts.dedup();
+            for text in texts {
+                let message = format!(
+                    "`{}` cannot be resolved. Did you forget to add a `prefix` alias or a `using` namespace?",
+                    text
+                );
+                for occurrence in self.occurrences.iter().filter(|o| o.text == text) {
+                    self.errors.push((occurrence.span, message.clone(), CODE));
+                }
+            }
+            return;
+        }
+
+        let name = match error {
+            SemanticError::UnknownPrimitive(name)
+            | SemanticError::PrimitiveRedeclared(name)
+            | SemanticError::PrimitiveAsSubject(name)
+            | SemanticError::InvalidLanguageTag(name, _)
+            | SemanticError::InvalidVariant(name, _)
+            | SemanticError::InvalidSuperkind(name, _)
+            | SemanticError::NotImported(name) => Some(&name.uri),
+            SemanticError::InvalidCardinality(relation, _) => Some(&relation.predicate.uri),
+            SemanticError::UnresolvedNames(_) => None,
+        };
+        let span = self
+            .occurrences
+            .iter()
+            .find(|occurrence| occurrence.uri.as_ref() == name)
+            .map(|occurrence| occurrence.span)
+            .unwrap_or_default();
+        self.errors.push((span, error.to_string(), CODE));
+    }
+
+    /// The name under a position.
+    fn occurrence_at(&self, position: Position) -> Option<&Occurrence> {
+        let offset = LineIndex::new(&self.text).offset(position);
+        self.occurrences.iter().find(|occurrence| {
+            occurrence.span.offset <= offset
+                && offset <= occurrence.span.offset + occurrence.span.length
+        })
+    }
+
+    /// How a URI is best written in this document: by its local name in the namespace being
+    /// used, behind a prefix, or in full.
+    fn shortest_name(&self, uri: &URI) -> String {
+        let uri = uri.to_string();
+        if let Some(namespace) = &self.namespace {
+            if let Some(local) = uri.strip_prefix(&format!("{}/", namespace)) {
+                if !local.contains('/') {
+                    return local.to_string();
+                }
+            }
+        }
+        for (prefix, expanded) in &self.prefixes {
+            if let Some(rest) = uri.strip_prefix(&expanded.to_string()) {
+                return format!("{}{}", prefix, rest);
+            }
+        }
+        uri
+    }
+
+    /// The label and doc fields of a kind, attribute, thing, or relation declared here.
+    fn fields_of(&self, uri: &URI) -> Option<&Vec<lore_ast::Field>> {
+        let structure = self.structure.as_ref()?;
+        structure
+            .kinds
+            .iter()
+            .find(|kind| kind.name.uri == *uri)
+            .map(|kind| &kind.fields)
+            .or_else(|| {
+                structure
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.name.uri == *uri)
+                    .map(|attr| &attr.fields)
+            })
+            .or_else(|| {
+                structure
+                    .things
+                    .iter()
+                    .find(|thing| thing.name.uri == *uri)
+                    .map(|thing| &thing.fields)
+            })
+            .or_else(|| {
+                structure
+                    .relations
+                    .iter()
+                    .find(|rel| rel.predicate.uri == *uri)
+                    .map(|rel| &rel.fields)
+            })
+    }
+}
+
+/// Every `.lore` file the editor has open, and every other one in the folders it works in, so
+/// names can be followed across files.
+#[derive(Default)]
+pub struct Workspace {
+    documents: BTreeMap<Url, Document>,
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    pub fn urls(&self) -> Vec<Url> {
+        self.documents.keys().cloned().collect()
+    }
+
+    pub fn document(&self, url: &Url) -> Option<&Document> {
+        self.documents.get(url)
+    }
+
+    pub fn update(&mut self, url: Url, text: String) {
+        let path = url
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(url.path()));
+        self.documents.insert(url, Document::new(path, text));
+    }
+
+    pub fn remove(&mut self, url: &Url) {
+        self.documents.remove(url);
+    }
+
+    /// Reads every `.lore` file under a folder that is not already open, skipping hidden folders
+    /// and build outputs.
+    pub fn scan(&mut self, root: &Path) {
+        let entries = match std::fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    self.scan(&path);
+                }
+                continue;
+            }
+            if path.extension().and_then(|ext| ext.to_str()) != Some("lore") {
+                continue;
+            }
+            let url = match Url::from_file_path(&path) {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            if self.documents.contains_key(&url) {
+                continue;
+            }
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                self.update(url, text);
+            }
+        }
+    }
+
+    fn declarations(&self) -> impl Iterator<Item = (&Url, &Document, &Occurrence)> {
+        self.documents.iter().flat_map(|(url, document)| {
+            document
+                .occurrences
+                .iter()
+                .filter(|occurrence| occurrence.role.is_declaration())
+                .map(move |occurrence| (url, document, occurrence))
+        })
+    }
+
+    /// The syntax and validation errors of every document, and whatever is wrong with the
+    /// things in it, checked against the kinds declared anywhere in the workspace.
+    pub fn diagnostics(&self) -> BTreeMap<Url, Vec<Diagnostic>> {
+        let mut store = lore_store::Store::new();
+        for document in self.documents.values() {
+            if let Some(structure) = &document.structure {
+                let _ = store.add_tree(structure.clone());
+            }
+        }
+        let thing_errors = match store.check_things() {
+            Err(lore_store::StoreError::InvalidThings { errors }) => errors,
+            _ => vec![],
+        };
+
+        let mut diagnostics = BTreeMap::new();
+        for (url, document) in &self.documents {
+            let index = LineIndex::new(&document.text);
+            let diagnostic = |span: Span, message: String, code: &str| Diagnostic {
+                range: index.range(span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String(code.to_string())),
+                source: Some("lore".to_string()),
+                message,
+                ..Diagnostic::default()
+            };
+
+            let mut found: Vec<Diagnostic> = document
+                .errors
+                .iter()
+                .map(|(span, message, code)| diagnostic(*span, message.clone(), code))
+                .collect();
+            for error in thing_errors.iter().filter(|e| e.file == document.path) {
+                let span = miette::Diagnostic::labels(error)
+                    .and_then(|mut labels| labels.next())
+                    .map(|label| Span {
+                        offset: label.offset(),
+                        length: label.len(),
+                    })
+                    .unwrap_or_default();
+                found.push(diagnostic(
+                    span,
+                    error.message.clone(),
+                    "lore::store::thing",
+                ));
+            }
+            diagnostics.insert(url.clone(), found);
+        }
+        diagnostics
+    }
+
+    /// Where the name under the cursor is declared. Prefixed names nobody declares go to their
+    /// `prefix`.
+    pub fn definition(&self, url: &Url, position: Position) -> Vec<Location> {
+        let document = match self.documents.get(url) {
+            Some(document) => document,
+            None => return vec![],
+        };
+        let occurrence = match document.occurrence_at(position) {
+            Some(occurrence) => occurrence,
+            None => return vec![],
+        };
+
+        let locations: Vec<Location> = match &occurrence.uri {
+            Some(uri) if occurrence.role != Role::Prefix => self
+                .declarations()
+                .filter(|(_, _, declaration)| {
+                    declaration.role != Role::Prefix && declaration.uri.as_ref() == Some(uri)
+                })
+                .map(|(url, document, declaration)| {
+                    Location::new(
+                        url.clone(),
+                        LineIndex::new(&document.text).range(declaration.span),
+                    )
+                })
+                .collect(),
+            _ => vec![],
+        };
+        if !locations.is_empty() {
+            return locations;
+        }
+
+        let index = LineIndex::new(&document.text);
+        document
+            .occurrences
+            .iter()
+            .filter(|declaration| {
+                declaration.role == Role::Prefix && occurrence.text.starts_with(&declaration.text)
+            })
+            .map(|declaration| Location::new(url.clone(), index.range(declaration.span)))
+            .collect()
+    }
+
+    /// The URI the name under the cursor expands to, with its labels and docs.
+    pub fn hover(&self, url: &Url, position: Position) -> Option<Hover> {
+        let document = self.documents.get(url)?;
+        let occurrence = document.occurrence_at(position)?;
+        let uri = occurrence.uri.as_ref()?;
+
+        let declared = self
+            .declarations()
+            .find(|(_, _, declaration)| {
+                declaration.role != Role::Prefix && declaration.uri.as_ref() == Some(uri)
+            })
+            .map(|(_, document, declaration)| (document, declaration.role));
+        let mut value = match declared {
+            Some((_, role)) => format!("`{}` ({})", uri, role.describe()),
+            None => format!("`{}` ({})", uri, occurrence.role.describe()),
+        };
+
+        if let Some(fields) = declared.and_then(|(document, _)| document.fields_of(uri)) {
+            for (lang, label) in Localized::Label.texts(fields) {
+                match lang.as_str() {
+                    "" => value.push_str(&format!("\n\n**Label:** {}", label)),
+                    lang => value.push_str(&format!("\n\n**Label ({}):** {}", lang, label)),
+                }
+            }
+            for (lang, doc) in Localized::Doc.texts(fields) {
+                match lang.as_str() {
+                    "" => value.push_str(&format!("\n\n{}", doc)),
+                    lang => value.push_str(&format!("\n\n_({})_ {}", lang, doc)),
+                }
+            }
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(LineIndex::new(&document.text).range(occurrence.span)),
+        })
+    }
+
+    /// Every name declared in the workspace, written the way this document would write it, and
+    /// every prefix of this document.
+    pub fn completion(&self, url: &Url) -> Vec<CompletionItem> {
+        let document = match self.documents.get(url) {
+            Some(document) => document,
+            None => return vec![],
+        };
+
+        let mut items: BTreeMap<String, CompletionItem> = BTreeMap::new();
+        for (prefix, uri) in &document.prefixes {
+            items.insert(
+                prefix.clone(),
+                CompletionItem {
+                    label: prefix.clone(),
+                    kind: Some(CompletionItemKind::MODULE),
+                    detail: Some(uri.to_string()),
+                    ..CompletionItem::default()
+                },
+            );
+        }
+        for (_, _, declaration) in self.declarations() {
+            let uri = match (&declaration.uri, declaration.role) {
+                (_, Role::Prefix) | (None, _) => continue,
+                (Some(uri), _) => uri,
+            };
+            let label = document.shortest_name(uri);
+            let kind = match declaration.role {
+                Role::Kind => CompletionItemKind::CLASS,
+                Role::Attribute => CompletionItemKind::FIELD,
+                Role::Relation => CompletionItemKind::PROPERTY,
+                _ => CompletionItemKind::VALUE,
+            };
+            items.entry(label.clone()).or_insert(CompletionItem {
+                label,
+                kind: Some(kind),
+                detail: Some(uri.to_string()),
+                ..CompletionItem::default()
+            });
+        }
+        items.into_values().collect()
+    }
+
+    /// The namespace, prefixes, and declarations of a document, in the order they are written.
+    pub fn symbols(&self, url: &Url) -> Vec<DocumentSymbol> {
+        let document = match self.documents.get(url) {
+            Some(document) => document,
+            None => return vec![],
+        };
+        let index = LineIndex::new(&document.text);
+        document
+            .occurrences
+            .iter()
+            .filter(|occurrence| occurrence.role != Role::Reference)
+            .map(|occurrence| {
+                let kind = match occurrence.role {
+                    Role::Namespace => SymbolKind::NAMESPACE,
+                    Role::Prefix => SymbolKind::MODULE,
+                    Role::Kind => SymbolKind::CLASS,
+                    Role::Attribute => SymbolKind::FIELD,
+                    Role::Relation => SymbolKind::PROPERTY,
+                    _ => SymbolKind::OBJECT,
+                };
+                #[allow(deprecated)]
+                DocumentSymbol {
+                    name: occurrence.text.clone(),
+                    detail: occurrence.uri.as_ref().map(|uri| uri.to_string()),
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    range: index.range(occurrence.span),
+                    selection_range: index.range(occurrence.span),
+                    children: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    const HEROES: &str = r#"prefix lore:v1 as @lore
+prefix dota:v2022/items as @items
+using dota:v2022
+import "items.lore"
+
+kind Hero {
+  @lore/label/en "Hero"
+  @lore/doc/en "A playable character."
+}
+
+rel Hero hasItem @items/Item
+"#;
+
+    const ITEMS: &str = r#"using dota:v2022/items
+
+kind Item
+thing Blink : Item
+"#;
+
+    /// The heroes and items, written to a folder of their own so imports can be read.
+    fn workspace(name: &str) -> (Workspace, Url, Url) {
+        let dir = std::env::temp_dir().join(format!("lore_lsp_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("heroes.lore"), HEROES).unwrap();
+        std::fs::write(dir.join("items.lore"), ITEMS).unwrap();
+
+        let heroes = Url::from_file_path(dir.join("heroes.lore")).unwrap();
+        let items = Url::from_file_path(dir.join("items.lore")).unwrap();
+        let mut workspace = Workspace::new();
+        workspace.update(heroes.clone(), HEROES.to_string());
+        workspace.update(items.clone(), ITEMS.to_string());
+        (workspace, heroes, items)
+    }
+
+    fn url(workspace: &Url, file: &str) -> Url {
+        workspace.join(file).unwrap()
+    }
+
+    fn messages(diagnostics: BTreeMap<Url, Vec<Diagnostic>>) -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .flat_map(|(url, diagnostics)| {
+                let file = url
+                    .path_segments()
+                    .unwrap()
+                    .next_back()
+                    .unwrap()
+                    .to_string();
+                diagnostics.into_iter().map(move |d| {
+                    format!(
+                        "{}:{}:{}: {}",
+                        file, d.range.start.line, d.range.start.character, d.message
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn definition_across_files() {
+        let (workspace, heroes, items) = workspace("definition_across_files");
+        // `@items/Item` in the relation
+        let locations = workspace.definition(&heroes, Position::new(10, 20));
+        assert_eq!(
+            locations,
+            vec![Location::new(
+                items,
+                Range::new(Position::new(2, 5), Position::new(2, 9))
+            )]
+        );
+
+        // `Hero` as the subject of the relation
+        let locations = workspace.definition(&heroes, Position::new(10, 5));
+        assert_eq!(
+            locations,
+            vec![Location::new(
+                heroes,
+                Range::new(Position::new(5, 5), Position::new(5, 9))
+            )]
+        );
+    }
+
+    #[test]
+    fn definition_of_undeclared_prefixed_name() {
+        let (workspace, heroes, _) = workspace("definition_of_undeclared_prefixed_name");
+        // `@lore/label/en` is not declared anywhere, so it goes to its prefix
+        let locations = workspace.definition(&heroes, Position::new(6, 4));
+        assert_eq!(
+            locations,
+            vec![Location::new(
+                heroes,
+                Range::new(Position::new(0, 18), Position::new(0, 23))
+            )]
+        );
+    }
+
+    #[test]
+    fn hover_shows_uri_and_docs() {
+        let (workspace, heroes, _) = workspace("hover_shows_uri_and_docs");
+        let hover = workspace.hover(&heroes, Position::new(10, 5)).unwrap();
+        match hover.contents {
+            HoverContents::Markup(markup) => assert_eq!(
+                markup.value,
+                "`dota:v2022/Hero` (kind)\n\n**Label (en):** Hero\n\n_(en)_ A playable character."
+            ),
+            contents => panic!("{:?}", contents),
+        }
+    }
+
+    #[test]
+    fn completion_uses_local_names_and_prefixes() {
+        let (workspace, heroes, _) = workspace("completion_uses_local_names_and_prefixes");
+        let items: Vec<(String, String)> = workspace
+            .completion(&heroes)
+            .into_iter()
+            .map(|item| (item.label, item.detail.unwrap_or_default()))
+            .collect();
+        assert_debug_snapshot!(items);
+    }
+
+    #[test]
+    fn document_symbols() {
+        let (workspace, _, items) = workspace("document_symbols");
+        let symbols: Vec<String> = workspace
+            .symbols(&items)
+            .into_iter()
+            .map(|symbol| {
+                format!(
+                    "{:?} {} {} {}:{}",
+                    symbol.kind,
+                    symbol.name,
+                    symbol.detail.unwrap_or_default(),
+                    symbol.range.start.line,
+                    symbol.range.start.character
+                )
+            })
+            .collect();
+        assert_debug_snapshot!(symbols);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (mut workspace, heroes, items) = workspace("diagnostics");
+        workspace.update(
+            items.clone(),
+            "using dota:v2022/items\n\nkind Item\nthing Blink : Item {\n  hasItem Tango\n}\n"
+                .to_string(),
+        );
+        workspace.update(url(&heroes, "broken.lore"), "kind Unit\nrel".to_string());
+        workspace.update(
+            url(&heroes, "unresolved.lore"),
+            "kind Unit\nrel Unit speed Speed".to_string(),
+        );
+
+        let diagnostics = workspace.diagnostics();
+        assert!(diagnostics[&heroes].is_empty());
+        assert_debug_snapshot!(messages(diagnostics));
+    }
+
+    #[test]
+    fn diagnostics_of_missing_imports() {
+        let (mut workspace, heroes, _) = workspace("diagnostics_of_missing_imports");
+        // `Hero` is declared in heroes.lore, which this file does not import
+        workspace.update(
+            url(&heroes, "abilities.lore"),
+            "using dota:v2022\n\nkind Ability\nrel Hero hasAbility Ability\n".to_string(),
+        );
+        workspace.update(
+            url(&heroes, "units.lore"),
+            "using dota:v2022\nimport \"creeps.lore\"\n\nkind Unit\n".to_string(),
+        );
+
+        let diagnostics = workspace.diagnostics();
+        assert!(diagnostics[&heroes].is_empty());
+        let dir = heroes.to_file_path().unwrap();
+        let dir = dir.parent().unwrap().display().to_string();
+        let messages: Vec<String> = messages(diagnostics)
+            .into_iter()
+            .map(|message| message.replace(&dir, "$DIR"))
+            .collect();
+        assert_debug_snapshot!(messages);
+    }
+}
@@ -14,6 +14,9 @@ pub enum Token {
     #[token("using")]
     Using,
 
+    #[token("import")]
+    Import,
+
     #[token("kind")]
     Kind,
 
@@ -23,16 +26,19 @@ pub enum Token {
     #[token("rel")]
     Relation,
 
+    #[token("thing")]
+    Thing,
+
     #[token("in")]
     In,
 
     #[token(":")]
     Colon,
 
-    #[regex("(\"([^\"\\\\]|\\\\.)*\")", |lex| lex.slice()[1..lex.slice().len() -1].parse())]
+    #[regex("(\"([^\"\\\\]|\\\\.)*\")", |lex| unescape(&lex.slice()[1..lex.slice().len() -1]))]
     LiteralString(String),
 
-    #[regex("(\"\"\"([^\"\\\\]|\\\\.)*\"\"\")", |lex| lex.slice()[3..lex.slice().len() -3].parse())]
+    #[regex("(\"\"\"([^\"\\\\]|\\\\.)*\"\"\")", |lex| unescape(&lex.slice()[3..lex.slice().len() -3]))]
     MultiLineLiteralString(String),
 
     #[token("{")]
@@ -53,11 +59,30 @@ pub enum Token {
     #[regex("[0-9]+", |lex| lex.slice().parse())]
     Number(u64),
 
+    #[regex("[0-9]+\\.[0-9]+", |lex| lex.slice().parse())]
+    Float(String),
+
     #[error]
     #[regex(r"[ \t\n\f]+", logos::skip)]
     Error,
 }
 
+/// Reads `\"` and `\\` in a string as `"` and `\`. Other escapes are kept as written.
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if next == '"' || next == '\\' => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +226,16 @@ mod tests {
         assert_eq!(lex.next(), Some(Token::Text("User".to_string())));
     }
 
+    #[test]
+    fn thing_with_kind() {
+        let mut lex = Token::lexer(r#"thing AntiMage : Hero"#);
+
+        assert_eq!(lex.next(), Some(Token::Thing));
+        assert_eq!(lex.next(), Some(Token::Text("AntiMage".to_string())));
+        assert_eq!(lex.next(), Some(Token::Colon));
+        assert_eq!(lex.next(), Some(Token::Text("Hero".to_string())));
+    }
+
     #[test]
     fn field() {
         let mut lex = Token::lexer(r#"range: integer"#);
@@ -266,4 +301,27 @@ mod tests {
         assert_eq!(lex.next(), Some(Token::Number(1)));
         assert_eq!(lex.next(), Some(Token::ClosedBrace));
     }
+
+    #[test]
+    fn lex_floats() {
+        let mut lex = Token::lexer("turnRate 0.5 movementSpeed 310");
+        assert_eq!(lex.next(), Some(Token::Text("turnRate".to_string())));
+        assert_eq!(lex.next(), Some(Token::Float("0.5".to_string())));
+        assert_eq!(lex.next(), Some(Token::Text("movementSpeed".to_string())));
+        assert_eq!(lex.next(), Some(Token::Number(310)));
+    }
+
+    #[test]
+    fn lex_escaped_strings() {
+        let mut lex = Token::lexer(r#""The \"Butcher\"" "C:\\meat" "\n""#);
+        assert_eq!(
+            lex.next(),
+            Some(Token::LiteralString("The \"Butcher\"".to_string()))
+        );
+        assert_eq!(
+            lex.next(),
+            Some(Token::LiteralString("C:\\meat".to_string()))
+        );
+        assert_eq!(lex.next(), Some(Token::LiteralString("\\n".to_string())));
+    }
 }
@@ -1,8 +1,10 @@
 pub mod lexer;
 pub mod parser;
 pub mod parsetree;
+pub mod printer;
 pub mod validator;
 
 pub use parser::*;
 pub use parsetree::*;
+pub use printer::*;
 pub use validator::*;
@@ -6,6 +6,15 @@ use miette::{Diagnostic, NamedSource, SourceSpan};
 use std::path::PathBuf;
 use thiserror::Error;
 
+/// The inside of a `{ }` block.
+#[derive(Default)]
+struct Block {
+    fields: Vec<Field>,
+    spans: Vec<lore_ast::Span>,
+    /// The comments after the last field.
+    closing_comments: Vec<String>,
+}
+
 struct PeekableLexer<'source> {
     lexer: Lexer<'source, Token>,
     peeked: Option<Option<Token>>,
@@ -27,6 +36,32 @@ impl<'source> PeekableLexer<'source> {
     }
 }
 
+impl<'source> PeekableLexer<'source> {
+    /// The span of the last token lexed. Only the token just returned by `next` when nothing was
+    /// peeked after it.
+    fn span(&self) -> lore_ast::Span {
+        let range = self.lexer.span();
+        lore_ast::Span {
+            offset: range.start,
+            length: range.end - range.start,
+        }
+    }
+
+    fn blank_line_between(&self, start: usize, end: usize) -> bool {
+        self.lexer
+            .source()
+            .get(start..end)
+            .is_some_and(|gap| gap.matches('\n').count() > 1)
+    }
+
+    fn same_line(&self, start: usize, end: usize) -> bool {
+        self.lexer
+            .source()
+            .get(start..end)
+            .is_some_and(|gap| !gap.contains('\n'))
+    }
+}
+
 impl<'source> Iterator for PeekableLexer<'source> {
     type Item = Token;
 
@@ -61,6 +96,9 @@ pub enum SyntaxError {
     #[error("")]
     UseExpectsTextPrefix,
 
+    #[error("The `import` syntax should follow the format:\n\timport \"<path>.lore\"\nor\n\timport <namespace>")]
+    ImportExpectsPathOrNamespace,
+
     #[error("The `kind <name>` syntax is missing a name.")]
     KindIsMissingAName,
 
@@ -82,7 +120,16 @@ pub enum SyntaxError {
     #[error("The `rel` syntax should follow the format:\n\trel <subject> <predicate> <object>.\nAll 3 must be URIs or aliased names.")]
     RelationExpectedObjectToBeName,
 
-    #[error("We expected to find an Alias, a Kind, an Attribute, or a Relation.")]
+    #[error("The `thing` syntax should follow the format:\n\tthing <name> : <kind>.\nDid you forget to give it a name?")]
+    ThingIsMissingAName,
+
+    #[error("The `thing` syntax should follow the format:\n\tthing <name> : <kind>.\nDid you forget the `:` before its kind?")]
+    ThingIsMissingTheColon,
+
+    #[error("The `thing` syntax should follow the format:\n\tthing <name> : <kind>.\nDid you forget to say what kind of thing it is?")]
+    ThingIsMissingAKind,
+
+    #[error("We expected to find an Alias, a Kind, an Attribute, a Relation, or a Thing.")]
     ExpectedTopLevelItem,
 
     #[error("Did you forget to close this block with a `}}` ? ")]
@@ -136,7 +183,7 @@ pub struct Parser {
 
 impl Parser {
     pub fn for_file(file: PathBuf) -> Result<Parser, FileError> {
-        let source = std::fs::read_to_string(&file).map_err(|io| FileError::Io(io))?;
+        let source = std::fs::read_to_string(&file).map_err(FileError::Io)?;
         Ok(Parser { file, source })
     }
 
@@ -147,6 +194,10 @@ impl Parser {
         })
     }
 
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
     pub fn parse(&mut self) -> Result<Structure, ParseError> {
         let mut lexer = PeekableLexer::new(&self.source);
         let items = Parser::parse_structure(&mut lexer).map_err(|error| {
@@ -168,26 +219,28 @@ impl Parser {
         Ok(Structure::new(items, self.file.clone()))
     }
 
-    fn parse_structure(mut lex: &mut PeekableLexer) -> Result<Vec<StructureItem>, SyntaxError> {
+    fn parse_structure(lex: &mut PeekableLexer) -> Result<Vec<StructureItem>, SyntaxError> {
         let mut items = vec![];
         while let Some(token) = lex.next() {
-            let item = Parser::parse_structure_item(&mut lex, token)?;
+            let item = Parser::parse_structure_item(lex, token)?;
             items.push(item);
         }
         Ok(items)
     }
 
     fn parse_structure_item(
-        mut lex: &mut PeekableLexer,
+        lex: &mut PeekableLexer,
         token: Token,
     ) -> Result<StructureItem, SyntaxError> {
         match token {
-            Token::Using => Parser::parse_using(&mut lex),
-            Token::Prefix => Parser::parse_prefix(&mut lex),
-            Token::Kind => Parser::parse_kind(&mut lex),
-            Token::Attribute => Parser::parse_attr(&mut lex),
-            Token::Relation => Parser::parse_rel(&mut lex),
-            Token::Comment(_) => Parser::parse_comment(&mut lex),
+            Token::Using => Parser::parse_using(lex),
+            Token::Prefix => Parser::parse_prefix(lex),
+            Token::Import => Parser::parse_import(lex),
+            Token::Kind => Parser::parse_kind(lex),
+            Token::Attribute => Parser::parse_attr(lex),
+            Token::Relation => Parser::parse_rel(lex),
+            Token::Thing => Parser::parse_thing(lex),
+            Token::Comment(_) => Parser::parse_comment(lex),
             _ => Err(SyntaxError::ExpectedTopLevelItem),
         }
     }
@@ -208,8 +261,8 @@ impl Parser {
         }
     }
 
-    fn parse_prefix(mut lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let uri = match Parser::parse_uri(&mut lex) {
+    fn parse_prefix(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let uri = match Parser::parse_uri(lex) {
             Ok(uri) => Ok(uri),
             _ => Err(SyntaxError::UseExpectsURI),
         }?;
@@ -219,7 +272,7 @@ impl Parser {
             _ => Err(SyntaxError::PrefixIsMissingTheAsKeyword),
         }?;
 
-        let prefix = match Parser::parse_uri(&mut lex) {
+        let prefix = match Parser::parse_uri(lex) {
             Ok(uri) => Ok(uri),
             _ => Err(SyntaxError::PrefixIsMissingTheAliasedName),
         }?;
@@ -227,8 +280,8 @@ impl Parser {
         Ok(StructureItem::Alias { uri, prefix })
     }
 
-    fn parse_using(mut lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let uri = match Parser::parse_uri(&mut lex) {
+    fn parse_using(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let uri = match Parser::parse_uri(lex) {
             Ok(uri) => Ok(uri),
             _ => Err(SyntaxError::UseExpectsURI),
         }?;
@@ -236,51 +289,104 @@ impl Parser {
         Ok(StructureItem::Namespace { uri })
     }
 
-    fn parse_kind(mut lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let name = match Parser::parse_name(&mut lex) {
+    fn parse_import(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        match lex.next() {
+            Some(Token::LiteralString(path)) => Ok(StructureItem::Import(Import::File(path))),
+            Some(Token::URI(uri)) => Ok(StructureItem::Import(Import::Namespace(
+                URI::from_string(uri),
+            ))),
+            _ => Err(SyntaxError::ImportExpectsPathOrNamespace),
+        }
+    }
+
+    fn parse_kind(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let name = match Parser::parse_name(lex) {
             Ok(name) => Ok(name),
             _ => Err(SyntaxError::KindIsMissingAName),
         }?;
 
-        let fields = Parser::parse_fields(&mut lex)?;
+        let (fields, closing_comments) = Parser::parse_fields(lex)?;
 
-        Ok(StructureItem::Kind { name, fields })
+        Ok(StructureItem::Kind {
+            name,
+            fields,
+            closing_comments,
+        })
     }
 
-    fn parse_attr(mut lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let name = match Parser::parse_name(&mut lex) {
+    fn parse_attr(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let name = match Parser::parse_name(lex) {
             Ok(name) => Ok(name),
             _ => Err(SyntaxError::AttributeIsMissingAName),
         }?;
 
-        let fields = Parser::parse_fields(&mut lex)?;
+        let (fields, closing_comments) = Parser::parse_fields(lex)?;
 
-        Ok(StructureItem::Attribute { name, fields })
+        Ok(StructureItem::Attribute {
+            name,
+            fields,
+            closing_comments,
+        })
     }
 
-    fn parse_rel(mut lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let subject = match Parser::parse_name(&mut lex) {
+    fn parse_rel(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let subject = match Parser::parse_name(lex) {
             Ok(name) => Ok(name),
             _ => Err(SyntaxError::RelationExpectedSubjectToBeName),
         }?;
 
-        let predicate = match Parser::parse_name(&mut lex) {
+        let predicate = match Parser::parse_name(lex) {
             Ok(name) => Ok(name),
             _ => Err(SyntaxError::RelationExpectedPredicateToBeName),
         }?;
 
-        let object = match Parser::parse_name(&mut lex) {
+        let object = match Parser::parse_name(lex) {
             Ok(name) => Ok(name),
             _ => Err(SyntaxError::RelationExpectedObjectToBeName),
         }?;
 
-        let fields = Parser::parse_fields(&mut lex)?;
+        let (fields, closing_comments) = Parser::parse_fields(lex)?;
 
         Ok(StructureItem::Relation {
             subject,
             predicate,
             object,
             fields,
+            closing_comments,
+        })
+    }
+
+    fn parse_thing(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
+        let name = match Parser::parse_name(lex) {
+            Ok(name) => Ok(name),
+            _ => Err(SyntaxError::ThingIsMissingAName),
+        }?;
+        let name_span = lex.span();
+
+        match lex.next() {
+            Some(Token::Colon) => Ok(()),
+            _ => Err(SyntaxError::ThingIsMissingTheColon),
+        }?;
+
+        let kind = match Parser::parse_name(lex) {
+            Ok(name) => Ok(name),
+            _ => Err(SyntaxError::ThingIsMissingAKind),
+        }?;
+        let kind_span = lex.span();
+
+        let block = Parser::parse_block(lex)?;
+
+        Ok(StructureItem::Thing {
+            name,
+            kind,
+            fields: block.fields,
+            closing_comments: block.closing_comments,
+            spans: lore_ast::ThingSpans {
+                name: name_span,
+                kind: kind_span,
+                fields: block.spans,
+                ..Default::default()
+            },
         })
     }
 
@@ -288,35 +394,59 @@ impl Parser {
         Ok(StructureItem::Comment(lex.lexer.slice().to_string()))
     }
 
-    /// TODO(@ostera): get rid of this!
-    fn parse_field_comment(lex: &mut PeekableLexer) -> Result<StructureItem, SyntaxError> {
-        let next = lex.peek();
-        if let Some(Token::Comment(comment)) = next {
-            let comment = comment.to_string();
-            lex.next();
-            Ok(StructureItem::Comment(comment))
-        } else {
-            Err(SyntaxError::ExpectedAComment)
-        }
+    /// The fields of a `{ }` block, and the comments left after its last field.
+    fn parse_fields(lex: &mut PeekableLexer) -> Result<(Vec<Field>, Vec<String>), SyntaxError> {
+        let block = Parser::parse_block(lex)?;
+        Ok((block.fields, block.closing_comments))
     }
 
-    fn parse_fields(mut lex: &mut PeekableLexer) -> Result<Vec<Field>, SyntaxError> {
+    fn parse_block(lex: &mut PeekableLexer) -> Result<Block, SyntaxError> {
         let next = lex.peek();
         if let Some(Token::OpenBrace) = next {
             lex.next();
             let mut fields = vec![];
+            let mut spans = vec![];
+            let mut comments = vec![];
+            let mut spaced = false;
+            // where the last thing we read ends, to find the blank lines between fields
+            let mut end = lex.span().offset + lex.span().length;
             loop {
-                let _ = Parser::parse_field_comment(&mut lex);
+                let next = lex.peek().is_some();
+                if next && !fields.is_empty() && comments.is_empty() {
+                    spaced = lex.blank_line_between(end, lex.span().offset);
+                }
 
-                match Parser::parse_field(&mut lex) {
-                    Ok(field) => fields.push(field),
+                if let Some(Token::Comment(_)) = lex.peek() {
+                    lex.next();
+                    let start = lex.span().offset;
+                    let comment = lex.lexer.slice().trim_end().to_string();
+                    let trailing = comments.is_empty() && lex.same_line(end, start);
+                    end = start + comment.len();
+                    match fields.last_mut() {
+                        Some(
+                            field @ Field {
+                                trailing_comment: None,
+                                ..
+                            },
+                        ) if trailing => field.trailing_comment = Some(comment),
+                        _ => comments.push(comment),
+                    }
+                    continue;
+                }
+
+                match Parser::parse_field(lex) {
+                    Ok((mut field, span)) => {
+                        field.comments = std::mem::take(&mut comments);
+                        field.spaced = std::mem::take(&mut spaced);
+                        end = span.offset + span.length;
+                        fields.push(field);
+                        spans.push(span);
+                    }
 
                     Err(SyntaxError::NameIsMissing) | Err(SyntaxError::InvalidLiteral(None)) => {
                         return Err(SyntaxError::IncompleteFieldBlock)
                     }
 
-                    Err(SyntaxError::NameIsInvalid(Some(Token::Comment(_)))) => continue,
-
                     Err(SyntaxError::NameIsInvalid(Some(Token::ClosedBrace))) => {
                         break;
                     }
@@ -324,16 +454,33 @@ impl Parser {
                     Err(e) => return Err(e),
                 }
             }
-            Ok(fields)
+            Ok(Block {
+                fields,
+                spans,
+                closing_comments: comments,
+            })
         } else {
-            Ok(vec![])
+            Ok(Block::default())
         }
     }
 
-    fn parse_field(mut lex: &mut PeekableLexer) -> Result<Field, SyntaxError> {
-        let name = Parser::parse_name(&mut lex)?;
-        let value = Parser::parse_literal(&mut lex)?;
-        Ok(Field { name, value })
+    fn parse_field(lex: &mut PeekableLexer) -> Result<(Field, lore_ast::Span), SyntaxError> {
+        let name = Parser::parse_name(lex)?;
+        let start = lex.span().offset;
+        let value = Parser::parse_literal(lex)?;
+        let end = lex.span().offset + lex.span().length;
+        let span = lore_ast::Span {
+            offset: start,
+            length: end - start,
+        };
+        let field = Field {
+            name,
+            value,
+            comments: vec![],
+            trailing_comment: None,
+            spaced: false,
+        };
+        Ok((field, span))
     }
 
     fn parse_literal(lex: &mut PeekableLexer) -> Result<Literal, SyntaxError> {
@@ -341,6 +488,7 @@ impl Parser {
             Some(Token::LiteralString(s)) => Ok(Literal::String(s)),
             Some(Token::MultiLineLiteralString(s)) => Ok(Literal::String(s)),
             Some(Token::Number(n)) => Ok(Literal::Number(n)),
+            Some(Token::Float(f)) => Ok(Literal::Float(f)),
             Some(Token::URI(uri)) => Ok(Literal::Name(Name::URI(URI::from_string(uri)))),
             Some(Token::Text(alias)) => Ok(Literal::Name(Name::Alias(alias))),
             token => Err(SyntaxError::InvalidLiteral(token)),
@@ -509,6 +657,30 @@ attr Name {
  "#
     );
 
+    test!(parse_thing_with_kind, "thing AntiMage : Hero");
+
+    test!(parse_thing_missing_colon, "thing AntiMage Hero");
+
+    test!(parse_thing_missing_kind, "thing AntiMage :");
+
+    test!(parse_import_file, r#"import "../base/names.lore""#);
+
+    test!(parse_import_namespace, "import dota:v2022");
+
+    test!(parse_import_missing_target, "import Hero");
+
+    test!(
+        parse_thing_with_fields,
+        r#"
+            thing AntiMage : Hero {
+                movementSpeed 310
+                primaryAttribute Agility
+                @dota/alias "Magina"
+                @dota/alias "Wei"
+            }
+        "#
+    );
+
     test!(parse_kind_with_fields_incomplete, r#" kind Name { "#);
 
     test!(parse_kind_with_fields_empty, r#" kind Name {} "#);
@@ -14,10 +14,10 @@ pub enum Name {
     Alias(String),
 }
 
-impl Into<lore_ast::Name> for &Name {
-    fn into(self) -> lore_ast::Name {
-        match self {
-            Name::URI(uri) => lore_ast::Name::of_uri(&uri),
+impl From<&Name> for lore_ast::Name {
+    fn from(val: &Name) -> Self {
+        match val {
+            Name::URI(uri) => lore_ast::Name::of_uri(uri),
             Name::Alias(alias) => lore_ast::Name::unresolved_alias(alias),
         }
     }
@@ -26,6 +26,7 @@ impl Into<lore_ast::Name> for &Name {
 #[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
     Number(u64),
+    Float(String),
     String(String),
     Name(Name),
 }
@@ -34,6 +35,15 @@ pub enum Literal {
 pub struct Field {
     pub name: Name,
     pub value: Literal,
+
+    /// The comments on the lines right before the field, so they can be printed back.
+    pub comments: Vec<String>,
+
+    /// The comment on the same line as the field, after its value.
+    pub trailing_comment: Option<String>,
+
+    /// Whether a blank line separates the field, or its comments, from the field before it.
+    pub spaced: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -49,14 +59,20 @@ pub enum StructureItem {
         prefix: URI,
     },
 
+    Import(Import),
+
     Kind {
         name: Name,
         fields: Vec<Field>,
+        /// The comments after the last field, before the closing `}`.
+        closing_comments: Vec<String>,
     },
 
     Attribute {
         name: Name,
         fields: Vec<Field>,
+        /// The comments after the last field, before the closing `}`.
+        closing_comments: Vec<String>,
     },
 
     Relation {
@@ -64,7 +80,26 @@ pub enum StructureItem {
         predicate: Name,
         object: Name,
         fields: Vec<Field>,
+        /// The comments after the last field, before the closing `}`.
+        closing_comments: Vec<String>,
     },
+
+    Thing {
+        name: Name,
+        kind: Name,
+        fields: Vec<Field>,
+        /// The comments after the last field, before the closing `}`.
+        closing_comments: Vec<String>,
+        spans: lore_ast::ThingSpans,
+    },
+}
+
+/// What an `import` brings in: a file, relative to the importing one, or every file of the
+/// package for a namespace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Import {
+    File(String),
+    Namespace(URI),
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -85,4 +120,14 @@ impl Structure {
     pub fn filename(&self) -> &PathBuf {
         &self.filename
     }
+
+    pub fn imports(&self) -> Vec<&Import> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                StructureItem::Import(import) => Some(import),
+                _ => None,
+            })
+            .collect()
+    }
 }
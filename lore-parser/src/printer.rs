@@ -0,0 +1,412 @@
+use crate::parsetree::*;
+use miette::Diagnostic;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{} file(s) are not formatted", .files.len())]
+#[diagnostic(
+    code(lore::fmt::unformatted),
+    help("Run `lore fmt` on them to format them.")
+)]
+pub struct UnformattedError {
+    pub files: Vec<PathBuf>,
+}
+
+/// Prints a parse tree back as source in the canonical layout: prefixes, `using`, and imports first, one
+/// blank line between declarations, and field values aligned within each block. Comments stay
+/// with the declaration or field written after them, on the line of the field they follow, or at
+/// the end of their block; the comments opening a file that starts with its header stay on top.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Printer {
+    out: String,
+}
+
+/// A declaration and the comments right before it.
+struct Group<'a> {
+    comments: Vec<&'a str>,
+    item: &'a StructureItem,
+}
+
+fn keyword(item: &StructureItem) -> &'static str {
+    match item {
+        StructureItem::Namespace { .. } => "using",
+        StructureItem::Alias { .. } => "prefix",
+        StructureItem::Import(_) => "import",
+        StructureItem::Kind { .. } => "kind",
+        StructureItem::Attribute { .. } => "attr",
+        StructureItem::Relation { .. } => "rel",
+        StructureItem::Thing { .. } => "thing",
+        StructureItem::Comment(_) => "#",
+    }
+}
+
+fn fields_of(item: &StructureItem) -> &[Field] {
+    match item {
+        StructureItem::Kind { fields, .. }
+        | StructureItem::Attribute { fields, .. }
+        | StructureItem::Relation { fields, .. }
+        | StructureItem::Thing { fields, .. } => fields,
+        _ => &[],
+    }
+}
+
+fn closing_comments_of(item: &StructureItem) -> &[String] {
+    match item {
+        StructureItem::Kind {
+            closing_comments, ..
+        }
+        | StructureItem::Attribute {
+            closing_comments, ..
+        }
+        | StructureItem::Relation {
+            closing_comments, ..
+        }
+        | StructureItem::Thing {
+            closing_comments, ..
+        } => closing_comments,
+        _ => &[],
+    }
+}
+
+fn has_block(item: &StructureItem) -> bool {
+    !fields_of(item).is_empty() || !closing_comments_of(item).is_empty()
+}
+
+fn is_header(item: &StructureItem) -> bool {
+    matches!(
+        item,
+        StructureItem::Alias { .. } | StructureItem::Namespace { .. } | StructureItem::Import(_)
+    )
+}
+
+fn name(name: &Name) -> String {
+    match name {
+        Name::URI(uri) => uri.to_string(),
+        Name::Alias(alias) => alias.clone(),
+    }
+}
+
+/// Writes a string so the lexer reads it back as it was. A backslash is only escaped where it
+/// would otherwise be read as an escape, so strings like `\n` print as they were written.
+pub fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' if matches!(chars.peek(), None | Some('"') | Some('\\')) => out.push_str("\\\\"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Number(n) => n.to_string(),
+        Literal::Float(f) => f.clone(),
+        Literal::String(s) => format!("\"{}\"", escape_string(s)),
+        Literal::Name(n) => name(n),
+    }
+}
+
+impl Printer {
+    pub fn new() -> Printer {
+        Printer::default()
+    }
+
+    pub fn print(mut self, structure: &Structure) -> String {
+        let mut groups: Vec<Group> = vec![];
+        let mut comments = vec![];
+        // the comments opening a file that starts with its header are about the whole file
+        let mut leading = vec![];
+        for item in structure.items() {
+            match item {
+                StructureItem::Comment(comment) => comments.push(comment.trim_end()),
+                item if groups.is_empty() && is_header(item) => {
+                    leading = std::mem::take(&mut comments);
+                    groups.push(Group {
+                        comments: vec![],
+                        item,
+                    })
+                }
+                item => groups.push(Group {
+                    comments: std::mem::take(&mut comments),
+                    item,
+                }),
+            }
+        }
+        self.comments(&leading, "");
+
+        let (header, body): (Vec<Group>, Vec<Group>) =
+            groups.into_iter().partition(|group| is_header(group.item));
+        let (prefixes, rest): (Vec<Group>, Vec<Group>) = header
+            .into_iter()
+            .partition(|group| matches!(group.item, StructureItem::Alias { .. }));
+        let (namespaces, imports): (Vec<Group>, Vec<Group>) = rest
+            .into_iter()
+            .partition(|group| matches!(group.item, StructureItem::Namespace { .. }));
+
+        for group in &prefixes {
+            self.comments(&group.comments, "");
+            self.item(group.item);
+        }
+        if !prefixes.is_empty() && namespaces.len() + imports.len() > 0 {
+            self.out.push('\n');
+        }
+        for group in namespaces.iter().chain(&imports) {
+            self.comments(&group.comments, "");
+            self.item(group.item);
+        }
+
+        let mut previous: Option<&StructureItem> = None;
+        for group in &body {
+            let grouped = match previous {
+                // a run of declarations without blocks, like `attr a` then `attr b`, stays together
+                Some(previous) => {
+                    group.comments.is_empty()
+                        && keyword(previous) == keyword(group.item)
+                        && !has_block(previous)
+                        && !has_block(group.item)
+                }
+                None => false,
+            };
+            if !grouped && !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.comments(&group.comments, "");
+            self.item(group.item);
+            previous = Some(group.item);
+        }
+
+        if !comments.is_empty() {
+            if !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.comments(&comments, "");
+        }
+
+        self.out
+    }
+
+    fn comments(&mut self, comments: &[&str], indent: &str) {
+        for comment in comments {
+            self.out.push_str(&format!("{}{}\n", indent, comment));
+        }
+    }
+
+    fn item(&mut self, item: &StructureItem) {
+        let head = match item {
+            StructureItem::Namespace { uri } => format!("using {}", uri),
+            StructureItem::Alias { uri, prefix } => format!("prefix {} as {}", uri, prefix),
+            StructureItem::Import(Import::File(path)) => format!("import \"{}\"", path),
+            StructureItem::Import(Import::Namespace(uri)) => format!("import {}", uri),
+            StructureItem::Kind { name: n, .. } => format!("kind {}", name(n)),
+            StructureItem::Attribute { name: n, .. } => format!("attr {}", name(n)),
+            StructureItem::Relation {
+                subject,
+                predicate,
+                object,
+                ..
+            } => format!("rel {} {} {}", name(subject), name(predicate), name(object)),
+            StructureItem::Thing { name: n, kind, .. } => {
+                format!("thing {} : {}", name(n), name(kind))
+            }
+            StructureItem::Comment(comment) => comment.trim_end().to_string(),
+        };
+        self.out.push_str(&head);
+        self.block(fields_of(item), closing_comments_of(item));
+        self.out.push('\n');
+    }
+
+    fn block(&mut self, fields: &[Field], closing_comments: &[String]) {
+        if fields.is_empty() && closing_comments.is_empty() {
+            return;
+        }
+        let keys: Vec<String> = fields.iter().map(|field| name(&field.name)).collect();
+        let width = keys
+            .iter()
+            .map(|key| key.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        self.out.push_str(" {\n");
+        for (field, key) in fields.iter().zip(keys) {
+            if field.spaced {
+                self.out.push('\n');
+            }
+            let comments: Vec<&str> = field.comments.iter().map(|c| c.as_str()).collect();
+            self.comments(&comments, "  ");
+            let padding = " ".repeat(width - key.chars().count());
+            self.out
+                .push_str(&format!("  {}{} {}", key, padding, literal(&field.value)));
+            if let Some(comment) = &field.trailing_comment {
+                self.out.push_str(&format!(" {}", comment));
+            }
+            self.out.push('\n');
+        }
+        let closing_comments: Vec<&str> = closing_comments.iter().map(|c| c.as_str()).collect();
+        self.comments(&closing_comments, "  ");
+        self.out.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use insta::*;
+
+    macro_rules! test {
+        ($name:ident, $src:expr) => {
+            #[test]
+            fn $name() {
+                let parsetree = Parser::for_string("$name", $src).unwrap().parse().unwrap();
+                let formatted = Printer::new().print(&parsetree);
+
+                // formatting what is already formatted changes nothing
+                let reparsed = Parser::for_string("$name", &formatted)
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                assert_eq!(Printer::new().print(&reparsed), formatted);
+
+                let snapshot = format!(
+                    r#"
+input:
+    {}
+
+output:
+
+{}
+"#,
+                    $src, formatted
+                );
+                assert_snapshot!(snapshot)
+            }
+        };
+    }
+
+    test!(
+        format_header_first,
+        r#"
+kind Hero
+# where everything lives
+using   dota:v2022
+prefix lore:v1    as @lore
+rel Hero movementSpeed @lore/Int
+"#
+    );
+
+    test!(
+        format_imports_after_using,
+        r#"
+kind Hero
+import "names.lore"
+using dota:v2022
+import lore:v1
+"#
+    );
+
+    test!(
+        format_groups_declarations,
+        r#"
+using dota:v2022
+kind Ability
+attr castingType
+attr damageType
+
+attr castRange
+# the hero
+kind Hero
+kind Item
+rel Ability isConstrainedBy Attribute
+rel Hero hasItem Item
+"#
+    );
+
+    test!(
+        format_sample,
+        r#"
+
+# these are comments for the ontology writers, not
+# about the things!
+attr Name {
+  @doc/en      "A name to call something by."
+
+  @label/en    "Name"
+  @label/es    "Nombre"
+
+  @see_also    @lore/attrs/Nickname
+
+  # if a thing has a name, then the name means the thing
+  @symmetry    :symmetric
+
+
+
+  # a name might be its own name, like "word"
+  @reflexivity :reflexive
+}
+
+        "#
+    );
+
+    test!(
+        format_aligns_fields,
+        r#"
+using dota:v2022
+thing AntiMage : Hero{mainName "Anti-Mage"
+movementSpeed 310 @lore/label/en """Anti-Mage"""}
+rel Hero hasItem Item {
+     @lore/cardinality/max 6
+  # left over
+}
+kind Empty {}
+"#
+    );
+
+    test!(
+        format_keeps_closing_comments_in_their_block,
+        r#"
+using dota:v2022
+rel Hero hasItem Item {
+  @lore/cardinality/max 6
+  # at most six, the backpack does not count
+}
+kind Hero
+"#
+    );
+
+    test!(
+        format_keeps_blocks_with_only_comments,
+        r#"
+using dota:v2022
+kind Empty {
+  # only a comment
+}
+kind Hero
+"#
+    );
+
+    test!(
+        format_keeps_the_file_header_on_top,
+        r#"
+# The base of the Dota 2 ontology.
+using dota:v2022
+prefix lore:v1 as @lore
+kind Hero
+"#
+    );
+
+    test!(
+        format_keeps_trailing_comments_on_their_line,
+        r#"
+using dota:v2022
+attr Name {
+  @lore/doc/en "A name to call something by." # trailing
+  @lore/label/en "Name"
+}
+"#
+    );
+}
@@ -1,7 +1,7 @@
 use crate::parsetree::*;
 use lore_ast::URI;
 use miette::Diagnostic;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -11,8 +11,15 @@ pub struct Validator {
     relations: Vec<lore_ast::Relation>,
     kinds: Vec<lore_ast::Kind>,
     attributes: Vec<lore_ast::Attribute>,
+    things: Vec<lore_ast::Thing>,
     aliases: HashMap<String, URI>,
     unresolved_names: Vec<lore_ast::Name>,
+    errors: Vec<SemanticError>,
+    /// The names the imports declare, when the file was read along with its imports. Only then
+    /// are references checked, since a lone file cannot know what other files declare.
+    imported: Option<HashSet<URI>>,
+    /// Names used as a relation's subject or object, or as a thing's kind.
+    references: Vec<lore_ast::Name>,
 }
 
 fn format_names(names: Vec<lore_ast::Name>) -> String {
@@ -21,17 +28,49 @@ fn format_names(names: Vec<lore_ast::Name>) -> String {
         if let Some(str) = name.alias {
             strs.push(format!("* {}", str))
         } else {
-            strs.push(format!("* {}", name.to_string()))
+            strs.push(format!("* {}", name))
         }
     }
     strs.join("\n").to_string()
 }
 
-#[derive(Error, Debug, Diagnostic)]
+fn format_primitives() -> String {
+    let names: Vec<&str> = lore_ast::Primitive::all()
+        .iter()
+        .map(|p| p.name())
+        .collect();
+    names.join(" | ")
+}
+
+#[derive(Error, Debug, Diagnostic, Clone, PartialEq, Eq)]
 #[diagnostic(code(lore::validator::semantic))]
 pub enum SemanticError {
     #[error("The follow names cannot be resolved: \n{}\nDid you forget to add a `prefix` alias or a `using` namespace?", format_names(.0.to_vec()))]
     UnresolvedNames(Vec<lore_ast::Name>),
+
+    #[error("`{}` looks like a built-in primitive but there is no such primitive.\nTry one of: {}", .0, format_primitives())]
+    UnknownPrimitive(lore_ast::Name),
+
+    #[error("`{}` is a built-in primitive and cannot be declared as a kind, an attribute, or a thing.", .0)]
+    PrimitiveRedeclared(lore_ast::Name),
+
+    #[error("`{}` is a built-in primitive and cannot be the subject of a relation.", .0)]
+    PrimitiveAsSubject(lore_ast::Name),
+
+    #[error("`{}` is not a valid BCP 47 language tag in `{}`.\nTry a tag like `en`, `es-AR`, or `zh-Hant`.", .1, .0)]
+    InvalidLanguageTag(lore_ast::Name, String),
+
+    #[error("The relation `{} {} {}` has an invalid cardinality: {}.", .0.subject, .0.predicate, .0.object, .1)]
+    InvalidCardinality(Box<lore_ast::Relation>, String),
+
+    #[error("The variant `{}` of `{}` must be a name, like `Strength` or `@dota/Strength`.", .1, .0)]
+    InvalidVariant(lore_ast::Name, lore_ast::Literal),
+
+    #[error("The superkind `{}` of `{}` must be a name, like `Unit` or `@dota/Unit`.", .1, .0)]
+    InvalidSuperkind(lore_ast::Name, Box<lore_ast::Literal>),
+
+    #[error("`{}` is not declared in this file or in the files it imports.\nDid you forget to `import` the file or package that declares it?", .0)]
+    NotImported(lore_ast::Name),
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -40,8 +79,15 @@ pub enum SemanticError {
 pub struct ValidationError {
     filename: PathBuf,
 
-    #[source]
-    error: SemanticError,
+    #[related]
+    errors: Vec<SemanticError>,
+}
+
+impl ValidationError {
+    /// Everything wrong with the file, in the order it was found.
+    pub fn errors(&self) -> &[SemanticError] {
+        &self.errors
+    }
 }
 
 impl Validator {
@@ -49,6 +95,19 @@ impl Validator {
         Validator::default()
     }
 
+    /// Resolves names in files without a `using` line against `namespace`.
+    pub fn with_namespace(mut self, namespace: URI) -> Validator {
+        self.local_namespace = Some(namespace);
+        self
+    }
+
+    /// Only lets names like `Hero` refer to primitives, to what the file itself declares, or to
+    /// `names`, which are what its imports declare. An empty set checks files without imports.
+    pub fn with_imports(mut self, names: HashSet<URI>) -> Validator {
+        self.imported = Some(names);
+        self
+    }
+
     pub fn validate(
         mut self,
         parsetree: Structure,
@@ -68,15 +127,22 @@ impl Validator {
 
         for item in parsetree.items() {
             match item {
-                StructureItem::Kind { name, fields } => {
+                StructureItem::Kind { name, fields, .. } => {
                     let name = self.normalize_name(name);
-                    let fields = self.normalize_fields(&fields);
-                    self.kinds.push(lore_ast::Kind { name, fields });
+                    self.check_not_primitive(&name);
+                    let fields = self.normalize_fields(fields);
+                    self.check_languages(&fields);
+                    let kind = lore_ast::Kind { name, fields };
+                    self.check_variants(&kind);
+                    self.check_superkinds(&kind);
+                    self.kinds.push(kind);
                 }
 
-                StructureItem::Attribute { name, fields } => {
+                StructureItem::Attribute { name, fields, .. } => {
                     let name = self.normalize_name(name);
-                    let fields = self.normalize_fields(&fields);
+                    self.check_not_primitive(&name);
+                    let fields = self.normalize_fields(fields);
+                    self.check_languages(&fields);
                     self.attributes.push(lore_ast::Attribute { name, fields });
                 }
 
@@ -85,34 +151,175 @@ impl Validator {
                     predicate,
                     object,
                     fields,
+                    ..
                 } => {
                     let subject = self.normalize_name(subject);
                     let predicate = self.normalize_name(predicate);
                     let object = self.normalize_name(object);
-                    let fields = self.normalize_fields(&fields);
-                    self.relations.push(lore_ast::Relation {
+                    self.reference(&subject);
+                    self.reference(&object);
+                    self.check_relation_primitives(&subject, &object);
+                    let fields = self.normalize_fields(fields);
+                    self.check_languages(&fields);
+                    let relation = lore_ast::Relation {
                         subject,
                         predicate,
                         object,
                         fields,
-                    })
+                    };
+                    self.check_cardinality(&relation);
+                    self.relations.push(relation)
+                }
+
+                StructureItem::Thing {
+                    name,
+                    kind,
+                    fields,
+                    spans,
+                    ..
+                } => {
+                    let name = self.normalize_name(name);
+                    self.check_not_primitive(&name);
+                    let kind = self.normalize_name(kind);
+                    self.reference(&kind);
+                    let fields = self.normalize_fields(fields);
+                    self.check_languages(&fields);
+                    let spans = lore_ast::ThingSpans {
+                        file: parsetree.filename().clone(),
+                        ..spans.clone()
+                    };
+                    self.things.push(lore_ast::Thing {
+                        name,
+                        kind,
+                        fields,
+                        spans,
+                    });
                 }
 
                 _ => (),
             }
         }
 
-        if self.unresolved_names.is_empty() {
+        self.check_imported();
+
+        let mut errors = vec![];
+        if !self.unresolved_names.is_empty() {
+            errors.push(SemanticError::UnresolvedNames(
+                self.unresolved_names.clone(),
+            ));
+        }
+        errors.append(&mut self.errors);
+
+        if !errors.is_empty() {
+            Err(ValidationError {
+                filename: parsetree.filename().clone(),
+                errors,
+            })
+        } else {
             Ok(lore_ast::Structure {
                 kinds: self.kinds,
                 attributes: self.attributes,
                 relations: self.relations,
+                things: self.things,
             })
-        } else {
-            Err(ValidationError {
-                filename: parsetree.filename().clone(),
-                error: SemanticError::UnresolvedNames(self.unresolved_names.clone()),
-            })
+        }
+    }
+
+    fn reference(&mut self, name: &lore_ast::Name) {
+        if !lore_ast::Primitive::is_primitive_like(&name.uri) {
+            self.references.push(name.clone());
+        }
+    }
+
+    fn check_imported(&mut self) {
+        let imported = match &self.imported {
+            Some(imported) => imported,
+            None => return,
+        };
+        let declared: HashSet<URI> = self
+            .kinds
+            .iter()
+            .map(|kind| kind.name.to_uri())
+            .chain(self.attributes.iter().map(|attr| attr.name.to_uri()))
+            .chain(self.things.iter().map(|thing| thing.name.to_uri()))
+            .chain(self.relations.iter().map(|rel| rel.predicate.to_uri()))
+            .collect();
+        for name in &self.references {
+            let uri = name.to_uri();
+            if !declared.contains(&uri) && !imported.contains(&uri) {
+                self.errors.push(SemanticError::NotImported(name.clone()));
+            }
+        }
+    }
+
+    fn check_not_primitive(&mut self, name: &lore_ast::Name) {
+        if lore_ast::Primitive::is_primitive_like(&name.uri) {
+            self.errors
+                .push(SemanticError::PrimitiveRedeclared(name.clone()));
+        }
+    }
+
+    fn check_relation_primitives(&mut self, subject: &lore_ast::Name, object: &lore_ast::Name) {
+        if lore_ast::Primitive::is_primitive_like(&subject.uri) {
+            self.errors
+                .push(SemanticError::PrimitiveAsSubject(subject.clone()));
+        }
+
+        if lore_ast::Primitive::is_primitive_like(&object.uri)
+            && lore_ast::Primitive::from_uri(&object.uri).is_none()
+        {
+            self.errors
+                .push(SemanticError::UnknownPrimitive(object.clone()));
+        }
+    }
+
+    fn check_languages(&mut self, fields: &[lore_ast::Field]) {
+        for field in fields {
+            for localized in lore_ast::Localized::all() {
+                if let Some(tag) = localized.language_of(field) {
+                    if !tag.is_empty() && !lore_ast::is_valid_language_tag(&tag) {
+                        self.errors
+                            .push(SemanticError::InvalidLanguageTag(field.name.clone(), tag));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_cardinality(&mut self, relation: &lore_ast::Relation) {
+        if let Err(err) = lore_ast::Cardinality::from_fields(&relation.fields) {
+            self.errors.push(SemanticError::InvalidCardinality(
+                Box::new(relation.clone()),
+                err.to_string(),
+            ));
+        }
+    }
+
+    fn check_variants(&mut self, kind: &lore_ast::Kind) {
+        for field in &kind.fields {
+            if lore_ast::Kind::is_variant_field(field) {
+                if let lore_ast::Literal::Name(_) = field.value {
+                    continue;
+                }
+                self.errors.push(SemanticError::InvalidVariant(
+                    kind.name.clone(),
+                    field.value.clone(),
+                ));
+            }
+        }
+    }
+
+    fn check_superkinds(&mut self, kind: &lore_ast::Kind) {
+        for field in &kind.fields {
+            if lore_ast::Kind::is_superkind_field(field) {
+                if let lore_ast::Literal::Name(_) = field.value {
+                    continue;
+                }
+                self.errors.push(SemanticError::InvalidSuperkind(
+                    kind.name.clone(),
+                    Box::new(field.value.clone()),
+                ));
+            }
         }
     }
 
@@ -129,9 +336,9 @@ impl Validator {
             }
             None => {
                 if name.is_unresolved() {
-                    for (prefix, expanded_uri) in (&self.aliases).into_iter() {
-                        if name.uri.has_prefix(&prefix) {
-                            name.set_uri(&name.uri.expand_prefix(&prefix, &expanded_uri));
+                    for (prefix, expanded_uri) in self.aliases.iter() {
+                        if name.uri.has_prefix(prefix) {
+                            name.set_uri(&name.uri.expand_prefix(prefix, expanded_uri));
                             return name;
                         }
                     }
@@ -145,6 +352,7 @@ impl Validator {
     pub fn normalize_literal(&mut self, lit: &Literal) -> lore_ast::Literal {
         match lit {
             Literal::Number(n) => lore_ast::Literal::Number(*n),
+            Literal::Float(f) => lore_ast::Literal::Float(f.to_string()),
             Literal::String(s) => lore_ast::Literal::String(s.to_string()),
             Literal::Name(n) => lore_ast::Literal::Name(self.normalize_name(n)),
         }
@@ -266,4 +474,245 @@ output:
         }
         "#
     );
+
+    test!(
+        validate_primitive_object,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+        attr Name
+        rel Name asString @lore/String
+        "#
+    );
+
+    test!(
+        validate_unknown_primitive,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+        attr Name
+        rel Name asString @lore/Strnig
+        "#
+    );
+
+    test!(
+        validate_primitive_redeclared,
+        r#"
+        prefix lore:v1 as @lore
+        kind @lore/String
+        "#
+    );
+
+    test!(
+        validate_cardinality,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind Hero
+        kind Ability
+        kind Item
+
+        rel Hero hasAbility Ability {
+          @lore/cardinality/min 1
+          @lore/cardinality/max @lore/many
+        }
+
+        rel Hero carries Item {
+          @lore/cardinality @lore/many
+        }
+        "#
+    );
+
+    test!(
+        validate_cardinality_min_greater_than_max,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        rel Hero hasAbility Ability {
+          @lore/cardinality/min 4
+          @lore/cardinality/max 2
+        }
+        "#
+    );
+
+    test!(
+        validate_cardinality_not_a_number,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        rel Hero hasAbility Ability {
+          @lore/cardinality "lots"
+        }
+        "#
+    );
+
+    test!(
+        validate_cardinality_conflicting,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        rel Hero hasAbility Ability {
+          @lore/cardinality 1
+          @lore/cardinality/max 2
+        }
+        "#
+    );
+
+    test!(
+        validate_language_tags,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind Hero {
+          @lore/doc "A playable character."
+          @lore/doc/en "A playable character."
+          @lore/doc/es-AR "Un personaje jugable."
+          @lore/label/zh-Hant "英雄"
+          @lore/label/sr-Latn-RS "Heroj"
+          @lore/label/de-CH-1996 "Held"
+          @lore/label/en-x-dota "Hero"
+        }
+        "#
+    );
+
+    test!(
+        validate_invalid_language_tag,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind Hero {
+          @lore/doc/e "A playable character."
+          @lore/label/en-u "Hero"
+        }
+        "#
+    );
+
+    test!(
+        validate_variants,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind PrimaryAttribute {
+          @lore/variant Strength
+          @lore/variant Agility
+          @lore/variant Intelligence
+        }
+        "#
+    );
+
+    test!(
+        validate_variant_not_a_name,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind PrimaryAttribute {
+          @lore/variant "Strength"
+        }
+        "#
+    );
+
+    test!(
+        validate_superkind_not_a_name,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind Hero {
+          @lore/superkind "Unit"
+        }
+        "#
+    );
+
+    test!(
+        validate_reports_every_error,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind PrimaryAttribute {
+          @lore/variant "Strength"
+        }
+        kind Hero {
+          @lore/superkind "Unit"
+          @lore/label/en-u "Hero"
+        }
+        rel @items/Item droppedBy Hero
+        "#
+    );
+
+    test!(
+        validate_thing,
+        r#"
+        prefix lore:v1 as @lore
+        prefix valve:heroes as @heroes
+        using dota:v2022
+
+        thing @heroes/AntiMage : Hero {
+          @lore/label/en "Anti-Mage"
+          movementSpeed 310
+          primaryAttribute Agility
+        }
+        "#
+    );
+
+    #[test]
+    fn validate_only_imported_names() {
+        let src = r#"
+        using dota:v2022
+        import "names.lore"
+
+        kind Hero
+        rel Hero mainName Name
+        rel Hero hasItem Item
+        thing AntiMage : Hero
+        "#;
+        let imported: HashSet<URI> = vec![URI::from_string("dota:v2022/Name".to_string())]
+            .into_iter()
+            .collect();
+        let parsetree = parser::Parser::for_string("imports", src)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let error = Validator::new()
+            .with_imports(imported)
+            .validate(parsetree)
+            .unwrap_err();
+        let errors: Vec<String> = error.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec!["`dota:v2022/Item` is not declared in this file or in the files it imports.\nDid you forget to `import` the file or package that declares it?"]
+        );
+    }
+
+    #[test]
+    fn validate_names_without_imports() {
+        let src = r#"
+        using dota:v2022
+
+        kind Hero
+        rel Hero mainName lore:v1/String
+        rel Hero hasItem dota:v2022/items/Item
+        "#;
+        let parsetree = parser::Parser::for_string("no-imports", src)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let error = Validator::new()
+            .with_imports(HashSet::new())
+            .validate(parsetree)
+            .unwrap_err();
+        let errors: Vec<String> = error.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec!["`dota:v2022/items/Item` is not declared in this file or in the files it imports.\nDid you forget to `import` the file or package that declares it?"]
+        );
+    }
 }
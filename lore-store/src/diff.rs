@@ -0,0 +1,370 @@
+use crate::store::*;
+use lore_ast::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Something declared in an ontology that a change applies to. Relations are told apart by
+/// their subject and predicate, since those are what generated code names them after.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Declaration {
+    Kind(URI),
+    Attribute(URI),
+    Relation(URI, URI),
+}
+
+impl std::fmt::Display for Declaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Declaration::Kind(uri) => write!(f, "kind {}", uri),
+            Declaration::Attribute(uri) => write!(f, "attr {}", uri),
+            Declaration::Relation(subject, predicate) => write!(f, "rel {} {}", subject, predicate),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed(String),
+}
+
+/// One difference between two versions of an ontology, and whether code generated from the
+/// old version could stop compiling or accept data the new version rejects.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Change {
+    pub declaration: Declaration,
+    pub kind: ChangeKind,
+    pub breaking: bool,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ChangeKind::Added => write!(f, "+ {}", self.declaration)?,
+            ChangeKind::Removed => write!(f, "- {}", self.declaration)?,
+            ChangeKind::Changed(how) => write!(f, "~ {}: {}", self.declaration, how)?,
+        }
+        if self.breaking {
+            write!(f, " (breaking)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Change {
+    fn new(declaration: &Declaration, kind: ChangeKind, breaking: bool) -> Change {
+        Change {
+            declaration: declaration.clone(),
+            kind,
+            breaking,
+        }
+    }
+}
+
+/// Everything that changed from `old` to `new`, sorted by declaration.
+///
+/// Only what is written on a declaration is compared, so relations a kind gets from a new
+/// superkind show up as changes to the superkind's relations. The new superkind is still
+/// breaking when it brings required relations, since things of the kind are checked against
+/// them.
+pub fn diff(old: &Store, new: &Store) -> Vec<Change> {
+    let mut changes = vec![];
+
+    for uri in old.kinds.keys() {
+        let declaration = Declaration::Kind(uri.clone());
+        match new.kinds.get(uri) {
+            None => changes.push(Change::new(&declaration, ChangeKind::Removed, true)),
+            Some(_) => changes.extend(kind_changes(old, new, uri)),
+        }
+    }
+    for uri in new.kinds.keys().filter(|uri| !old.kinds.contains_key(uri)) {
+        let declaration = Declaration::Kind(uri.clone());
+        changes.push(Change::new(&declaration, ChangeKind::Added, false));
+    }
+
+    for (uri, attribute) in &old.attributes {
+        let declaration = Declaration::Attribute(uri.clone());
+        match new.attributes.get(uri) {
+            None => changes.push(Change::new(&declaration, ChangeKind::Removed, true)),
+            Some(other) => changes.extend(field_changes(
+                &declaration,
+                &attribute.fields,
+                &other.fields,
+            )),
+        }
+    }
+    for uri in new
+        .attributes
+        .keys()
+        .filter(|uri| !old.attributes.contains_key(uri))
+    {
+        let declaration = Declaration::Attribute(uri.clone());
+        changes.push(Change::new(&declaration, ChangeKind::Added, false));
+    }
+
+    let old_relations = relations(old);
+    let new_relations = relations(new);
+    for (declaration, relation) in &old_relations {
+        match new_relations.get(declaration) {
+            None => changes.push(Change::new(declaration, ChangeKind::Removed, true)),
+            Some(other) => changes.extend(relation_changes(declaration, relation, other)),
+        }
+    }
+    for (declaration, relation) in &new_relations {
+        if !old_relations.contains_key(declaration) {
+            // values built by existing code will not have a required relation
+            let required = cardinality(relation).min > 0;
+            changes.push(Change::new(declaration, ChangeKind::Added, required));
+        }
+    }
+
+    changes.sort();
+    changes
+}
+
+fn relations(store: &Store) -> BTreeMap<Declaration, &Relation> {
+    store
+        .relations_by_subject
+        .values()
+        .flatten()
+        .map(|rel| {
+            let declaration = Declaration::Relation(rel.subject.to_uri(), rel.predicate.to_uri());
+            (declaration, rel)
+        })
+        .collect()
+}
+
+fn cardinality(relation: &Relation) -> Cardinality {
+    // the validator already rejected invalid cardinalities
+    Cardinality::from_fields(&relation.fields).unwrap_or_default()
+}
+
+fn kind_changes(old: &Store, new: &Store, uri: &URI) -> Vec<Change> {
+    let declaration = &Declaration::Kind(uri.clone());
+    let (before, after) = (&old.kinds[uri], &new.kinds[uri]);
+    let mut changes = vec![];
+
+    // generated code matches on variants exhaustively, so adding one breaks it too
+    let old_variants: BTreeSet<&URI> = before.variants().into_iter().map(|n| &n.uri).collect();
+    let new_variants: BTreeSet<&URI> = after.variants().into_iter().map(|n| &n.uri).collect();
+    for variant in old_variants.difference(&new_variants) {
+        let how = format!("variant `{}` removed", variant);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), true));
+    }
+    for variant in new_variants.difference(&old_variants) {
+        let how = format!("variant `{}` added", variant);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), true));
+    }
+
+    let old_superkinds: BTreeSet<&URI> = before.superkinds().into_iter().map(|n| &n.uri).collect();
+    let new_superkinds: BTreeSet<&URI> = after.superkinds().into_iter().map(|n| &n.uri).collect();
+    for superkind in old_superkinds.difference(&new_superkinds) {
+        let how = format!("superkind `{}` removed", superkind);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), true));
+    }
+    // existing things of the kind may not have the relations the superkind requires
+    let inherited = old.ancestry(uri);
+    for superkind in new_superkinds.difference(&old_superkinds) {
+        let how = format!("superkind `{}` added", superkind);
+        let required = new
+            .ancestry(superkind)
+            .iter()
+            .filter(|kind| !inherited.contains(kind))
+            .filter_map(|kind| new.relations_by_subject.get(kind))
+            .flatten()
+            .any(|relation| cardinality(relation).min > 0);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), required));
+    }
+
+    changes.extend(field_changes(declaration, &before.fields, &after.fields));
+    changes
+}
+
+fn relation_changes(declaration: &Declaration, old: &Relation, new: &Relation) -> Vec<Change> {
+    let mut changes = vec![];
+
+    if old.object.to_uri() != new.object.to_uri() {
+        let how = format!("object changed from `{}` to `{}`", old.object, new.object);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), true));
+    }
+
+    let (before, after) = (cardinality(old), cardinality(new));
+    if before != after {
+        let narrowed = after.min > before.min
+            || match (before.max, after.max) {
+                (None, Some(_)) => true,
+                (Some(before), Some(after)) => after < before,
+                (_, None) => false,
+            };
+        let how = format!(
+            "cardinality {} from {} to {}",
+            if narrowed { "narrowed" } else { "widened" },
+            before,
+            after
+        );
+        // a different multiplicity is a different type in generated code, even when wider
+        let breaking = narrowed || before.multiplicity() != after.multiplicity();
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), breaking));
+    }
+
+    changes.extend(field_changes(declaration, &old.fields, &new.fields));
+    changes
+}
+
+/// Changes to the fields that only annotate a declaration, like docs and labels, which never
+/// break generated code.
+fn field_changes(declaration: &Declaration, old: &[Field], new: &[Field]) -> Vec<Change> {
+    let annotations = |fields: &[Field]| {
+        let mut annotations: BTreeMap<URI, Vec<Literal>> = BTreeMap::new();
+        for field in fields {
+            if Kind::is_variant_field(field)
+                || Kind::is_superkind_field(field)
+                || Cardinality::is_cardinality_field(field)
+            {
+                continue;
+            }
+            annotations
+                .entry(field.name.uri.clone())
+                .or_default()
+                .push(field.value.clone());
+        }
+        annotations
+    };
+    let (old, new) = (annotations(old), annotations(new));
+
+    let mut changes = vec![];
+    for (name, values) in &old {
+        let how = match new.get(name) {
+            None => format!("field `{}` removed", name),
+            Some(other) if other != values => format!("field `{}` changed", name),
+            Some(_) => continue,
+        };
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), false));
+    }
+    for name in new.keys().filter(|name| !old.contains_key(name)) {
+        let how = format!("field `{}` added", name);
+        changes.push(Change::new(declaration, ChangeKind::Changed(how), false));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    macro_rules! test {
+        ($name:ident, $old:expr, $new:expr) => {
+            #[test]
+            fn $name() {
+                let mut old = Store::new();
+                old.add_from_string($old).unwrap();
+                let mut new = Store::new();
+                new.add_from_string($new).unwrap();
+                let changes: Vec<String> = diff(&old, &new).iter().map(|c| c.to_string()).collect();
+                assert_snapshot!(changes.join("\n"));
+            }
+        };
+    }
+
+    test!(
+        diff_kinds_and_attributes,
+        "using dota:v2022\nkind Hero\nkind Shop\nattr Name\n",
+        "using dota:v2022\nkind Hero\nkind Item\nattr Title\n"
+    );
+
+    test!(
+        diff_variants_and_superkinds,
+        r#"
+using dota:v2022
+kind Unit
+kind Creep
+kind Hero { lore:v1/superkind Creep }
+kind PrimaryAttribute {
+  lore:v1/variant Strength
+  lore:v1/variant Agility
+}
+"#,
+        r#"
+using dota:v2022
+kind Unit
+kind Creep
+kind Hero { lore:v1/superkind Unit }
+kind PrimaryAttribute {
+  lore:v1/variant Strength
+  lore:v1/variant Intelligence
+}
+"#
+    );
+
+    test!(
+        diff_superkinds_with_required_relations,
+        r#"
+using dota:v2022
+attr Name
+kind Unit
+kind Creep
+kind Hero { lore:v1/superkind Creep }
+kind Item
+kind Ward { lore:v1/superkind Item }
+rel Creep name Name { lore:v1/cardinality/min 1 }
+rel Unit nickname Name { lore:v1/cardinality/max 1 }
+"#,
+        r#"
+using dota:v2022
+attr Name
+kind Unit { lore:v1/superkind Creep }
+kind Creep
+kind Hero { lore:v1/superkind Creep lore:v1/superkind Unit }
+kind Item { lore:v1/superkind Creep }
+kind Ward { lore:v1/superkind Item }
+rel Creep name Name { lore:v1/cardinality/min 1 }
+rel Unit nickname Name { lore:v1/cardinality/max 1 }
+"#
+    );
+
+    test!(
+        diff_relations,
+        r#"
+using dota:v2022
+kind Hero
+kind Item
+kind Ability
+attr Name
+rel Hero mainName Name
+rel Hero hasItem Item { lore:v1/cardinality/max 6 }
+rel Hero hasAbility Ability { lore:v1/cardinality lore:v1/many }
+rel Hero nickname Name { lore:v1/cardinality/max 1 }
+rel Hero bestFriend Hero
+"#,
+        r#"
+using dota:v2022
+kind Hero
+kind Item
+kind Ability
+attr Name
+rel Hero mainName Name
+rel Hero hasItem Item { lore:v1/cardinality/max 8 }
+rel Hero hasAbility Ability { lore:v1/cardinality/min 1 lore:v1/cardinality/max 4 }
+rel Hero nickname Name { lore:v1/cardinality lore:v1/many }
+rel Hero bestFriend Item
+rel Hero level Name
+rel Hero title Name { lore:v1/cardinality/max 1 }
+"#
+    );
+
+    test!(
+        diff_annotations_are_not_breaking,
+        "using dota:v2022\nkind Hero { lore:v1/docs \"A hero\" }\n",
+        "using dota:v2022\nkind Hero { lore:v1/docs \"A playable hero\" lore:v1/label \"Hero\" }\n"
+    );
+
+    #[test]
+    fn diff_same_store_is_empty() {
+        let mut store = Store::new();
+        store
+            .add_from_string("using dota:v2022\nkind Hero\nattr Name\nrel Hero mainName Name\n")
+            .unwrap();
+        assert!(diff(&store, &store).is_empty());
+    }
+}
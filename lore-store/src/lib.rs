@@ -1,4 +1,16 @@
+pub mod diff;
+pub mod load;
+pub mod manifest;
+pub mod packages;
 pub mod quads;
+pub mod shacl;
 pub mod store;
+pub mod things;
 
+pub use diff::*;
+pub use load::*;
+pub use manifest::*;
+pub use packages::*;
+pub use shacl::*;
 pub use store::*;
+pub use things::*;
@@ -0,0 +1,761 @@
+use crate::store::*;
+use lore_ast::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// The formats `lore load` reads instance data from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    /// A header row naming the columns, then one thing per row.
+    Csv,
+    /// An array of flat objects, one thing per object.
+    Json,
+}
+
+impl DataFormat {
+    pub fn of_path(path: &Path) -> Option<DataFormat> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(DataFormat::Csv),
+            "json" => Some(DataFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// How the columns of a CSV file, or the keys of a JSON object, become the relations of a kind.
+/// Written as a small JSON file:
+///
+/// ```json
+/// {
+///   "kind": "dota:v2022/Hero",
+///   "id": "hero_name",
+///   "namespace": "dota:v2022/heroes",
+///   "separator": ";",
+///   "columns": {
+///     "speed": "movementSpeed",
+///     "notes": null
+///   }
+/// }
+/// ```
+///
+/// Every field is optional. Columns that are not listed are matched against the predicates of
+/// the relations of the kind by name, and columns mapped to `null` are skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    /// The kind of every thing, as a URI or the last segment of one.
+    pub kind: Option<String>,
+    /// The column that names each thing.
+    pub id: String,
+    /// Where things are named, instead of the namespace of their kind.
+    pub namespace: Option<String>,
+    /// Splits a CSV cell into many values, for relations that take more than one.
+    pub separator: String,
+    pub columns: BTreeMap<String, Option<String>>,
+}
+
+impl Default for Mapping {
+    fn default() -> Mapping {
+        Mapping {
+            kind: None,
+            id: "id".to_string(),
+            namespace: None,
+            separator: ";".to_string(),
+            columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl Mapping {
+    pub fn new() -> Mapping {
+        Mapping::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Mapping, StoreError> {
+        let invalid = |reason: String| StoreError::InvalidMapping { reason };
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        let object = value
+            .as_object()
+            .ok_or_else(|| invalid("expected a JSON object".to_string()))?;
+
+        let string = |key: &str| -> Result<Option<String>, StoreError> {
+            match object.get(key) {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(serde_json::Value::String(s)) => Ok(Some(s.clone())),
+                Some(_) => Err(invalid(format!("`{}` must be a string", key))),
+            }
+        };
+
+        let mut mapping = Mapping::new();
+        mapping.kind = string("kind")?;
+        mapping.namespace = string("namespace")?;
+        if let Some(id) = string("id")? {
+            mapping.id = id;
+        }
+        if let Some(separator) = string("separator")? {
+            mapping.separator = separator;
+        }
+
+        match object.get("columns") {
+            None => (),
+            Some(serde_json::Value::Object(columns)) => {
+                for (column, predicate) in columns {
+                    let predicate = match predicate {
+                        serde_json::Value::String(s) => Some(s.clone()),
+                        serde_json::Value::Null => None,
+                        _ => {
+                            return Err(invalid(format!(
+                                "column `{}` must map to a predicate or to null",
+                                column
+                            )))
+                        }
+                    };
+                    mapping.columns.insert(column.clone(), predicate);
+                }
+            }
+            Some(_) => return Err(invalid("`columns` must be an object".to_string())),
+        }
+
+        Ok(mapping)
+    }
+}
+
+/// One value read from the data, with where it was read from.
+struct Cell {
+    column: String,
+    values: Vec<String>,
+    span: Span,
+}
+
+struct Record {
+    cells: Vec<Cell>,
+}
+
+impl Record {
+    fn get(&self, column: &str) -> Option<&Cell> {
+        self.cells.iter().find(|cell| cell.column == column)
+    }
+}
+
+/// Turns the name of a thing in the data into something that can be written in a `.lore` file.
+fn identifier(id: &str) -> String {
+    id.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Splits CSV text into rows of cells, each with the span of the cell in the text. Quoted cells
+/// may contain commas, newlines, and quotes written twice.
+fn read_csv(text: &str) -> Result<Vec<Vec<(String, Span)>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if let Some((_, '"')) = chars.peek() {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            ',' if !quoted => {
+                row.push((
+                    std::mem::take(&mut cell),
+                    Span {
+                        offset: start,
+                        length: i - start,
+                    },
+                ));
+                start = i + 1;
+            }
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                let end = if text[..i].ends_with('\r') { i - 1 } else { i };
+                row.push((
+                    std::mem::take(&mut cell),
+                    Span {
+                        offset: start,
+                        length: end - start,
+                    },
+                ));
+                rows.push(std::mem::take(&mut row));
+                start = i + 1;
+            }
+            c => cell.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("a quoted cell is never closed".to_string());
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push((
+            cell,
+            Span {
+                offset: start,
+                length: text.len() - start,
+            },
+        ));
+        rows.push(row);
+    }
+
+    // blank lines are not rows
+    rows.retain(|row| !(row.len() == 1 && row[0].0.trim().is_empty()));
+    Ok(rows)
+}
+
+fn csv_records(text: &str, separator: &str) -> Result<Vec<Record>, String> {
+    let mut rows = read_csv(text)?.into_iter();
+    let header: Vec<String> = match rows.next() {
+        Some(header) => header
+            .into_iter()
+            .map(|(column, _)| column.trim().to_string())
+            .collect(),
+        None => return Ok(vec![]),
+    };
+
+    let mut records = vec![];
+    for (line, row) in rows.enumerate() {
+        if row.len() != header.len() {
+            return Err(format!(
+                "row {} has {} cells, but the header has {} columns",
+                line + 2,
+                row.len(),
+                header.len()
+            ));
+        }
+        let cells = header
+            .iter()
+            .zip(row)
+            .map(|(column, (text, span))| Cell {
+                column: column.clone(),
+                values: text
+                    .split(separator)
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+                span,
+            })
+            .collect();
+        records.push(Record { cells });
+    }
+    Ok(records)
+}
+
+fn json_records(text: &str) -> Result<Vec<Record>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let objects = value
+        .as_array()
+        .ok_or_else(|| "expected an array of objects".to_string())?;
+
+    let scalar = |value: &serde_json::Value| -> Result<Option<String>, String> {
+        match value {
+            serde_json::Value::Null => Ok(None),
+            serde_json::Value::Bool(b) => Ok(Some(b.to_string())),
+            serde_json::Value::Number(n) => Ok(Some(n.to_string())),
+            serde_json::Value::String(s) => Ok(Some(s.clone())),
+            _ => Err(format!(
+                "expected a string, number, or boolean, but found {}",
+                value
+            )),
+        }
+    };
+
+    let mut records = vec![];
+    for object in objects {
+        let object = object
+            .as_object()
+            .ok_or_else(|| format!("expected an object, but found {}", object))?;
+        let mut cells = vec![];
+        for (key, value) in object {
+            let values = match value {
+                serde_json::Value::Array(values) => values.iter().map(scalar).collect::<Result<
+                    Vec<Option<String>>,
+                    String,
+                >>(
+                )?,
+                value => vec![scalar(value)?],
+            };
+            cells.push(Cell {
+                column: key.clone(),
+                values: values.into_iter().flatten().collect(),
+                // JSON is parsed without positions, so diagnostics point at the whole file
+                span: Span::default(),
+            });
+        }
+        records.push(Record { cells });
+    }
+    Ok(records)
+}
+
+impl Store {
+    /// Finds a kind by its full URI or, when that is unambiguous, by its last segment.
+    pub fn resolve_kind(&self, name: &str) -> Result<URI, StoreError> {
+        let uri = URI::from_string(name.to_string());
+        if self.kinds.contains_key(&uri) {
+            return Ok(uri);
+        }
+
+        let candidates: Vec<&Kind> = self
+            .kinds()
+            .into_iter()
+            .filter(|kind| kind.name.uri.local_name() == name)
+            .collect();
+        match candidates.as_slice() {
+            [kind] => Ok(kind.name.to_uri()),
+            [] => Err(StoreError::InvalidMapping {
+                reason: format!("there is no kind named `{}`", name),
+            }),
+            candidates => Err(StoreError::InvalidMapping {
+                reason: format!(
+                    "`{}` could be any of {}",
+                    name,
+                    candidates
+                        .iter()
+                        .map(|kind| format!("`{}`", kind.name))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            }),
+        }
+    }
+
+    /// Reads the things in a CSV or JSON file according to a mapping and adds them to the store.
+    /// The things are returned so they can be written out, and can be checked against the
+    /// ontology with `check_things`.
+    pub fn load_data(&mut self, path: &Path, mapping: &Mapping) -> Result<Vec<Thing>, StoreError> {
+        let invalid = |reason: String| StoreError::InvalidData {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let format = DataFormat::of_path(path)
+            .ok_or_else(|| invalid("expected a .csv or a .json file".to_string()))?;
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+
+        let records = match format {
+            DataFormat::Csv => csv_records(&text, &mapping.separator),
+            DataFormat::Json => json_records(&text),
+        }
+        .map_err(invalid)?;
+
+        let kind = match &mapping.kind {
+            Some(kind) => self.resolve_kind(kind)?,
+            None => {
+                return Err(StoreError::InvalidMapping {
+                    reason: "no kind was given for the things".to_string(),
+                })
+            }
+        };
+        let namespace = match &mapping.namespace {
+            Some(namespace) => URI::from_string(namespace.clone()),
+            None => kind.namespace(),
+        };
+
+        let mut things = vec![];
+        for record in &records {
+            things.push(self.thing(path, mapping, &kind, &namespace, record)?);
+        }
+
+        self.sources.insert(path.to_path_buf(), text);
+        self.add_tree(Structure {
+            things: things.clone(),
+            ..Structure::default()
+        })?;
+        Ok(things)
+    }
+
+    fn thing(
+        &self,
+        path: &Path,
+        mapping: &Mapping,
+        kind: &URI,
+        namespace: &URI,
+        record: &Record,
+    ) -> Result<Thing, StoreError> {
+        let id = record
+            .get(&mapping.id)
+            .filter(|cell| cell.values.len() == 1)
+            .ok_or_else(|| StoreError::InvalidData {
+                path: path.to_path_buf(),
+                reason: format!("every thing needs exactly one `{}`", mapping.id),
+            })?;
+
+        let mut thing = Thing {
+            name: Name::of_uri(&self.thing_uri(namespace, &id.values[0])),
+            kind: Name::of_uri(kind),
+            fields: vec![],
+            spans: ThingSpans {
+                file: PathBuf::from(path),
+                name: id.span,
+                kind: id.span,
+                fields: vec![],
+            },
+        };
+
+        let mut relations: Vec<&Relation> = vec![];
+        for kind in self.ancestry(kind) {
+            if let Some(rels) = self.relations_by_subject.get(&kind) {
+                relations.extend(rels);
+            }
+        }
+
+        for cell in &record.cells {
+            if cell.column == mapping.id {
+                continue;
+            }
+            let predicate = match mapping.columns.get(&cell.column) {
+                Some(None) => continue,
+                Some(Some(predicate)) => predicate,
+                None => &cell.column,
+            };
+            let rel = relations
+                .iter()
+                .find(|rel| {
+                    rel.predicate.to_string() == *predicate
+                        || rel.predicate.uri.local_name() == *predicate
+                })
+                .ok_or_else(|| StoreError::InvalidMapping {
+                    reason: format!(
+                        "column `{}` is not a relation of `{}`. Map it to one, or to null to skip it",
+                        cell.column, kind
+                    ),
+                })?;
+
+            for value in &cell.values {
+                thing.fields.push(Field {
+                    name: rel.predicate.clone(),
+                    value: self.literal(namespace, &rel.object, value),
+                });
+                thing.spans.fields.push(cell.span);
+            }
+        }
+
+        Ok(thing)
+    }
+
+    fn thing_uri(&self, namespace: &URI, id: &str) -> URI {
+        if id.contains(':') {
+            URI::from_string(id.to_string())
+        } else {
+            namespace.join(&identifier(id))
+        }
+    }
+
+    /// Reads a value as the object of a relation would be written in a `.lore` file.
+    fn literal(&self, namespace: &URI, object: &Name, value: &str) -> Literal {
+        if let Some(primitive) = self.primitive(&object.uri) {
+            return match primitive {
+                Primitive::Int => match value.parse::<u64>() {
+                    Ok(n) => Literal::Number(n),
+                    Err(_) => Literal::String(value.to_string()),
+                },
+                // whole floats are written like any other number, the rest with a fractional part
+                Primitive::Float => match value.parse::<f64>() {
+                    Ok(f) if f >= 0.0 && f.fract() == 0.0 && f <= u64::MAX as f64 => {
+                        Literal::Number(f as u64)
+                    }
+                    Ok(f) if f.is_finite() && f >= 0.0 => Literal::Float(f.to_string()),
+                    _ => Literal::String(value.to_string()),
+                },
+                _ => Literal::String(value.to_string()),
+            };
+        }
+
+        match self.kinds.get(&object.uri) {
+            Some(kind) if kind.is_enumeration() => {
+                let variant = kind.variants().into_iter().find(|variant| {
+                    variant.to_string() == value || variant.uri.local_name() == value
+                });
+                match variant {
+                    Some(variant) => Literal::Name(variant.clone()),
+                    None => Literal::Name(Name::of_uri(
+                        &self.thing_uri(&object.uri.namespace(), value),
+                    )),
+                }
+            }
+            Some(_) => Literal::Name(Name::of_uri(&self.thing_uri(namespace, value))),
+            None => Literal::String(value.to_string()),
+        }
+    }
+}
+
+/// Writes things as `thing` declarations with full URIs, so the file reads the same wherever it
+/// is included. It imports the files that declare their kinds and relations, relative to `path`,
+/// where it will be written.
+pub fn things_to_lore(store: &Store, things: &[Thing], path: &Path) -> String {
+    let dir = match path.parent() {
+        Some(dir) if dir != Path::new("") => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let dir = dir.canonicalize().unwrap_or(dir);
+    let itself = path.canonicalize().ok();
+
+    let mut files = BTreeSet::new();
+    for thing in things {
+        files.extend(store.files_declaring(&thing.kind.uri));
+        for field in &thing.fields {
+            files.extend(store.files_declaring(&field.name.uri));
+        }
+    }
+
+    let mut out = String::new();
+    for file in files {
+        if Some(file) == itself.as_ref() {
+            continue;
+        }
+        let import = relative_path(&dir, file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        out.push_str(&format!(
+            "import \"{}\"\n",
+            lore_parser::escape_string(&import)
+        ));
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    for (i, thing) in things.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        if thing.fields.is_empty() {
+            out.push_str(&format!("thing {} : {}\n", thing.name, thing.kind));
+            continue;
+        }
+        out.push_str(&format!("thing {} : {} {{\n", thing.name, thing.kind));
+        for field in &thing.fields {
+            let value = match &field.value {
+                Literal::String(s) => format!("\"{}\"", lore_parser::escape_string(s)),
+                Literal::Number(n) => n.to_string(),
+                Literal::Float(f) => f.clone(),
+                Literal::Name(name) => name.to_string(),
+            };
+            out.push_str(&format!("  {} {}\n", field.name, value));
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+/// `file` as a path from `dir`, going up as many directories as it takes.
+fn relative_path(dir: &Path, file: &Path) -> PathBuf {
+    let from: Vec<_> = dir.components().collect();
+    let to: Vec<_> = file.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    const HEROES: &str = r#"
+prefix lore:v1 as @lore
+using dota:v2022
+
+kind Hero
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+}
+
+rel Hero movementSpeed @lore/Int
+rel Hero turnRate @lore/Float {
+  @lore/cardinality/max 1
+}
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero alias @lore/String {
+  @lore/cardinality @lore/many
+}
+"#;
+
+    fn load(
+        file_name: &str,
+        data: &str,
+        mapping: Mapping,
+    ) -> (Store, Result<Vec<Thing>, StoreError>) {
+        let path = std::env::temp_dir().join(file_name);
+        std::fs::write(&path, data).unwrap();
+        let mut store = Store::new();
+        store.add_from_string(HEROES).unwrap();
+        let things = store.load_data(&path, &mapping);
+        std::fs::remove_file(&path).unwrap();
+        (store, things)
+    }
+
+    #[test]
+    fn read_csv_quoting() {
+        let rows = read_csv("a,\"b, \"\"c\"\"\"\r\n\n1,2").unwrap();
+        let cells: Vec<Vec<&str>> = rows
+            .iter()
+            .map(|row| row.iter().map(|(cell, _)| cell.as_str()).collect())
+            .collect();
+        assert_eq!(cells, vec![vec!["a", "b, \"c\""], vec!["1", "2"]]);
+        assert_eq!(
+            rows[1][1].1,
+            Span {
+                offset: 17,
+                length: 1
+            }
+        );
+        assert!(read_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn mapping_from_json() {
+        let mapping = Mapping::from_json(
+            r#"{ "kind": "Hero", "id": "hero", "columns": { "speed": "movementSpeed", "notes": null } }"#,
+        )
+        .unwrap();
+        assert_eq!(mapping.kind, Some("Hero".to_string()));
+        assert_eq!(mapping.id, "hero");
+        assert_eq!(mapping.separator, ";");
+        assert_eq!(
+            mapping.columns.get("speed"),
+            Some(&Some("movementSpeed".to_string()))
+        );
+        assert_eq!(mapping.columns.get("notes"), Some(&None));
+
+        assert!(Mapping::from_json(r#"{ "columns": { "speed": 1 } }"#).is_err());
+    }
+
+    #[test]
+    fn load_csv() {
+        let mut mapping = Mapping::new();
+        mapping.kind = Some("Hero".to_string());
+        mapping.id = "hero".to_string();
+        mapping
+            .columns
+            .insert("speed".to_string(), Some("movementSpeed".to_string()));
+        mapping.columns.insert("notes".to_string(), None);
+
+        let (store, things) = load(
+            "lore_load_csv.csv",
+            "hero,speed,primaryAttribute,alias,notes\nAnti-Mage,310,Agility,Magina;Wei,\"fast, very\"\nPudge,280,Strength,,\n",
+            mapping,
+        );
+        let things = things.unwrap();
+        assert!(store.check_things().is_ok());
+        assert_eq!(store.things().len(), 2);
+        // `310` is the second cell of the second line
+        assert_eq!(
+            things[0].spans.fields[0],
+            Span {
+                offset: 50,
+                length: 3
+            }
+        );
+        assert_snapshot!(things_to_lore(&store, &things, Path::new("heroes.lore")));
+    }
+
+    #[test]
+    fn load_json() {
+        let mut mapping = Mapping::new();
+        mapping.kind = Some("dota:v2022/Hero".to_string());
+        mapping.namespace = Some("valve:heroes".to_string());
+
+        let (store, things) = load(
+            "lore_load_json.json",
+            r#"[
+              { "id": "Anti-Mage", "movementSpeed": 310, "turnRate": 0.5, "primaryAttribute": "Agility", "alias": ["Magina", "Wei"] },
+              { "id": "Pudge", "movementSpeed": "slow", "primaryAttribute": "Charisma", "alias": null }
+            ]"#,
+            mapping,
+        );
+        let things = things.unwrap();
+        assert_snapshot!(things_to_lore(&store, &things, Path::new("heroes.lore")));
+
+        let errors: Vec<String> = match store.check_things() {
+            Err(StoreError::InvalidThings { errors }) => {
+                errors.iter().map(|e| e.message.clone()).collect()
+            }
+            _ => vec![],
+        };
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn load_unknown_column() {
+        let mut mapping = Mapping::new();
+        mapping.kind = Some("Hero".to_string());
+        let (_, things) = load(
+            "lore_load_unknown.csv",
+            "id,attackRange\nPudge,150\n",
+            mapping,
+        );
+        assert!(matches!(things, Err(StoreError::InvalidMapping { .. })));
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let dir = std::env::temp_dir().join(format!("lore_load_round_trip_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("heroes.lore"), HEROES).unwrap();
+        std::fs::write(
+            dir.join("data/heroes.json"),
+            r#"[
+              { "id": "Pudge", "movementSpeed": 280, "turnRate": 0.5, "primaryAttribute": "Strength", "alias": ["The \"Butcher\"", "C:\\meat\\", "\\n"] }
+            ]"#,
+        )
+        .unwrap();
+
+        let mut store = Store::new();
+        store.add_file(dir.join("heroes.lore")).unwrap();
+        let mut mapping = Mapping::new();
+        mapping.kind = Some("Hero".to_string());
+        let things = store
+            .load_data(&dir.join("data/heroes.json"), &mapping)
+            .unwrap();
+        assert!(store.check_things().is_ok());
+
+        // written next to the data, so the ontology is one directory up
+        let output = dir.join("data/heroes.lore");
+        let lore = things_to_lore(&store, &things, &output);
+        std::fs::write(&output, &lore).unwrap();
+        let mut reloaded = Store::new();
+        let added = reloaded.add_file(output).map(|_| ());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(lore.starts_with("import \"../heroes.lore\"\n\n"));
+        added.unwrap();
+        assert!(reloaded.check_things().is_ok());
+        // names read back from a file remember how they were written, so only compare values
+        let values = |things: Vec<&Thing>| -> Vec<(URI, Literal)> {
+            things
+                .iter()
+                .flat_map(|thing| &thing.fields)
+                .map(|field| (field.name.uri.clone(), field.value.clone()))
+                .map(|(uri, value)| match value {
+                    Literal::Name(name) => (uri, Literal::Name(Name::of_uri(&name.uri))),
+                    value => (uri, value),
+                })
+                .collect()
+        };
+        assert_eq!(values(things.iter().collect()), values(reloaded.things()));
+    }
+}
@@ -0,0 +1,460 @@
+use crate::packages::*;
+use crate::store::StoreError;
+use lore_ast::URI;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "lore.toml";
+
+/// Where the packages a project can `import` live, next to its manifest.
+pub const PACKAGES_DIR: &str = "lore_packages";
+
+/// A project described by a `lore.toml`:
+///
+/// ```toml
+/// [project]
+/// name = "dota2"
+/// namespace = "dota:v2022"
+/// sources = ["ontology"]
+///
+/// [codegen.ocaml]
+/// output-dir = "gen/ocaml"
+/// json = true
+///
+/// [dependencies]
+/// names = { path = "../names", version = "1.2.0" }
+/// items = { archive = "vendor/items-2.0.0.tar.gz", version = "2.0.0" }
+/// ```
+///
+/// Paths in it are relative to the directory it is in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub root: PathBuf,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// The namespace for files without a `using` line.
+    pub namespace: Option<URI>,
+    /// Files and directories to read `.lore` files from.
+    pub sources: Vec<PathBuf>,
+    pub targets: Vec<Target>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A `[dependencies]` entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    /// The version the package must declare, when pinned.
+    pub version: Option<String>,
+    pub source: DependencySource,
+}
+
+/// Where a package is, relative to the manifest that depends on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DependencySource {
+    Path(PathBuf),
+    /// A `.tar` or `.tar.gz` with the package in it.
+    Archive(PathBuf),
+}
+
+impl std::fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencySource::Path(path) => write!(f, "path+{}", path.display()),
+            DependencySource::Archive(path) => write!(f, "archive+{}", path.display()),
+        }
+    }
+}
+
+/// A `[codegen.<target>]` table, with the same options as `lore codegen`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub target: String,
+    pub output_dir: PathBuf,
+    pub json: bool,
+    pub strip_namespace: bool,
+    pub languages: Vec<String>,
+    pub all_languages: bool,
+    pub per_kind: bool,
+    pub proto_lock: PathBuf,
+    pub sql_dialect: String,
+}
+
+impl Target {
+    fn new(root: &Path, target: &str) -> Target {
+        Target {
+            target: target.to_string(),
+            output_dir: root.join("gen").join(target),
+            json: false,
+            strip_namespace: false,
+            languages: vec!["en".to_string()],
+            all_languages: false,
+            per_kind: false,
+            proto_lock: root.join("proto.lock"),
+            sql_dialect: "postgres".to_string(),
+        }
+    }
+}
+
+/// Every `.lore` file in `paths`, looking into directories but not into hidden ones or into
+/// packages, which are only read when imported. Files named directly are kept whatever their extension.
+pub fn lore_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = BTreeSet::new();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+    while let Some(path) = pending.pop() {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if (hidden || path.ends_with(PACKAGES_DIR)) && !paths.contains(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            if let Ok(entries) = std::fs::read_dir(&path) {
+                pending.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        } else if paths.contains(&path) || is_lore_file(&path) {
+            files.insert(path);
+        }
+    }
+    files.into_iter().collect()
+}
+
+pub fn is_lore_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "lore")
+}
+
+/// Checks a table only has the keys we know, so typos don't go unnoticed.
+fn known_keys(table: &toml::value::Table, keys: &[&str], within: &str) -> Result<(), String> {
+    match table.keys().find(|key| !keys.contains(&key.as_str())) {
+        Some(key) => Err(format!(
+            "unknown key `{}` in {}. Try one of: {}",
+            key,
+            within,
+            keys.join(" | ")
+        )),
+        None => Ok(()),
+    }
+}
+
+fn string(table: &toml::value::Table, key: &str) -> Result<Option<String>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("`{}` must be a string", key)),
+    }
+}
+
+fn strings(table: &toml::value::Table, key: &str) -> Result<Option<Vec<String>>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::Array(values)) => values
+            .iter()
+            .map(|value| match value {
+                toml::Value::String(s) => Ok(s.clone()),
+                _ => Err(format!("`{}` must be a list of strings", key)),
+            })
+            .collect::<Result<Vec<String>, String>>()
+            .map(Some),
+        Some(_) => Err(format!("`{}` must be a list of strings", key)),
+    }
+}
+
+fn boolean(table: &toml::value::Table, key: &str) -> Result<bool, String> {
+    match table.get(key) {
+        None => Ok(false),
+        Some(toml::Value::Boolean(b)) => Ok(*b),
+        Some(_) => Err(format!("`{}` must be true or false", key)),
+    }
+}
+
+impl Manifest {
+    /// Looks for a `lore.toml` in `dir` and then in every directory above it.
+    pub fn find(dir: &Path) -> Result<Option<Manifest>, StoreError> {
+        for dir in dir.ancestors() {
+            let path = dir.join(MANIFEST_FILE);
+            if path.is_file() {
+                return Manifest::read(&path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn read(path: &Path) -> Result<Manifest, StoreError> {
+        let toml = std::fs::read_to_string(path).map_err(|e| StoreError::InvalidManifest {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        let root = path.parent().unwrap_or_else(|| Path::new("."));
+        Manifest::from_toml(root, &toml).map_err(|reason| StoreError::InvalidManifest {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+
+    pub fn from_toml(root: &Path, toml: &str) -> Result<Manifest, String> {
+        let value: toml::Value = toml.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let table = value
+            .as_table()
+            .ok_or_else(|| "expected a TOML table".to_string())?;
+        known_keys(
+            table,
+            &["project", "codegen", "dependencies"],
+            "the manifest",
+        )?;
+
+        let mut manifest = Manifest {
+            root: root.to_path_buf(),
+            name: None,
+            version: None,
+            namespace: None,
+            sources: vec![root.to_path_buf()],
+            targets: vec![],
+            dependencies: vec![],
+        };
+
+        match table.get("project") {
+            None => (),
+            Some(toml::Value::Table(project)) => {
+                known_keys(
+                    project,
+                    &["name", "version", "namespace", "sources"],
+                    "[project]",
+                )?;
+                manifest.name = string(project, "name")?;
+                manifest.version = string(project, "version")?;
+                manifest.namespace = string(project, "namespace")?.map(URI::from_string);
+                if let Some(sources) = strings(project, "sources")? {
+                    manifest.sources = sources.iter().map(|source| root.join(source)).collect();
+                }
+            }
+            Some(_) => return Err("`project` must be a table".to_string()),
+        }
+
+        match table.get("codegen") {
+            None => (),
+            Some(toml::Value::Table(targets)) => {
+                for (name, options) in targets {
+                    let within = format!("[codegen.{}]", name);
+                    let options = options
+                        .as_table()
+                        .ok_or_else(|| format!("{} must be a table", within))?;
+                    known_keys(
+                        options,
+                        &[
+                            "output-dir",
+                            "json",
+                            "strip-namespace",
+                            "lang",
+                            "all-langs",
+                            "per-kind",
+                            "proto-lock",
+                            "sql-dialect",
+                        ],
+                        &within,
+                    )?;
+
+                    let mut target = Target::new(root, name);
+                    if let Some(output_dir) = string(options, "output-dir")? {
+                        target.output_dir = root.join(output_dir);
+                    }
+                    target.json = boolean(options, "json")?;
+                    target.strip_namespace = boolean(options, "strip-namespace")?;
+                    if let Some(languages) = strings(options, "lang")? {
+                        target.languages = languages;
+                    }
+                    target.all_languages = boolean(options, "all-langs")?;
+                    target.per_kind = boolean(options, "per-kind")?;
+                    if let Some(proto_lock) = string(options, "proto-lock")? {
+                        target.proto_lock = root.join(proto_lock);
+                    }
+                    if let Some(sql_dialect) = string(options, "sql-dialect")? {
+                        target.sql_dialect = sql_dialect;
+                    }
+                    manifest.targets.push(target);
+                }
+            }
+            Some(_) => return Err("`codegen` must be a table of targets".to_string()),
+        }
+
+        match table.get("dependencies") {
+            None => (),
+            Some(toml::Value::Table(dependencies)) => {
+                for (name, dependency) in dependencies {
+                    let within = format!("dependency `{}`", name);
+                    let dependency = dependency
+                        .as_table()
+                        .ok_or_else(|| format!("{} must be a table", within))?;
+                    known_keys(dependency, &["path", "archive", "version"], &within)?;
+                    let source = match (string(dependency, "path")?, string(dependency, "archive")?)
+                    {
+                        (Some(path), None) => DependencySource::Path(PathBuf::from(path)),
+                        (None, Some(archive)) => DependencySource::Archive(PathBuf::from(archive)),
+                        _ => {
+                            return Err(format!("{} needs either a `path` or an `archive`", within))
+                        }
+                    };
+                    manifest.dependencies.push(Dependency {
+                        name: name.clone(),
+                        version: string(dependency, "version")?,
+                        source,
+                    });
+                }
+            }
+            Some(_) => return Err("`dependencies` must be a table".to_string()),
+        }
+
+        Ok(manifest)
+    }
+
+    /// Every `.lore` file in the sources.
+    pub fn files(&self) -> Vec<PathBuf> {
+        lore_files(&self.sources)
+    }
+
+    /// Every package the project can import: the ones in the packages directory, and every
+    /// dependency, direct or not, checked against `lore.lock`.
+    pub fn packages(&self) -> Result<Vec<Manifest>, StoreError> {
+        let mut packages = self.vendored_packages()?;
+
+        let path = self.root.join(LOCK_FILE);
+        let locked = match std::fs::read_to_string(&path) {
+            Ok(toml) => Lock::from_toml(&toml).map_err(|reason| StoreError::InvalidLock {
+                path: path.clone(),
+                reason,
+            })?,
+            Err(_) if self.dependencies.is_empty() => Lock::default(),
+            Err(_) => return Err(StoreError::LockNotFound { path }),
+        };
+        let dependencies = resolve_dependencies(self, Some(&locked))?;
+        let differences = locked.differences(&Lock::of(&dependencies));
+        if !differences.is_empty() {
+            return Err(StoreError::LockMismatch {
+                path,
+                differences: differences.join("; "),
+            });
+        }
+
+        packages.extend(dependencies.into_iter().map(|package| package.manifest));
+        Ok(packages)
+    }
+
+    /// Locks the dependencies as they are now, unpacking every archive again.
+    pub fn lock(&self) -> Result<Lock, StoreError> {
+        let lock = Lock::of(&resolve_dependencies(self, None)?);
+        self.write_lock(&lock)?;
+        Ok(lock)
+    }
+
+    fn write_lock(&self, lock: &Lock) -> Result<(), StoreError> {
+        let path = self.root.join(LOCK_FILE);
+        std::fs::write(&path, lock.to_toml()).map_err(|e| StoreError::InvalidLock {
+            path,
+            reason: e.to_string(),
+        })
+    }
+
+    /// The packages in the packages directory, each a directory with its own manifest.
+    fn vendored_packages(&self) -> Result<Vec<Manifest>, StoreError> {
+        let dir = self.root.join(PACKAGES_DIR);
+        let mut packages = vec![];
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(packages),
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            let manifest = path.join(MANIFEST_FILE);
+            if manifest.is_file() {
+                packages.push(Manifest::read(&manifest)?);
+            }
+        }
+        Ok(packages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    #[test]
+    fn manifest_from_toml() {
+        let manifest = Manifest::from_toml(
+            Path::new("/dota"),
+            r#"
+[project]
+name = "dota2"
+namespace = "dota:v2022"
+sources = ["ontology", "heroes.lore"]
+
+[codegen.ocaml]
+output-dir = "gen/ml"
+json = true
+strip-namespace = true
+
+[codegen.markdown]
+lang = ["es", "en"]
+"#,
+        )
+        .unwrap();
+        assert_debug_snapshot!(manifest);
+    }
+
+    #[test]
+    fn manifest_dependencies() {
+        let manifest = Manifest::from_toml(
+            Path::new("/dota"),
+            r#"
+[project]
+version = "0.3.0"
+
+[dependencies]
+names = { path = "../names", version = "1.2.0" }
+items = { archive = "vendor/items-2.0.0.tar.gz" }
+"#,
+        )
+        .unwrap();
+        assert_debug_snapshot!(manifest.dependencies);
+
+        let error = Manifest::from_toml(Path::new("/dota"), "[dependencies]\nnames = {}\n");
+        assert_eq!(
+            error,
+            Err("dependency `names` needs either a `path` or an `archive`".to_string())
+        );
+    }
+
+    #[test]
+    fn manifest_unknown_key() {
+        let error = Manifest::from_toml(
+            Path::new("/dota"),
+            r#"
+[codegen.sql]
+dialect = "sqlite"
+"#,
+        )
+        .unwrap_err();
+        assert_snapshot!(error);
+    }
+
+    #[test]
+    fn manifest_found_upward() {
+        let dir = std::env::temp_dir().join(format!("lore-manifest-{}", std::process::id()));
+        let nested = dir.join("ontology").join("heroes");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            dir.join(MANIFEST_FILE),
+            "[project]\nsources = [\"ontology\"]\n",
+        )
+        .unwrap();
+        std::fs::write(nested.join("hero.lore"), "kind Hero\n").unwrap();
+        std::fs::write(nested.join("notes.txt"), "not lore\n").unwrap();
+
+        let manifest = Manifest::find(&nested).unwrap().unwrap();
+        assert_eq!(manifest.root, dir);
+        assert_eq!(manifest.files(), vec![nested.join("hero.lore")]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
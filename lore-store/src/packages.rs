@@ -0,0 +1,550 @@
+use crate::manifest::*;
+use crate::store::StoreError;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const LOCK_FILE: &str = "lore.lock";
+
+/// Where archives are unpacked, under the project root.
+const ARCHIVES_DIR: &str = ".lore/archives";
+
+/// A dependency that was found and read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub source: DependencySource,
+    pub manifest: Manifest,
+    /// The hash of its manifest and `.lore` files, by their paths within the package, so the same
+    /// package hashes the same from a directory or from an archive.
+    pub hash: String,
+}
+
+/// `lore.lock`: the exact packages a project was last built with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lock {
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub source: String,
+    pub hash: String,
+}
+
+/// Finds every package the manifest depends on, directly or through other packages. Archives are
+/// only unpacked when locking, with no `locked` to check against; otherwise they must already be
+/// unpacked with the hash `locked` has for them, so reading a project never changes it.
+pub fn resolve_dependencies(
+    manifest: &Manifest,
+    locked: Option<&Lock>,
+) -> Result<Vec<Package>, StoreError> {
+    let mut packages: Vec<Package> = vec![];
+    let mut pending: Vec<(PathBuf, Dependency)> = manifest
+        .dependencies
+        .iter()
+        .rev()
+        .map(|dependency| (manifest.root.clone(), dependency.clone()))
+        .collect();
+
+    while let Some((root, dependency)) = pending.pop() {
+        let invalid = |reason: String| StoreError::InvalidDependency {
+            name: dependency.name.clone(),
+            reason,
+        };
+
+        let dir = match &dependency.source {
+            DependencySource::Path(path) => root.join(path),
+            DependencySource::Archive(archive) => {
+                let version = dependency.version.as_deref().unwrap_or("unversioned");
+                let into = manifest
+                    .root
+                    .join(ARCHIVES_DIR)
+                    .join(format!("{}-{}", dependency.name, version));
+                let source = dependency.source.to_string();
+                match locked {
+                    None => unpack(&root.join(archive), &into).map_err(invalid)?,
+                    Some(lock) => lock
+                        .packages
+                        .iter()
+                        .find(|locked| locked.name == dependency.name && locked.source == source)
+                        .and_then(|locked| unpacked(&into, &locked.hash))
+                        .ok_or_else(|| StoreError::LockMismatch {
+                            path: manifest.root.join(LOCK_FILE),
+                            differences: format!(
+                                "`{}` is not unpacked in {} as locked",
+                                dependency.name,
+                                into.display()
+                            ),
+                        })?,
+                }
+            }
+        };
+        if !dir.join(MANIFEST_FILE).is_file() {
+            return Err(invalid(format!(
+                "there is no {} in {}",
+                MANIFEST_FILE,
+                dir.display()
+            )));
+        }
+        let package = Manifest::read(&dir.join(MANIFEST_FILE))?;
+
+        let found = package.version.as_deref().unwrap_or("unversioned");
+        if let Some(version) = &dependency.version {
+            if package.version.as_ref() != Some(version) {
+                return Err(invalid(format!(
+                    "version {} is wanted but {} has version {}",
+                    version,
+                    dir.display(),
+                    found
+                )));
+            }
+        }
+        if let Some(existing) = packages.iter().find(|p| p.name == dependency.name) {
+            if existing.manifest.version != package.version {
+                return Err(invalid(format!(
+                    "both version {} and version {} are needed",
+                    existing
+                        .manifest
+                        .version
+                        .as_deref()
+                        .unwrap_or("unversioned"),
+                    found
+                )));
+            }
+            continue;
+        }
+
+        pending.extend(
+            package
+                .dependencies
+                .iter()
+                .rev()
+                .map(|dependency| (package.root.clone(), dependency.clone())),
+        );
+        packages.push(Package {
+            name: dependency.name.clone(),
+            source: dependency.source.clone(),
+            hash: hash(&package).map_err(invalid)?,
+            manifest: package,
+        });
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+/// Unpacks a `.tar` or `.tar.gz` into `dir`, and returns the directory the package is in.
+fn unpack(archive: &Path, dir: &Path) -> Result<PathBuf, String> {
+    let file = std::fs::File::open(archive)
+        .map_err(|e| format!("could not read {}: {}", archive.display(), e))?;
+    // start over, so nothing is left of another version
+    if dir.exists() {
+        std::fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let name = archive.to_string_lossy();
+    let unpacked = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(dir)
+    } else {
+        tar::Archive::new(file).unpack(dir)
+    };
+    unpacked.map_err(|e| format!("could not unpack {}: {}", archive.display(), e))?;
+    package_dir(dir)
+}
+
+/// The directory an archive was unpacked into, if what is there still hashes to `locked`.
+fn unpacked(dir: &Path, locked: &str) -> Option<PathBuf> {
+    let dir = package_dir(dir).ok()?;
+    let package = Manifest::read(&dir.join(MANIFEST_FILE)).ok()?;
+    if hash(&package).ok()? == locked {
+        Some(dir)
+    } else {
+        None
+    }
+}
+
+/// The directory in `dir` that an archive unpacked its package into.
+fn package_dir(dir: &Path) -> Result<PathBuf, String> {
+    // archives are often made of a directory, with the package inside it
+    if !dir.join(MANIFEST_FILE).is_file() {
+        let entries: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        if let [only] = entries.as_slice() {
+            if only.is_dir() {
+                return Ok(only.clone());
+            }
+        }
+    }
+    Ok(dir.to_path_buf())
+}
+
+fn hash(package: &Manifest) -> Result<String, String> {
+    let mut files: Vec<(String, PathBuf)> = package
+        .files()
+        .into_iter()
+        .chain(std::iter::once(package.root.join(MANIFEST_FILE)))
+        .map(|file| {
+            let name = file
+                .strip_prefix(&package.root)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (name, file)
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let mut hasher = Sha256::new();
+    for (name, file) in files {
+        let contents = std::fs::read(&file)
+            .map_err(|e| format!("could not read {}: {}", file.display(), e))?;
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(&contents);
+        hasher.update([0]);
+    }
+    Ok(format!("sha256:{:x}", hasher.finalize()))
+}
+
+fn quoted(s: &str) -> String {
+    toml::Value::String(s.to_string()).to_string()
+}
+
+impl Lock {
+    pub fn of(packages: &[Package]) -> Lock {
+        Lock {
+            packages: packages
+                .iter()
+                .map(|package| LockedPackage {
+                    name: package.name.clone(),
+                    version: package.manifest.version.clone(),
+                    source: package.source.to_string(),
+                    hash: package.hash.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Lock, String> {
+        let value: toml::Value = toml.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        let packages = match value.get("package") {
+            None => return Ok(Lock::default()),
+            Some(toml::Value::Array(packages)) => packages,
+            Some(_) => return Err("`package` must be a list of tables".to_string()),
+        };
+
+        let mut lock = Lock::default();
+        for package in packages {
+            let string = |key: &str| -> Result<Option<String>, String> {
+                match package.get(key) {
+                    None => Ok(None),
+                    Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+                    Some(_) => Err(format!("`{}` must be a string", key)),
+                }
+            };
+            let required = |key: &str| -> Result<String, String> {
+                string(key)?.ok_or_else(|| format!("every package needs a `{}`", key))
+            };
+            lock.packages.push(LockedPackage {
+                name: required("name")?,
+                version: string("version")?,
+                source: required("source")?,
+                hash: required("hash")?,
+            });
+        }
+        Ok(lock)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = "# Written by lore. Run `lore lock` to update it.\n".to_string();
+        for package in &self.packages {
+            out.push_str("\n[[package]]\n");
+            out.push_str(&format!("name = {}\n", quoted(&package.name)));
+            if let Some(version) = &package.version {
+                out.push_str(&format!("version = {}\n", quoted(version)));
+            }
+            out.push_str(&format!("source = {}\n", quoted(&package.source)));
+            out.push_str(&format!("hash = {}\n", quoted(&package.hash)));
+        }
+        out
+    }
+
+    /// What is different in `found` from what was locked.
+    pub fn differences(&self, found: &Lock) -> Vec<String> {
+        let mut differences = vec![];
+        for package in &found.packages {
+            match self.packages.iter().find(|p| p.name == package.name) {
+                None => differences.push(format!("`{}` is not locked", package.name)),
+                Some(locked) if locked.version != package.version => differences.push(format!(
+                    "`{}` was locked at version {} but is now version {}",
+                    package.name,
+                    locked.version.as_deref().unwrap_or("unversioned"),
+                    package.version.as_deref().unwrap_or("unversioned")
+                )),
+                Some(locked) if locked.source != package.source => differences.push(format!(
+                    "`{}` was locked from {} but now comes from {}",
+                    package.name, locked.source, package.source
+                )),
+                Some(locked) if locked.hash != package.hash => differences.push(format!(
+                    "the contents of `{}` changed since it was locked",
+                    package.name
+                )),
+                Some(_) => (),
+            }
+        }
+        for locked in &self.packages {
+            if !found.packages.iter().any(|p| p.name == locked.name) {
+                differences.push(format!("`{}` is locked but no longer needed", locked.name));
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    /// Writes `files` into a fresh directory under the temp dir.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("lore_packages_{}_{}", name, std::process::id()));
+        for (file, src) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        }
+        dir
+    }
+
+    fn archive(dir: &Path, into: &Path) {
+        let file = std::fs::File::create(into).unwrap();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        ));
+        builder.append_dir_all("items-2.0.0", dir).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn resolve_path_and_archive_dependencies() {
+        let dir = project(
+            "resolve",
+            &[
+                (
+                    "app/lore.toml",
+                    r#"
+[dependencies]
+names = { path = "../names", version = "1.0.0" }
+items = { archive = "vendor/items-2.0.0.tar.gz", version = "2.0.0" }
+"#,
+                ),
+                (
+                    "names/lore.toml",
+                    r#"
+[project]
+version = "1.0.0"
+namespace = "names:v1"
+
+[dependencies]
+base = { path = "../base" }
+"#,
+                ),
+                ("names/name.lore", "attr Name\n"),
+                ("base/lore.toml", "[project]\nnamespace = \"base:v1\"\n"),
+                (
+                    "items/lore.toml",
+                    "[project]\nversion = \"2.0.0\"\nnamespace = \"items:v2\"\n",
+                ),
+                ("items/item.lore", "kind Item\n"),
+            ],
+        );
+        std::fs::create_dir_all(dir.join("app/vendor")).unwrap();
+        archive(
+            &dir.join("items"),
+            &dir.join("app/vendor/items-2.0.0.tar.gz"),
+        );
+
+        let manifest = Manifest::read(&dir.join("app/lore.toml")).unwrap();
+        let packages = resolve_dependencies(&manifest, None).unwrap();
+        let items = hash(&Manifest::read(&dir.join("items/lore.toml")).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let found: Vec<(String, String, String)> = packages
+            .iter()
+            .map(|p| {
+                (
+                    p.name.clone(),
+                    p.manifest.namespace.clone().unwrap_or_default().to_string(),
+                    p.source.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "base".to_string(),
+                    "base:v1".to_string(),
+                    "path+../base".to_string()
+                ),
+                (
+                    "items".to_string(),
+                    "items:v2".to_string(),
+                    "archive+vendor/items-2.0.0.tar.gz".to_string()
+                ),
+                (
+                    "names".to_string(),
+                    "names:v1".to_string(),
+                    "path+../names".to_string()
+                ),
+            ]
+        );
+        // unpacked or not, a package hashes the same
+        assert_eq!(packages[1].hash, items);
+    }
+
+    #[test]
+    fn resolve_wrong_version() {
+        let dir = project(
+            "wrong_version",
+            &[
+                (
+                    "app/lore.toml",
+                    "[dependencies]\nnames = { path = \"../names\", version = \"2.0.0\" }\n",
+                ),
+                ("names/lore.toml", "[project]\nversion = \"1.0.0\"\n"),
+            ],
+        );
+        let manifest = Manifest::read(&dir.join("app/lore.toml")).unwrap();
+        let error = resolve_dependencies(&manifest, None).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            error,
+            StoreError::InvalidDependency { name, .. } if name == "names"
+        ));
+    }
+
+    #[test]
+    fn resolve_only_unpacks_when_locking() {
+        let dir = project(
+            "reuse",
+            &[
+                (
+                    "app/lore.toml",
+                    "[dependencies]\nitems = { archive = \"items-2.0.0.tar.gz\", version = \"2.0.0\" }\n",
+                ),
+                ("items/lore.toml", "[project]\nversion = \"2.0.0\"\n"),
+                ("items/item.lore", "kind Item\n"),
+            ],
+        );
+        archive(&dir.join("items"), &dir.join("app/items-2.0.0.tar.gz"));
+        let manifest = Manifest::read(&dir.join("app/lore.toml")).unwrap();
+        let lock = Lock::of(&resolve_dependencies(&manifest, None).unwrap());
+
+        // unpacking again would remove it
+        let unpacked = dir.join("app/.lore/archives/items-2.0.0/items-2.0.0");
+        std::fs::write(unpacked.join("marker"), "").unwrap();
+        let packages = resolve_dependencies(&manifest, Some(&lock)).unwrap();
+
+        std::fs::write(unpacked.join("item.lore"), "kind Shop\n").unwrap();
+        let changed = resolve_dependencies(&manifest, Some(&lock)).unwrap_err();
+        let kept = unpacked.join("marker").exists();
+
+        std::fs::remove_dir_all(dir.join("app/.lore")).unwrap();
+        let removed = resolve_dependencies(&manifest, Some(&lock)).unwrap_err();
+        let still_removed = !unpacked.exists();
+
+        resolve_dependencies(&manifest, None).unwrap();
+        let relocked = unpacked.join("item.lore").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(lock.differences(&Lock::of(&packages)).is_empty());
+        assert!(matches!(changed, StoreError::LockMismatch { .. }));
+        assert!(kept);
+        assert!(matches!(removed, StoreError::LockMismatch { .. }));
+        assert!(still_removed);
+        assert!(relocked);
+    }
+
+    #[test]
+    fn packages_need_a_lock() {
+        let dir = project(
+            "unlocked",
+            &[
+                (
+                    "app/lore.toml",
+                    "[dependencies]\nnames = { path = \"../names\" }\n",
+                ),
+                ("names/lore.toml", "[project]\nnamespace = \"names:v1\"\n"),
+            ],
+        );
+        let manifest = Manifest::read(&dir.join("app/lore.toml")).unwrap();
+        let unlocked = manifest.packages().unwrap_err();
+        let written = dir.join("app").join(LOCK_FILE).exists();
+        manifest.lock().unwrap();
+        let packages = manifest.packages().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(unlocked, StoreError::LockNotFound { .. }));
+        assert!(!written);
+        assert_eq!(packages.len(), 1);
+    }
+
+    #[test]
+    fn lock_round_trip() {
+        let lock = Lock {
+            packages: vec![
+                LockedPackage {
+                    name: "items".to_string(),
+                    version: Some("2.0.0".to_string()),
+                    source: "archive+vendor/items-2.0.0.tar.gz".to_string(),
+                    hash: "sha256:abc".to_string(),
+                },
+                LockedPackage {
+                    name: "names".to_string(),
+                    version: None,
+                    source: "path+../names".to_string(),
+                    hash: "sha256:def".to_string(),
+                },
+            ],
+        };
+        let toml = lock.to_toml();
+        assert_eq!(Lock::from_toml(&toml).unwrap(), lock);
+        assert_snapshot!(toml);
+    }
+
+    #[test]
+    fn lock_differences() {
+        let locked = |name: &str, version: &str, hash: &str| LockedPackage {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            source: format!("path+../{}", name),
+            hash: hash.to_string(),
+        };
+        let before = Lock {
+            packages: vec![
+                locked("heroes", "1.0.0", "sha256:1"),
+                locked("items", "1.0.0", "sha256:2"),
+                locked("names", "1.0.0", "sha256:3"),
+            ],
+        };
+        let after = Lock {
+            packages: vec![
+                locked("abilities", "1.0.0", "sha256:0"),
+                locked("heroes", "1.1.0", "sha256:1"),
+                locked("items", "1.0.0", "sha256:4"),
+            ],
+        };
+        assert_debug_snapshot!(before.differences(&after));
+        assert!(after.differences(&after).is_empty());
+    }
+}
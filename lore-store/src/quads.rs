@@ -19,13 +19,24 @@ impl ToQuads for Kind {
         );
 
         let lore_is_kind = Quad::new(
-            this,
+            this.clone(),
             NamedNode::new("https://lore-lang.org/v1/type").unwrap(),
             NamedNode::new("https://lore-lang.org/v1/Kind").unwrap(),
             None,
         );
 
-        vec![owl_is_class, lore_is_kind]
+        let mut quads = vec![owl_is_class, lore_is_kind];
+
+        for superkind in self.superkinds() {
+            quads.push(Quad::new(
+                this.clone(),
+                NamedNode::new("http://www.w3.org/2000/01/rdf-schema#subClassOf").unwrap(),
+                NamedNode::new(superkind.to_string()).unwrap(),
+                None,
+            ));
+        }
+
+        quads
     }
 }
 
@@ -62,6 +73,24 @@ impl ToQuads for Relation {
             None,
         );
 
+        if let Some(primitive) = Primitive::from_uri(&self.object.uri) {
+            let owl_is_datatype_property = Quad::new(
+                this.clone(),
+                NamedNode::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#type").unwrap(),
+                NamedNode::new("http://www.w3.org/2002/07/owl#DatatypeProperty").unwrap(),
+                None,
+            );
+
+            let rdf_range = Quad::new(
+                this,
+                NamedNode::new("http://www.w3.org/2000/01/rdf-schema#range").unwrap(),
+                NamedNode::new(primitive.xsd_datatype()).unwrap(),
+                None,
+            );
+
+            return vec![rdf_domain, owl_is_datatype_property, rdf_range];
+        }
+
         let rdf_range = Quad::new(
             this,
             NamedNode::new("http://www.w3.org/2000/01/rdf-schema#range").unwrap(),
@@ -72,3 +101,85 @@ impl ToQuads for Relation {
         vec![rdf_domain, rdf_range]
     }
 }
+
+/// The quads for a thing. Values are typed by the primitive their relation points to, which
+/// `primitive_of` looks up by the field's name, or by how they were written when it has none.
+pub fn thing_quads(thing: &Thing, primitive_of: impl Fn(&URI) -> Option<Primitive>) -> Vec<Quad> {
+    let typed = |value: &str, primitive: Primitive| -> Term {
+        oxigraph::model::Literal::new_typed_literal(
+            value,
+            NamedNode::new(primitive.xsd_datatype()).unwrap(),
+        )
+        .into()
+    };
+    let this = NamedNode::new(thing.name.to_string()).unwrap();
+
+    let rdf_type = Quad::new(
+        this.clone(),
+        NamedNode::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#type").unwrap(),
+        NamedNode::new(thing.kind.to_string()).unwrap(),
+        None,
+    );
+
+    let lore_is_thing = Quad::new(
+        this.clone(),
+        NamedNode::new("https://lore-lang.org/v1/type").unwrap(),
+        NamedNode::new("https://lore-lang.org/v1/Thing").unwrap(),
+        None,
+    );
+
+    let mut quads = vec![rdf_type, lore_is_thing];
+
+    for field in &thing.fields {
+        // labels and docs become the usual RDF Schema annotations, tagged with their language
+        let localized = [
+            (
+                Localized::Label,
+                "http://www.w3.org/2000/01/rdf-schema#label",
+            ),
+            (
+                Localized::Doc,
+                "http://www.w3.org/2000/01/rdf-schema#comment",
+            ),
+        ]
+        .iter()
+        .find_map(|(localized, predicate)| {
+            localized.language_of(field).map(|lang| (*predicate, lang))
+        });
+
+        let field_name = || NamedNode::new(field.name.to_string()).unwrap();
+        let (predicate, object): (NamedNode, Term) =
+            match (&field.value, localized, primitive_of(&field.name.uri)) {
+                (lore_ast::Literal::String(text), Some((predicate, lang)), _) => {
+                    let literal = if lang.is_empty() {
+                        oxigraph::model::Literal::new_simple_literal(text)
+                    } else {
+                        oxigraph::model::Literal::new_language_tagged_literal(text, lang).unwrap()
+                    };
+                    (NamedNode::new(predicate).unwrap(), literal.into())
+                }
+                (lore_ast::Literal::String(text), None, Some(primitive)) => {
+                    (field_name(), typed(text, primitive))
+                }
+                (lore_ast::Literal::String(text), None, None) => (
+                    field_name(),
+                    oxigraph::model::Literal::new_simple_literal(text).into(),
+                ),
+                (lore_ast::Literal::Number(n), _, Some(Primitive::Float)) => {
+                    (field_name(), typed(&n.to_string(), Primitive::Float))
+                }
+                (lore_ast::Literal::Number(n), _, _) => {
+                    (field_name(), typed(&n.to_string(), Primitive::Int))
+                }
+                (lore_ast::Literal::Float(f), _, _) => (field_name(), typed(f, Primitive::Float)),
+                (lore_ast::Literal::Name(name), _, _) => (
+                    field_name(),
+                    NamedNode::new(name.to_string()).unwrap().into(),
+                ),
+            };
+
+        quads.push(Quad::new(this.clone(), predicate, object, None));
+    }
+
+    quads
+}
@@ -0,0 +1,457 @@
+use crate::store::*;
+use lore_ast::*;
+use oxigraph::model::*;
+use std::collections::HashSet;
+use std::path::Path;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDFS_SUBCLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+
+/// What the values of a property must be.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueConstraint {
+    /// `sh:class`: instances of a kind.
+    Class(URI),
+    /// `sh:datatype`: literals of a primitive's XML Schema datatype.
+    Datatype(Primitive),
+    /// `sh:in`: one of the variants of an enumeration.
+    In(Vec<URI>),
+    /// Attributes say nothing about how their values are written down.
+    Any,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyShape {
+    pub path: URI,
+    pub value: ValueConstraint,
+    pub cardinality: Cardinality,
+}
+
+/// The shape of every instance of one kind, named `<kind>#shape`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeShape {
+    pub target_class: URI,
+    pub properties: Vec<PropertyShape>,
+}
+
+/// SHACL shapes derived from the kinds of a store and the relations they are the subject of.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shapes {
+    pub shapes: Vec<NodeShape>,
+}
+
+/// A single result of validating a data graph, named after the SHACL constraint component that
+/// was not satisfied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub focus_node: String,
+    pub path: URI,
+    pub component: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} <{}>: {} ({})",
+            self.focus_node, self.path, self.message, self.component
+        )
+    }
+}
+
+impl Store {
+    pub fn shapes(&self) -> Shapes {
+        let shapes = self
+            .kinds()
+            .iter()
+            // variants are named individuals, never instances of their enumeration
+            .filter(|kind| !kind.is_enumeration())
+            .map(|kind| {
+                let properties = self
+                    .relations_by_subject
+                    .get(&kind.name.to_uri())
+                    .map(|rels| rels.iter().map(|rel| self.property_shape(rel)).collect())
+                    .unwrap_or_default();
+                NodeShape {
+                    target_class: kind.name.to_uri(),
+                    properties,
+                }
+            })
+            .collect();
+        Shapes { shapes }
+    }
+
+    fn property_shape(&self, rel: &Relation) -> PropertyShape {
+        let object = rel.object.to_uri();
+        let value = if let Some(primitive) = self.primitive(&object) {
+            ValueConstraint::Datatype(primitive)
+        } else if let Some(kind) = self.kinds.get(&object) {
+            if kind.is_enumeration() {
+                ValueConstraint::In(kind.variants().iter().map(|v| v.to_uri()).collect())
+            } else {
+                ValueConstraint::Class(object)
+            }
+        } else {
+            ValueConstraint::Any
+        };
+        PropertyShape {
+            path: rel.predicate.to_uri(),
+            value,
+            cardinality: rel.cardinality(),
+        }
+    }
+
+    /// Reads a Turtle file into a graph that can be checked against the shapes of this store.
+    pub fn load_turtle(path: &Path) -> Result<oxigraph::MemoryStore, StoreError> {
+        let invalid = |reason: String| StoreError::InvalidData {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let file = std::fs::File::open(path).map_err(|e| invalid(e.to_string()))?;
+        let data = oxigraph::MemoryStore::new();
+        data.load_graph(
+            std::io::BufReader::new(file),
+            oxigraph::io::GraphFormat::Turtle,
+            &GraphName::DefaultGraph,
+            None,
+        )
+        .map_err(|e| invalid(e.to_string()))?;
+        Ok(data)
+    }
+}
+
+impl Shapes {
+    pub fn to_turtle(&self) -> String {
+        let mut out = String::new();
+        out.push_str("@prefix sh: <http://www.w3.org/ns/shacl#> .\n");
+        out.push_str("@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .\n");
+
+        for shape in &self.shapes {
+            let mut statements = vec![
+                "a sh:NodeShape".to_string(),
+                format!("sh:targetClass <{}>", shape.target_class),
+            ];
+            for property in &shape.properties {
+                let mut constraints = vec![format!("sh:path <{}>", property.path)];
+                match &property.value {
+                    ValueConstraint::Class(class) => {
+                        constraints.push(format!("sh:class <{}>", class))
+                    }
+                    ValueConstraint::Datatype(primitive) => constraints.push(format!(
+                        "sh:datatype xsd:{}",
+                        primitive.xsd_datatype().rsplit('#').next().unwrap()
+                    )),
+                    ValueConstraint::In(variants) => {
+                        let variants: Vec<String> =
+                            variants.iter().map(|v| format!("<{}>", v)).collect();
+                        constraints.push(format!("sh:in ( {} )", variants.join(" ")))
+                    }
+                    ValueConstraint::Any => (),
+                }
+                if property.cardinality.min > 0 {
+                    constraints.push(format!("sh:minCount {}", property.cardinality.min));
+                }
+                if let Some(max) = property.cardinality.max {
+                    constraints.push(format!("sh:maxCount {}", max));
+                }
+                statements.push(format!(
+                    "sh:property [\n    {}\n  ]",
+                    constraints.join(" ;\n    ")
+                ));
+            }
+            out.push_str(&format!(
+                "\n<{}#shape>\n  {} .\n",
+                shape.target_class,
+                statements.join(" ;\n  ")
+            ));
+        }
+
+        out
+    }
+
+    /// Checks every instance of every target class in `data`. Instances of subclasses declared
+    /// with `rdfs:subClassOf` in the data are targeted as well.
+    pub fn validate(&self, data: &oxigraph::MemoryStore) -> Vec<Violation> {
+        let mut violations = vec![];
+        for shape in &self.shapes {
+            for focus in instances_of(data, &shape.target_class) {
+                for property in &shape.properties {
+                    check_property(data, &focus, property, &mut violations);
+                }
+            }
+        }
+        violations
+    }
+}
+
+fn named(uri: &str) -> NamedNode {
+    NamedNode::new_unchecked(uri.to_string())
+}
+
+/// A class and everything declared a subclass of it, directly or not.
+fn subclasses(data: &oxigraph::MemoryStore, class: &URI) -> HashSet<NamedOrBlankNode> {
+    let mut classes = HashSet::new();
+    let mut pending: Vec<NamedOrBlankNode> = vec![named(&class.to_string()).into()];
+    while let Some(class) = pending.pop() {
+        if !classes.insert(class.clone()) {
+            continue;
+        }
+        let term: Term = class.into();
+        for quad in data.quads_for_pattern(
+            None,
+            Some(named(RDFS_SUBCLASS_OF).as_ref()),
+            Some(term.as_ref()),
+            Some(GraphNameRef::DefaultGraph),
+        ) {
+            pending.push(quad.subject);
+        }
+    }
+    classes
+}
+
+/// Every instance of a class, sorted so violations are always reported in the same order.
+fn instances_of(data: &oxigraph::MemoryStore, class: &URI) -> Vec<NamedOrBlankNode> {
+    let mut instances = HashSet::new();
+    for class in subclasses(data, class) {
+        let term: Term = class.into();
+        for quad in data.quads_for_pattern(
+            None,
+            Some(named(RDF_TYPE).as_ref()),
+            Some(term.as_ref()),
+            Some(GraphNameRef::DefaultGraph),
+        ) {
+            instances.insert(quad.subject);
+        }
+    }
+    let mut instances: Vec<NamedOrBlankNode> = instances.into_iter().collect();
+    instances.sort_by_key(|instance| instance.to_string());
+    instances
+}
+
+fn has_class(data: &oxigraph::MemoryStore, value: &Term, class: &URI) -> bool {
+    let node = match value {
+        Term::NamedNode(node) => NamedOrBlankNode::from(node.clone()),
+        Term::BlankNode(node) => NamedOrBlankNode::from(node.clone()),
+        Term::Literal(_) => return false,
+    };
+    let classes = subclasses(data, class);
+    data.quads_for_pattern(
+        Some(node.as_ref()),
+        Some(named(RDF_TYPE).as_ref()),
+        None,
+        Some(GraphNameRef::DefaultGraph),
+    )
+    .any(|quad| match quad.object {
+        Term::NamedNode(class) => classes.contains(&class.into()),
+        Term::BlankNode(class) => classes.contains(&class.into()),
+        Term::Literal(_) => false,
+    })
+}
+
+fn check_property(
+    data: &oxigraph::MemoryStore,
+    focus: &NamedOrBlankNode,
+    property: &PropertyShape,
+    violations: &mut Vec<Violation>,
+) {
+    let mut values: Vec<Term> = data
+        .quads_for_pattern(
+            Some(focus.as_ref()),
+            Some(named(&property.path.to_string()).as_ref()),
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| quad.object)
+        .collect();
+    values.sort_by_key(|value| value.to_string());
+
+    let mut violation = |component: &'static str, message: String| {
+        violations.push(Violation {
+            focus_node: focus.to_string(),
+            path: property.path.clone(),
+            component,
+            message,
+        })
+    };
+
+    let count = values.len() as u64;
+    if count < property.cardinality.min {
+        violation(
+            "sh:MinCountConstraintComponent",
+            format!(
+                "expected at least {} value(s), found {}",
+                property.cardinality.min, count
+            ),
+        );
+    }
+    if let Some(max) = property.cardinality.max {
+        if count > max {
+            violation(
+                "sh:MaxCountConstraintComponent",
+                format!("expected at most {} value(s), found {}", max, count),
+            );
+        }
+    }
+
+    for value in &values {
+        match &property.value {
+            ValueConstraint::Class(class) => {
+                if !has_class(data, value, class) {
+                    violation(
+                        "sh:ClassConstraintComponent",
+                        format!("{} is not an instance of <{}>", value, class),
+                    );
+                }
+            }
+            ValueConstraint::Datatype(primitive) => {
+                let matches = match value {
+                    Term::Literal(literal) => {
+                        literal.datatype().as_str() == primitive.xsd_datatype()
+                    }
+                    _ => false,
+                };
+                if !matches {
+                    violation(
+                        "sh:DatatypeConstraintComponent",
+                        format!(
+                            "{} is not a literal of datatype <{}>",
+                            value,
+                            primitive.xsd_datatype()
+                        ),
+                    );
+                }
+            }
+            ValueConstraint::In(variants) => {
+                let matches = match value {
+                    Term::NamedNode(node) => {
+                        variants.iter().any(|v| v.to_string() == node.as_str())
+                    }
+                    _ => false,
+                };
+                if !matches {
+                    let variants: Vec<String> =
+                        variants.iter().map(|v| format!("<{}>", v)).collect();
+                    violation(
+                        "sh:InConstraintComponent",
+                        format!("{} is not one of {}", value, variants.join(", ")),
+                    );
+                }
+            }
+            ValueConstraint::Any => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::*;
+
+    const HEROES: &str = r#"
+prefix lore:v1 as @lore
+
+using dota:v2022
+
+kind Hero
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+}
+
+attr Name
+
+rel Hero mainName Name
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero movementSpeed @lore/Int
+
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max 6
+}
+
+rel Hero alias @lore/String {
+  @lore/cardinality/max 1
+}
+"#;
+
+    fn data(turtle: &str) -> oxigraph::MemoryStore {
+        let data = oxigraph::MemoryStore::new();
+        data.load_graph(
+            turtle.as_bytes(),
+            oxigraph::io::GraphFormat::Turtle,
+            &GraphName::DefaultGraph,
+            None,
+        )
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn shapes_to_turtle() {
+        let mut store = Store::new();
+        store.add_from_string(HEROES).unwrap();
+        let turtle = store.shapes().to_turtle();
+
+        // the output is valid Turtle
+        data(&turtle);
+
+        assert_snapshot!(turtle);
+    }
+
+    #[test]
+    fn validate_conforming_data() {
+        let mut store = Store::new();
+        store.add_from_string(HEROES).unwrap();
+
+        let data = data(
+            r#"
+@prefix d: <dota:v2022/> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+d:Carry rdfs:subClassOf d:Hero .
+
+d:AntiMage a d:Carry ;
+  d:mainName "Anti-Mage" ;
+  d:primaryAttribute d:Agility ;
+  d:movementSpeed 310 ;
+  d:hasAbility d:ManaBreak, d:Blink .
+
+d:ManaBreak a d:Ability .
+d:Blink a d:Ability .
+"#,
+        );
+
+        assert_eq!(store.shapes().validate(&data), vec![]);
+    }
+
+    #[test]
+    fn validate_violations() {
+        let mut store = Store::new();
+        store.add_from_string(HEROES).unwrap();
+
+        let data = data(
+            r#"
+@prefix d: <dota:v2022/> .
+
+d:Pudge a d:Hero ;
+  d:primaryAttribute d:Charisma ;
+  d:movementSpeed "280" ;
+  d:hasAbility d:Hook ;
+  d:alias "Butcher", "Pudge" .
+
+d:Hook a d:Item .
+"#,
+        );
+
+        let violations: Vec<String> = store
+            .shapes()
+            .validate(&data)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_snapshot!(violations.join("\n"));
+    }
+}
@@ -1,21 +1,109 @@
-use crate::quads::ToQuads;
+use crate::manifest::Manifest;
+use crate::quads::{thing_quads, ToQuads};
+use crate::things::ThingError;
 use lore_ast::*;
 use miette::Diagnostic;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Diagnostic, Error, Debug)]
 #[diagnostic(code(lore::store), url(docsrs))]
 pub enum StoreError {
+    #[error(transparent)]
+    FileError(#[from] lore_parser::FileError),
+
     #[error(transparent)]
     ParseError(#[from] lore_parser::ParseError),
 
     #[error(transparent)]
+    #[diagnostic(transparent)]
     ValidationError(#[from] lore_parser::ValidationError),
 
     #[error(transparent)]
     QueryError(#[from] oxigraph::sparql::EvaluationError),
 
+    #[error("Could not read RDF data from {path}: {reason}")]
+    InvalidData { path: PathBuf, reason: String },
+
+    #[error("{path} does not conform to the shapes: {violations} violation(s)")]
+    NonConforming { path: PathBuf, violations: usize },
+
+    #[error("Invalid mapping: {reason}")]
+    InvalidMapping { reason: String },
+
+    #[error("Invalid manifest {path}: {reason}")]
+    #[diagnostic(code(lore::store::manifest))]
+    InvalidManifest { path: PathBuf, reason: String },
+
+    #[error("Import cycle: {cycle}")]
+    #[diagnostic(
+        code(lore::store::imports),
+        help("Move what the files need from each other into a file they can both import.")
+    )]
+    ImportCycle { cycle: String },
+
+    #[error("Could not find a package for `{namespace}`, imported by {path}")]
+    #[diagnostic(
+        code(lore::store::imports),
+        help("Packages are read from lore_packages/, one directory with a lore.toml each.")
+    )]
+    PackageNotFound { namespace: URI, path: PathBuf },
+
+    #[error("Could not read {import}, imported by {path}: {reason}")]
+    #[diagnostic(
+        code(lore::store::imports),
+        help("Imported files are found relative to the file importing them.")
+    )]
+    ImportNotFound {
+        import: PathBuf,
+        path: PathBuf,
+        reason: String,
+    },
+
+    #[error("Invalid dependency `{name}`: {reason}")]
+    #[diagnostic(code(lore::store::dependencies))]
+    InvalidDependency { name: String, reason: String },
+
+    #[error("Invalid lockfile {path}: {reason}")]
+    #[diagnostic(code(lore::store::lock))]
+    InvalidLock { path: PathBuf, reason: String },
+
+    #[error("The dependencies are not locked: {path} does not exist")]
+    #[diagnostic(
+        code(lore::store::lock),
+        help("Run `lore lock` to resolve the dependencies and lock them.")
+    )]
+    LockNotFound { path: PathBuf },
+
+    #[error("{path} is out of date: {differences}")]
+    #[diagnostic(
+        code(lore::store::lock),
+        help("Run `lore lock` if these changes are expected.")
+    )]
+    LockMismatch { path: PathBuf, differences: String },
+
+    #[error("Could not find a lore.toml in {dir} or any directory above it")]
+    #[diagnostic(
+        code(lore::store::manifest),
+        help("Pass the .lore files to read, or create a lore.toml for the project.")
+    )]
+    ManifestNotFound { dir: PathBuf },
+
+    #[error("{count} breaking change(s)")]
+    #[diagnostic(
+        code(lore::store::diff),
+        help("Code generated from the old ontology may stop compiling or reject new data.")
+    )]
+    BreakingChanges { count: usize },
+
+    #[error("Some things do not match the ontology")]
+    #[diagnostic(code(lore::store::things))]
+    InvalidThings {
+        #[related]
+        errors: Vec<ThingError>,
+    },
+
     #[error("Runtime error")]
     Runtime(String),
 }
@@ -29,6 +117,32 @@ pub struct Store {
     pub attributes: HashMap<URI, Attribute>,
 
     pub kinds: HashMap<URI, Kind>,
+
+    pub things: HashMap<URI, Thing>,
+
+    /// The file every kind, attribute, and thing was declared in, when it was read from one.
+    pub declared_in: HashMap<URI, PathBuf>,
+
+    /// The contents of every file or string read, to point diagnostics about things at.
+    pub sources: HashMap<PathBuf, String>,
+
+    /// The namespace for files without a `using` line.
+    pub namespace: Option<URI>,
+
+    /// The packages `import <namespace>` can read.
+    pub packages: Vec<Manifest>,
+
+    /// The names every file read declares, by canonical path, so each file is read only once.
+    loaded: HashMap<PathBuf, HashSet<URI>>,
+
+    /// Every file an `import` pointed to, even those that failed to read.
+    imported: BTreeSet<PathBuf>,
+
+    /// When every name was first declared, counting from the first one the store read.
+    declared: HashMap<URI, usize>,
+
+    /// Called with the AST of every file read, once it is validated.
+    inspector: Option<fn(&Path, &lore_ast::Structure)>,
 }
 
 impl std::fmt::Debug for Store {
@@ -51,6 +165,14 @@ impl std::fmt::Debug for Store {
         }
         writeln!(f, "  }}")?;
 
+        writeln!(f, "  things: {{")?;
+        let mut things: Vec<Thing> = self.things.values().cloned().collect();
+        things.sort();
+        for t in things {
+            writeln!(f, "    {:#?}", t)?;
+        }
+        writeln!(f, "  }}")?;
+
         writeln!(f, "  relations: {{")?;
         let mut relations: Vec<(URI, Vec<Relation>)> =
             self.relations_by_subject.clone().into_iter().collect();
@@ -69,15 +191,75 @@ impl Store {
         Store::default()
     }
 
+    pub fn with_namespace(mut self, namespace: URI) -> Store {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    pub fn with_packages(mut self, packages: Vec<Manifest>) -> Store {
+        self.packages = packages;
+        self
+    }
+
+    /// Shows `inspect` the validated AST of every file read from now on, imports included.
+    pub fn with_inspector(mut self, inspect: fn(&Path, &lore_ast::Structure)) -> Store {
+        self.inspector = Some(inspect);
+        self
+    }
+
+    /// Where a name comes among everything declared so far, if it was declared at all.
+    pub fn declaration_order(&self, uri: &URI) -> Option<usize> {
+        self.declared.get(uri).copied()
+    }
+
+    fn declare(&mut self, uri: URI) {
+        let next = self.declared.len();
+        self.declared.entry(uri).or_insert(next);
+    }
+
+    /// The files read from disk that declare `uri`, by canonical path.
+    pub fn files_declaring(&self, uri: &URI) -> Vec<&PathBuf> {
+        let mut files: Vec<&PathBuf> = self
+            .loaded
+            .iter()
+            .filter(|(_, names)| names.contains(uri))
+            .map(|(file, _)| file)
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// The files read because some other file imported them, so they can be watched too.
+    pub fn imported_files(&self) -> Vec<&PathBuf> {
+        self.imported.iter().collect()
+    }
+
     pub fn kinds(&self) -> Vec<&Kind> {
-        self.kinds.values().collect()
+        let mut kinds: Vec<&Kind> = self.kinds.values().collect();
+        kinds.sort();
+        kinds
+    }
+
+    /// The built-in primitive a relation points to, if any. Primitives are never declared in
+    /// the ontology, so they are not found among the kinds or attributes.
+    pub fn primitive(&self, uri: &URI) -> Option<Primitive> {
+        Primitive::from_uri(uri)
     }
 
     pub fn attributes(&self) -> Vec<&Attribute> {
-        self.attributes.values().collect()
+        let mut attributes: Vec<&Attribute> = self.attributes.values().collect();
+        attributes.sort();
+        attributes
+    }
+
+    pub fn things(&self) -> Vec<&Thing> {
+        let mut things: Vec<&Thing> = self.things.values().collect();
+        things.sort();
+        things
     }
 
     pub fn add_from_string(&mut self, src: &str) -> Result<&mut Store, StoreError> {
+        self.sources.insert(PathBuf::from("tmp"), src.to_string());
         let mut parser = lore_parser::Parser::for_string("tmp", src)?;
         let parsetree = parser.parse().map_err(StoreError::ParseError)?;
         let validator = lore_parser::Validator::new();
@@ -87,8 +269,183 @@ impl Store {
         self.add_tree(ast)
     }
 
+    pub fn add_file(&mut self, path: PathBuf) -> Result<&mut Store, StoreError> {
+        let (source, parsetree) = self.parse_file(&path)?;
+        self.add_parsed_file(path, source, parsetree)
+    }
+
+    /// Reads and parses a file without adding it, so the parse tree can be kept and added to more
+    /// than one store.
+    pub fn parse_file(&self, path: &Path) -> Result<(String, lore_parser::Structure), StoreError> {
+        let mut parser = lore_parser::Parser::for_file(path.to_path_buf())?;
+        let parsetree = parser.parse().map_err(StoreError::ParseError)?;
+        Ok((parser.source().to_string(), parsetree))
+    }
+
+    /// Adds a file read with `parse_file`, after everything it imports.
+    pub fn add_parsed_file(
+        &mut self,
+        path: PathBuf,
+        source: String,
+        parsetree: lore_parser::Structure,
+    ) -> Result<&mut Store, StoreError> {
+        let namespace = self.namespace.clone();
+        self.load(path, source, parsetree, namespace, &mut vec![])?;
+        Ok(self)
+    }
+
+    /// Adds a file after the files it imports, and returns the names it declares. `importing` are
+    /// the files waiting on this one, which tells a cycle apart from a file imported twice.
+    fn load(
+        &mut self,
+        path: PathBuf,
+        source: String,
+        parsetree: lore_parser::Structure,
+        namespace: Option<URI>,
+        importing: &mut Vec<PathBuf>,
+    ) -> Result<HashSet<URI>, StoreError> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(names) = self.loaded.get(&key) {
+            return Ok(names.clone());
+        }
+        if let Some(start) = importing.iter().position(|file| *file == key) {
+            let cycle: Vec<String> = importing[start..]
+                .iter()
+                .chain(std::iter::once(&key))
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(StoreError::ImportCycle {
+                cycle: cycle.join(" -> "),
+            });
+        }
+
+        importing.push(key.clone());
+        let imported = self.load_imported(&path, &parsetree, &namespace, importing)?;
+        importing.pop();
+
+        let mut validator = lore_parser::Validator::new();
+        if let Some(namespace) = namespace {
+            validator = validator.with_namespace(namespace);
+        }
+        validator = validator.with_imports(imported);
+        let ast = validator
+            .validate(parsetree)
+            .map_err(StoreError::ValidationError)?;
+        if let Some(inspect) = self.inspector {
+            inspect(&path, &ast);
+        }
+
+        let names: HashSet<URI> = ast
+            .kinds
+            .iter()
+            .map(|kind| kind.name.to_uri())
+            .chain(ast.attributes.iter().map(|attr| attr.name.to_uri()))
+            .chain(ast.things.iter().map(|thing| thing.name.to_uri()))
+            .chain(ast.relations.iter().map(|rel| rel.predicate.to_uri()))
+            .collect();
+        self.add_validated_file(path, source, ast)?;
+        self.loaded.insert(key, names.clone());
+        Ok(names)
+    }
+
+    /// Adds everything a file imports, without the file itself, and returns the names the imports
+    /// declare, so the file can be checked against them.
+    pub fn load_imports(
+        &mut self,
+        path: &Path,
+        parsetree: &lore_parser::Structure,
+    ) -> Result<HashSet<URI>, StoreError> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let namespace = self.namespace.clone();
+        self.load_imported(path, parsetree, &namespace, &mut vec![key])
+    }
+
+    fn load_imported(
+        &mut self,
+        path: &Path,
+        parsetree: &lore_parser::Structure,
+        namespace: &Option<URI>,
+        importing: &mut Vec<PathBuf>,
+    ) -> Result<HashSet<URI>, StoreError> {
+        let mut imported = HashSet::new();
+        for import in parsetree.imports() {
+            let (files, namespace) = self.locate(path, import, namespace)?;
+            for file in files {
+                self.imported.insert(file.clone());
+                let (source, parsetree) = match self.parse_file(&file) {
+                    Err(StoreError::FileError(error)) => {
+                        return Err(StoreError::ImportNotFound {
+                            import: file,
+                            path: path.to_path_buf(),
+                            reason: error.to_string(),
+                        })
+                    }
+                    result => result?,
+                };
+                imported.extend(self.load(
+                    file,
+                    source,
+                    parsetree,
+                    namespace.clone(),
+                    importing,
+                )?);
+            }
+        }
+        Ok(imported)
+    }
+
+    /// The files an import brings in, and the namespace to read them in.
+    fn locate(
+        &self,
+        from: &Path,
+        import: &lore_parser::Import,
+        namespace: &Option<URI>,
+    ) -> Result<(Vec<PathBuf>, Option<URI>), StoreError> {
+        match import {
+            lore_parser::Import::File(file) => {
+                let dir = from.parent().unwrap_or_else(|| Path::new(""));
+                Ok((vec![dir.join(file)], namespace.clone()))
+            }
+            lore_parser::Import::Namespace(uri) => {
+                match self
+                    .packages
+                    .iter()
+                    .find(|package| package.namespace.as_ref() == Some(uri))
+                {
+                    Some(package) => Ok((package.files(), package.namespace.clone())),
+                    None => Err(StoreError::PackageNotFound {
+                        namespace: uri.clone(),
+                        path: from.to_path_buf(),
+                    }),
+                }
+            }
+        }
+    }
+
+    fn add_validated_file(
+        &mut self,
+        path: PathBuf,
+        source: String,
+        ast: lore_ast::Structure,
+    ) -> Result<&mut Store, StoreError> {
+        self.sources.insert(path.clone(), source);
+
+        let names = ast
+            .kinds
+            .iter()
+            .map(|kind| &kind.name)
+            .chain(ast.attributes.iter().map(|attr| &attr.name))
+            .chain(ast.things.iter().map(|thing| &thing.name));
+        for name in names {
+            self.declared_in.insert(name.to_uri(), path.clone());
+        }
+
+        self.add_tree(ast)
+    }
+
     pub fn add_tree(&mut self, ast: lore_ast::Structure) -> Result<&mut Store, StoreError> {
         for attribute in ast.attributes {
+            self.declare(attribute.name.to_uri());
             self.attributes
                 .insert(attribute.name.to_uri(), attribute.clone());
 
@@ -98,6 +455,7 @@ impl Store {
         }
 
         for kind in ast.kinds {
+            self.declare(kind.name.to_uri());
             self.kinds.insert(kind.name.to_uri(), kind.clone());
 
             for q in kind.to_quads() {
@@ -106,6 +464,7 @@ impl Store {
         }
 
         for rel in ast.relations {
+            self.declare(rel.predicate.to_uri());
             match self.relations_by_subject.get_mut(&rel.subject.to_uri()) {
                 None => {
                     self.relations_by_subject
@@ -125,6 +484,18 @@ impl Store {
             }
         }
 
+        // after the relations, so values can be typed by the relations declared next to them
+        for thing in ast.things {
+            self.declare(thing.name.to_uri());
+            let quads = thing_quads(&thing, |predicate| {
+                self.relation_primitive(&thing.kind.uri, predicate)
+            });
+            for q in quads {
+                self.graph.insert(q);
+            }
+            self.things.insert(thing.name.to_uri(), thing);
+        }
+
         Ok(self)
     }
 
@@ -239,4 +610,332 @@ output:
             rel @Hero @hasOne @Name
         "#
     );
+
+    #[test]
+    fn store_primitive_range() {
+        let mut store = Store::new();
+        store
+            .add_from_string(
+                r#"
+                prefix lore:v1 as @lore
+                using dota:v2022
+                attr Name
+                rel Name asString @lore/String
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            store
+                .query("SELECT ?range WHERE { <dota:v2022/asString> rdfs:range ?range }")
+                .unwrap(),
+            vec![vec![
+                "?range: <http://www.w3.org/2001/XMLSchema#string>".to_string()
+            ]]
+        );
+        assert_eq!(
+            store.primitive(&URI::from_string("lore:v1/String".to_string())),
+            Some(Primitive::String)
+        );
+    }
+
+    test!(
+        store_thing,
+        r#"
+        prefix lore:v1 as @lore
+        using dota:v2022
+
+        kind Hero
+        thing AntiMage : Hero {
+          movementSpeed 310
+        }
+        "#
+    );
+
+    #[test]
+    fn store_query_things() {
+        let mut store = Store::new();
+        store
+            .add_from_string(
+                r#"
+                prefix lore:v1 as @lore
+                using dota:v2022
+
+                kind Hero
+                rel Hero movementSpeed @lore/Int
+
+                thing AntiMage : Hero {
+                  @lore/label/en "Anti-Mage"
+                  movementSpeed 310
+                  primaryAttribute Agility
+                }
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            store
+                .query(
+                    r#"
+                    SELECT ?thing ?speed ?label ?attr WHERE {
+                      ?thing rdf:type <dota:v2022/Hero> ;
+                             <dota:v2022/movementSpeed> ?speed ;
+                             rdfs:label ?label ;
+                             <dota:v2022/primaryAttribute> ?attr .
+                    }
+                    "#
+                )
+                .unwrap(),
+            vec![vec![
+                "?thing: <dota:v2022/AntiMage>".to_string(),
+                "?speed: \"310\"^^<http://www.w3.org/2001/XMLSchema#integer>".to_string(),
+                "?label: \"Anti-Mage\"@en".to_string(),
+                "?attr: <dota:v2022/Agility>".to_string(),
+            ]]
+        );
+    }
+
+    #[test]
+    fn store_types_thing_values() {
+        let mut store = Store::new();
+        store
+            .add_from_string(
+                r#"
+                prefix lore:v1 as @lore
+                using dota:v2022
+
+                kind Item
+                rel Item releasedAt @lore/DateTime
+                rel Item active @lore/Bool
+                rel Item weight @lore/Float
+                rel Item name @lore/String
+
+                thing Blink : Item {
+                  releasedAt "2011-01-01T00:00:00Z"
+                  active "true"
+                  weight 2
+                  name "Blink Dagger"
+                }
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            store
+                .query(
+                    r#"
+                    SELECT ?p ?type WHERE {
+                      <dota:v2022/Blink> ?p ?o .
+                      FILTER(isLiteral(?o))
+                      BIND(DATATYPE(?o) AS ?type)
+                    }
+                    ORDER BY ?p
+                    "#
+                )
+                .unwrap(),
+            vec![
+                vec![
+                    "?p: <dota:v2022/active>".to_string(),
+                    "?type: <http://www.w3.org/2001/XMLSchema#boolean>".to_string(),
+                ],
+                vec![
+                    "?p: <dota:v2022/name>".to_string(),
+                    "?type: <http://www.w3.org/2001/XMLSchema#string>".to_string(),
+                ],
+                vec![
+                    "?p: <dota:v2022/releasedAt>".to_string(),
+                    "?type: <http://www.w3.org/2001/XMLSchema#dateTime>".to_string(),
+                ],
+                vec![
+                    "?p: <dota:v2022/weight>".to_string(),
+                    "?type: <http://www.w3.org/2001/XMLSchema#double>".to_string(),
+                ],
+            ]
+        );
+        assert!(store.shapes().validate(&store.graph).is_empty());
+    }
+
+    #[test]
+    fn store_declared_in() {
+        let path = std::env::temp_dir().join("lore_store_declared_in.lore");
+        std::fs::write(
+            &path,
+            r#"
+            using dota:v2022
+            kind Hero
+            attr Name
+            rel Hero mainName Name
+            "#,
+        )
+        .unwrap();
+
+        let mut store = Store::new();
+        store.add_file(path.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for uri in ["dota:v2022/Hero", "dota:v2022/Name"] {
+            assert_eq!(
+                store.declared_in.get(&URI::from_string(uri.to_string())),
+                Some(&path)
+            );
+        }
+        assert_eq!(
+            store
+                .declared_in
+                .get(&URI::from_string("dota:v2022/mainName".to_string())),
+            None
+        );
+    }
+
+    /// Writes `files` into a fresh directory under the temp dir.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lore_store_{}_{}", name, std::process::id()));
+        for (file, src) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn store_imports() {
+        let dir = project(
+            "imports",
+            &[
+                ("base/names.lore", "using dota:v2022\nattr Name\n"),
+                (
+                    "hero.lore",
+                    "using dota:v2022\nimport \"base/names.lore\"\nkind Hero\nrel Hero mainName Name\n",
+                ),
+                (
+                    "item.lore",
+                    "using dota:v2022\nimport \"base/names.lore\"\nkind Item\nrel Item named Name\n",
+                ),
+            ],
+        );
+
+        let mut store = Store::new();
+        store.add_file(dir.join("hero.lore")).unwrap();
+        store.add_file(dir.join("item.lore")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(store
+            .attributes
+            .contains_key(&URI::from_string("dota:v2022/Name".to_string())));
+        // names.lore is imported twice but read once
+        assert_eq!(store.sources.len(), 3);
+    }
+
+    #[test]
+    fn store_imports_only_what_is_imported() {
+        let dir = project(
+            "not_imported",
+            &[
+                ("names.lore", "using dota:v2022\nattr Name\n"),
+                ("items.lore", "using dota:v2022\nkind Item\n"),
+                (
+                    "hero.lore",
+                    "using dota:v2022\nimport \"names.lore\"\nkind Hero\nrel Hero hasItem Item\n",
+                ),
+            ],
+        );
+
+        let mut store = Store::new();
+        store.add_file(dir.join("items.lore")).unwrap();
+        let error = store.add_file(dir.join("hero.lore")).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match error {
+            StoreError::ValidationError(error) => assert!(matches!(
+                error.errors(),
+                [lore_parser::SemanticError::NotImported(_)]
+            )),
+            error => panic!("expected a validation error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn store_checks_files_without_imports() {
+        let dir = project(
+            "no_imports",
+            &[
+                ("items.lore", "using dota:v2022\nkind Item\n"),
+                (
+                    "hero.lore",
+                    "using dota:v2022\nkind Hero\nrel Hero hasItem Item\n",
+                ),
+            ],
+        );
+
+        let mut store = Store::new();
+        store.add_file(dir.join("items.lore")).unwrap();
+        let error = store.add_file(dir.join("hero.lore")).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match error {
+            StoreError::ValidationError(error) => assert!(matches!(
+                error.errors(),
+                [lore_parser::SemanticError::NotImported(_)]
+            )),
+            error => panic!("expected a validation error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn store_import_cycle() {
+        let dir = project(
+            "import_cycle",
+            &[
+                ("a.lore", "using dota:v2022\nimport \"b.lore\"\nkind A\n"),
+                ("b.lore", "using dota:v2022\nimport \"a.lore\"\nkind B\n"),
+            ],
+        );
+
+        let mut store = Store::new();
+        let error = store.add_file(dir.join("a.lore")).unwrap_err();
+        let dir = dir.canonicalize().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match error {
+            StoreError::ImportCycle { cycle } => assert_eq!(
+                cycle,
+                format!(
+                    "{a} -> {b} -> {a}",
+                    a = dir.join("a.lore").display(),
+                    b = dir.join("b.lore").display()
+                )
+            ),
+            error => panic!("expected an import cycle, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn store_imports_packages() {
+        let dir = project(
+            "packages",
+            &[
+                (
+                    "lore_packages/names/lore.toml",
+                    "[project]\nnamespace = \"names:v1\"\n",
+                ),
+                ("lore_packages/names/name.lore", "attr Name\n"),
+                (
+                    "hero.lore",
+                    "using dota:v2022\nprefix names:v1 as @names\nimport names:v1\nkind Hero\nrel Hero mainName @names/Name\n",
+                ),
+            ],
+        );
+
+        let manifest = Manifest::from_toml(&dir, "").unwrap();
+        let mut store = Store::new().with_packages(manifest.packages().unwrap());
+        store.add_file(dir.join("hero.lore")).unwrap();
+        let missing = Store::new().add_file(dir.join("hero.lore")).err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(store
+            .attributes
+            .contains_key(&URI::from_string("names:v1/Name".to_string())));
+        assert!(matches!(missing, Some(StoreError::PackageNotFound { .. })));
+    }
 }
@@ -0,0 +1,367 @@
+use crate::store::*;
+use lore_ast::*;
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// A thing that does not match what the ontology says about its kind.
+#[derive(Diagnostic, Error, Debug)]
+#[error("{message}")]
+#[diagnostic(code(lore::store::thing))]
+pub struct ThingError {
+    #[source_code]
+    src: NamedSource,
+
+    #[label("{label}")]
+    span: SourceSpan,
+
+    label: String,
+
+    pub message: String,
+
+    /// The file the thing was declared in.
+    pub file: PathBuf,
+}
+
+impl Store {
+    /// A kind followed by all of its superkinds, each of them once.
+    pub fn ancestry(&self, kind: &URI) -> Vec<URI> {
+        let mut ancestry = vec![];
+        let mut pending = vec![kind.clone()];
+        while let Some(kind) = pending.pop() {
+            if ancestry.contains(&kind) {
+                continue;
+            }
+            if let Some(declared) = self.kinds.get(&kind) {
+                for superkind in declared.superkinds().iter().rev() {
+                    pending.push(superkind.to_uri());
+                }
+            }
+            ancestry.push(kind);
+        }
+        ancestry
+    }
+
+    /// The primitive that the relation `predicate` of a kind, or of one of its superkinds, points
+    /// to.
+    pub fn relation_primitive(&self, kind: &URI, predicate: &URI) -> Option<Primitive> {
+        self.ancestry(kind)
+            .iter()
+            .filter_map(|kind| self.relations_by_subject.get(kind))
+            .flatten()
+            .find(|rel| &rel.predicate.uri == predicate)
+            .and_then(|rel| self.primitive(&rel.object.uri))
+    }
+
+    /// Checks every thing against the relations of its kind and superkinds: the kind must be
+    /// declared, every field must be one of those relations with a value of the right type, and
+    /// every relation must have as many values as its cardinality asks for. Labels and docs are
+    /// always allowed.
+    ///
+    /// Things may use kinds from other files, so this runs once everything has been added.
+    pub fn check_things(&self) -> Result<(), StoreError> {
+        let mut errors = vec![];
+        for thing in self.things() {
+            self.check_thing(thing, &mut errors);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(StoreError::InvalidThings { errors })
+        }
+    }
+
+    fn check_thing(&self, thing: &Thing, errors: &mut Vec<ThingError>) {
+        let error = |span: Span, label: &str, message: String| {
+            let file = thing.spans.file.display().to_string();
+            let source = self.sources.get(&thing.spans.file).cloned();
+            ThingError {
+                src: NamedSource::new(file, source.unwrap_or_default()),
+                span: (span.offset, span.length).into(),
+                label: label.to_string(),
+                message,
+                file: thing.spans.file.clone(),
+            }
+        };
+
+        if !self.kinds.contains_key(&thing.kind.uri) {
+            errors.push(error(
+                thing.spans.kind,
+                "not a kind",
+                format!(
+                    "`{}` is declared as a `{}`, but there is no such kind.",
+                    thing.name, thing.kind
+                ),
+            ));
+            return;
+        }
+
+        let mut relations: Vec<&Relation> = vec![];
+        for kind in self.ancestry(&thing.kind.uri) {
+            if let Some(rels) = self.relations_by_subject.get(&kind) {
+                relations.extend(rels);
+            }
+        }
+
+        let mut counts: HashMap<&URI, Vec<Span>> = HashMap::new();
+        for (i, field) in thing.fields.iter().enumerate() {
+            let span = thing.spans.fields.get(i).copied().unwrap_or_default();
+            if Localized::all()
+                .iter()
+                .any(|localized| localized.language_of(field).is_some())
+            {
+                continue;
+            }
+
+            let rel = match relations
+                .iter()
+                .find(|rel| rel.predicate.uri == field.name.uri)
+            {
+                Some(rel) => rel,
+                None => {
+                    errors.push(error(
+                        span,
+                        "unknown relation",
+                        format!(
+                            "`{}` is not a relation of `{}` or any of its superkinds.",
+                            field.name, thing.kind
+                        ),
+                    ));
+                    continue;
+                }
+            };
+            counts.entry(&rel.predicate.uri).or_default().push(span);
+
+            if let Some(problem) = self.check_value(&rel.object, &field.value) {
+                errors.push(error(
+                    span,
+                    "wrong value",
+                    format!(
+                        "`{}` of `{}` expects {}, but found `{}`.",
+                        rel.predicate, thing.name, problem, field.value
+                    ),
+                ));
+            }
+        }
+
+        for rel in relations {
+            let cardinality = rel.cardinality();
+            let spans = counts.get(&rel.predicate.uri).cloned().unwrap_or_default();
+            let count = spans.len() as u64;
+            if count < cardinality.min {
+                errors.push(error(
+                    thing.spans.name,
+                    "missing values",
+                    format!(
+                        "`{}` needs at least {} `{}`, but has {}.",
+                        thing.name, cardinality.min, rel.predicate, count
+                    ),
+                ));
+            }
+            if let Some(max) = cardinality.max {
+                if count > max {
+                    errors.push(error(
+                        spans[max as usize],
+                        "one too many",
+                        format!(
+                            "`{}` can have at most {} `{}`, but has {}.",
+                            thing.name, max, rel.predicate, count
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// What a value should have been, if it is not a valid object of a relation.
+    fn check_value(&self, object: &Name, value: &Literal) -> Option<String> {
+        if let Some(primitive) = self.primitive(&object.uri) {
+            let valid = match (primitive, value) {
+                (Primitive::Int, Literal::Number(_)) => true,
+                (Primitive::Float, Literal::Number(_)) => true,
+                (Primitive::Float, Literal::Float(_)) => true,
+                (Primitive::Bool, Literal::String(s)) => s == "true" || s == "false",
+                (Primitive::String, Literal::String(_)) => true,
+                (Primitive::Date, Literal::String(_)) => true,
+                (Primitive::DateTime, Literal::String(_)) => true,
+                (Primitive::URI, Literal::String(_)) => true,
+                (Primitive::URI, Literal::Name(_)) => true,
+                _ => false,
+            };
+            return match (valid, primitive) {
+                (true, _) => None,
+                (false, Primitive::Bool) => Some("`\"true\"` or `\"false\"`".to_string()),
+                (false, primitive) => Some(format!("a value of type `{}`", primitive.name())),
+            };
+        }
+
+        // attributes and undeclared objects say nothing about their values
+        let kind = self.kinds.get(&object.uri)?;
+
+        let name = match value {
+            Literal::Name(name) => name,
+            _ => return Some(format!("a `{}`", object)),
+        };
+
+        if kind.is_enumeration() {
+            if kind.variants().iter().any(|v| v.uri == name.uri) {
+                return None;
+            }
+            let variants: Vec<String> =
+                kind.variants().iter().map(|v| format!("`{}`", v)).collect();
+            return Some(format!("one of {}", variants.join(", ")));
+        }
+
+        match self.things.get(&name.uri) {
+            Some(other) if self.ancestry(&other.kind.uri).contains(&object.uri) => None,
+            _ => Some(format!("a `{}`", object)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEROES: &str = r#"
+prefix lore:v1 as @lore
+using dota:v2022
+
+kind Unit
+kind Hero {
+  @lore/superkind Unit
+}
+kind Ability
+
+kind PrimaryAttribute {
+  @lore/variant Strength
+  @lore/variant Agility
+}
+
+rel Unit movementSpeed @lore/Int
+rel Unit turnRate @lore/Float {
+  @lore/cardinality/max 1
+}
+rel Hero primaryAttribute PrimaryAttribute
+rel Hero hasAbility Ability {
+  @lore/cardinality/min 1
+  @lore/cardinality/max 2
+}
+"#;
+
+    fn errors(things: &str) -> Vec<String> {
+        let mut store = Store::new();
+        store
+            .add_from_string(&format!("{}\n{}", HEROES, things))
+            .unwrap();
+        match store.check_things() {
+            Ok(()) => vec![],
+            Err(StoreError::InvalidThings { errors }) => {
+                errors.iter().map(|e| e.message.clone()).collect()
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn ancestry() {
+        let mut store = Store::new();
+        store.add_from_string(HEROES).unwrap();
+        assert_eq!(
+            store.ancestry(&URI::from_string("dota:v2022/Hero".to_string())),
+            vec![
+                URI::from_string("dota:v2022/Hero".to_string()),
+                URI::from_string("dota:v2022/Unit".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn check_valid_things() {
+        assert_eq!(
+            errors(
+                r#"
+thing ManaBreak : Ability
+thing AntiMage : Hero {
+  @lore/label/en "Anti-Mage"
+  movementSpeed 310
+  primaryAttribute Agility
+  hasAbility ManaBreak
+}
+"#
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn check_invalid_things() {
+        assert_eq!(
+            errors(
+                r#"
+thing Roshan : Boss
+thing Blink : Item
+thing ManaBreak : Ability
+thing AntiMage : Hero {
+  movementSpeed "fast"
+  primaryAttribute Charisma
+  hasAbility Blink
+  hasAbility ManaBreak
+  hasAbility ManaBreak
+  attackRange 150
+}
+thing Pudge : Hero {
+  movementSpeed 280
+  primaryAttribute Strength
+}
+"#
+            ),
+            vec![
+                "`dota:v2022/movementSpeed` of `dota:v2022/AntiMage` expects a value of type `Int`, but found `fast`."
+                    .to_string(),
+                "`dota:v2022/primaryAttribute` of `dota:v2022/AntiMage` expects one of `dota:v2022/Strength`, `dota:v2022/Agility`, but found `dota:v2022/Charisma`."
+                    .to_string(),
+                "`dota:v2022/hasAbility` of `dota:v2022/AntiMage` expects a `dota:v2022/Ability`, but found `dota:v2022/Blink`."
+                    .to_string(),
+                "`dota:v2022/attackRange` is not a relation of `dota:v2022/Hero` or any of its superkinds."
+                    .to_string(),
+                "`dota:v2022/AntiMage` can have at most 2 `dota:v2022/hasAbility`, but has 3."
+                    .to_string(),
+                "`dota:v2022/Blink` is declared as a `dota:v2022/Item`, but there is no such kind."
+                    .to_string(),
+                "`dota:v2022/Pudge` needs at least 1 `dota:v2022/hasAbility`, but has 0."
+                    .to_string(),
+                "`dota:v2022/Roshan` is declared as a `dota:v2022/Boss`, but there is no such kind."
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_float_values() {
+        assert_eq!(
+            errors(
+                r#"
+thing ManaBreak : Ability
+thing AntiMage : Hero {
+  movementSpeed 310.5
+  turnRate 0.5
+  primaryAttribute Agility
+  hasAbility ManaBreak
+}
+thing Pudge : Hero {
+  movementSpeed 280
+  turnRate 1
+  primaryAttribute Strength
+  hasAbility ManaBreak
+}
+"#
+            ),
+            vec![
+                "`dota:v2022/movementSpeed` of `dota:v2022/AntiMage` expects a value of type `Int`, but found `310.5`."
+                    .to_string(),
+            ]
+        );
+    }
+}